@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use azurge_core::{
+    app::Plugin,
+    asset::prefab::PrefabOverrides,
+    ecs::{
+        components::{Mesh, Name},
+        EventReader, Events, IntoSystemConfig, Stage, World,
+    },
+    input::{
+        action::{ActionKind, Binding},
+        context::{Consume, InputContext},
+        interaction::Interaction,
+        mouse::CursorMode,
+        InputSystem, KeyPressed,
+    },
+    render::{
+        material::{BlendMode, Material},
+        particle::{Curve, EmitterShape, ParticleEffect, ParticleEmitter},
+        sprite::{atlas::TextureAtlasBuilder, Sprite},
+        transform::Transform,
+    },
+    time::Time,
+    Core,
+};
+use winit::keyboard::KeyCode;
+
+/// Loads `assets/demo.ron`, lines up instances of `assets/pillar.ron`, adds two sprites and a particle fountain, and spins the pivot.
+///
+/// The backquote key opens a console that echoes the lines typed in it, Escape closes it.
+/// The console's input context keeps the camera still meanwhile. Double tapping W dashes.
+pub struct DemoScenePlugin;
+
+impl Plugin for DemoScenePlugin {
+    fn build(&self, core: &mut Core) {
+        core.load_scene(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/demo.ron"))
+            .expect("load demo scene error");
+        spawn_pillars(core);
+
+        {
+            let mut input_system = core.world().resource_mut::<InputSystem>();
+            input_system.add_context("console", InputContext::new(Consume::All));
+            // Not a conflict with moving on W, the interactions differ
+            input_system.add_default_action(
+                "dash",
+                ActionKind::Button,
+                [Binding::key(KeyCode::KeyW)
+                    .with_interaction(Interaction::DoubleTap(Duration::from_millis(250)))],
+            );
+        }
+        // Remapped controls, e.g. saved from `InputSystem::save_bindings`
+        let bindings = concat!(env!("CARGO_MANIFEST_DIR"), "/bindings.ron");
+        if std::path::Path::new(bindings).exists() {
+            core.world()
+                .resource_mut::<InputSystem>()
+                .load_bindings(bindings)
+                .expect("load bindings error");
+        }
+        add_sprites_and_particles(core);
+        core.add_system(
+            Stage::Update,
+            spin_pivot
+                .with_name("spin_pivot")
+                .reads::<Time>()
+                .queries::<(&Name, &mut Transform)>(),
+        );
+        core.add_system(
+            Stage::PreRender,
+            (|world: &World| {
+                if world
+                    .resource::<InputSystem>()
+                    .action("dash")
+                    .just_pressed()
+                {
+                    println!("[app/demo]: dash");
+                }
+            })
+            .with_name("dash")
+            .after("console")
+            .before("lock_cursor")
+            .reads::<InputSystem>(),
+        );
+
+        let mut pressed = EventReader::<KeyPressed>::default();
+        core.add_system(
+            Stage::PreRender,
+            (move |world: &World| {
+                let events = world.resource::<Events<KeyPressed>>();
+                let mut input_system = world.resource_mut::<InputSystem>();
+                for event in pressed.read(&events) {
+                    match event.key {
+                        KeyCode::Backquote if !input_system.text_input().is_focused() => {
+                            input_system.set_cursor_mode(CursorMode::Free);
+                            input_system.text_input_mut().clear();
+                            input_system.start_text_input();
+                            input_system.push_context("console");
+                        }
+                        KeyCode::Escape => {
+                            input_system.stop_text_input();
+                            input_system.remove_context_from_stack("console");
+                        }
+                        _ => (),
+                    }
+                }
+                while let Some(line) = input_system.text_input_mut().take_submitted() {
+                    println!("[app/console]: {line}");
+                }
+            })
+            .with_name("console")
+            .before("lock_cursor")
+            .reads::<Events<KeyPressed>>()
+            .writes::<InputSystem>(),
+        );
+    }
+}
+
+fn spawn_pillars(core: &mut Core) {
+    core.load_prefab(
+        "pillar",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/assets/pillar.ron"),
+    )
+    .expect("load pillar prefab error");
+    for (i, x) in [-6.0, -3.0, 0.0].into_iter().enumerate() {
+        let mut overrides = PrefabOverrides::new()
+            .with_transform(0, Transform::from_translation([x, -1.0, 6.0].into()));
+        if i == 1 {
+            overrides = overrides.with_mesh(
+                1,
+                Mesh {
+                    path: "cube".to_string(),
+                    material: Material::new([0.4, 0.8, 1.0, 0.5].into(), BlendMode::AlphaBlend),
+                },
+            );
+        }
+        core.instantiate_prefab("pillar", &overrides)
+            .expect("instantiate pillar error");
+    }
+}
+
+fn add_sprites_and_particles(core: &mut Core) {
+    let renderer = core.renderer_mut();
+    let mut atlas = TextureAtlasBuilder::new(64);
+    atlas.add_image("checker", 8, 8, checker_texels(8, 8, [255, 255, 255, 255]));
+    atlas.add_image("stripes", 8, 4, checker_texels(8, 4, [255, 128, 0, 255]));
+    let atlas = atlas.build();
+    let atlas_texture = renderer
+        .resource_mut()
+        .load_texture_atlas("demo_atlas", &atlas);
+
+    let scene = renderer.scene_mut();
+    scene.add_sprite(
+        Sprite::new(atlas_texture.clone())
+            .with_position([0.0, 3.0].into())
+            .with_uv_rect(atlas.uv_rect("checker").unwrap()),
+    );
+    scene.add_sprite(
+        Sprite::new(atlas_texture)
+            .with_position([0.5, 3.5].into())
+            .with_rotation(0.5)
+            .with_size([1.0, 0.5].into())
+            .with_tint([1.0, 1.0, 1.0, 0.5].into())
+            .with_uv_rect(atlas.uv_rect("stripes").unwrap())
+            .with_layer(1),
+    );
+
+    scene.add_particle_effect(ParticleEffect::new(ParticleEmitter {
+        position: [0.0, 1.5, 0.0].into(),
+        shape: EmitterShape::Cone {
+            angle: 0.3,
+            radius: 0.2,
+        },
+        rate: 200.0,
+        velocity: [0.0, 2.0, 0.0].into(),
+        gravity: [0.0, -1.0, 0.0].into(),
+        color_over_life: Curve::linear([1.0, 0.8, 0.2, 1.0].into(), [1.0, 0.1, 0.0, 0.0].into()),
+        size_over_life: Curve::linear(0.1, 0.02),
+        ..Default::default()
+    }));
+}
+
+fn spin_pivot(world: &World) {
+    let delta = world.resource::<Time>().delta_secs();
+    world
+        .query::<(&Name, &mut Transform)>()
+        .for_each(|_, (name, transform)| {
+            if name.0 == "pivot" {
+                transform.rotate(glam::Quat::from_rotation_z(delta));
+            }
+        });
+}
+
+fn checker_texels(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+    (0..width * height)
+        .flat_map(|id| {
+            if (id % width + id / width).is_multiple_of(2) {
+                color
+            } else {
+                [0, 0, 0, 255]
+            }
+        })
+        .collect()
+}
@@ -1,72 +1,25 @@
-use std::sync::Arc;
-use std::time::Instant;
+use azurge_core::{
+    app::{App, DefaultPlugins},
+    input::record::InputRecording,
+};
+use demo::DemoScenePlugin;
 
-use azurge_core::Core;
+mod demo;
 
-use winit::application::ApplicationHandler;
-use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::window::{Window, WindowId};
-
-#[derive(Default)]
-struct App {
-    window: Option<Arc<Window>>,
-    core: Option<Core>,
-    last_tick: Option<Instant>,
-}
-
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window = event_loop
-            .create_window(Window::default_attributes())
-            .unwrap();
-        let window = Arc::new(window);
-
-        let core = Core::new(window.clone());
-        // window.request_redraw();
-
-        self.window = Some(window);
-        self.core = Some(core)
-    }
-
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
-        match event {
-            WindowEvent::CloseRequested => {
-                println!("The close button was pressed; stopping");
-                event_loop.exit();
-            }
-            WindowEvent::RedrawRequested => {
-                let time_delta = self.last_tick.unwrap_or(Instant::now()).elapsed();
-                self.last_tick = Some(Instant::now());
-                self.core.as_mut().unwrap().tick(time_delta);
-                self.core.as_ref().unwrap().render();
-            }
-            WindowEvent::Resized(size) => {
-                self.core.as_mut().unwrap().handle_resize(size);
-                // self.window.as_ref().unwrap().request_redraw();
-            }
-            WindowEvent::KeyboardInput { .. }
-            | WindowEvent::CursorMoved { .. }
-            | WindowEvent::MouseInput { .. } => {
-                self.core.as_mut().unwrap().handle_input_event(event);
-                // self.window.as_ref().unwrap().request_redraw();
-            }
-            _ => (),
+/// `--record <path>` saves the session's inputs, `--replay <path>` plays them back
+fn main() {
+    let mut app = App::new()
+        .add_plugin(DefaultPlugins)
+        .add_plugin(DemoScenePlugin);
+
+    let args = std::env::args().collect::<Vec<_>>();
+    match args.get(1..3) {
+        Some([flag, path]) if flag == "--record" => app = app.with_recording(path),
+        Some([flag, path]) if flag == "--replay" => {
+            let recording = InputRecording::load(path).expect("load recording error");
+            app = app.with_replay(recording);
         }
+        _ => (),
     }
-
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        self.window.as_ref().unwrap().request_redraw();
-    }
-}
-
-fn main() {
-    let event_loop = EventLoop::new().unwrap();
-
-    // ControlFlow::Poll continuously runs the event loop, even if the OS hasn't
-    // dispatched any events. This is ideal for games and similar applications.
-    event_loop.set_control_flow(ControlFlow::Poll);
-
-    let mut app = App::default();
-    event_loop.run_app(&mut app).expect("run app error");
+    app.run().expect("run app error");
 }
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use winit::{error::EventLoopError, window::WindowAttributes};
+
+use crate::{
+    input::{record::InputRecording, InputPlugin},
+    render::{camera::FlyCameraPlugin, RenderPlugin},
+    runtime::{FixedTimestep, Runtime},
+    Core,
+};
+
+/// A piece of engine or game functionality, set up once the [`Core`] exists:
+/// registers pipelines, resources, systems, input bindings and entities.
+pub trait Plugin: 'static {
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn build(&self, core: &mut Core);
+}
+
+/// The plugins of the engine itself: rendering, input and a fly camera
+pub struct DefaultPlugins;
+
+impl Plugin for DefaultPlugins {
+    fn build(&self, core: &mut Core) {
+        RenderPlugin.build(core);
+        InputPlugin.build(core);
+        FlyCameraPlugin.build(core);
+    }
+}
+
+/// Collects plugins, then hands them to a [`Runtime`], as in
+/// `App::new().add_plugin(DefaultPlugins).run()`
+#[derive(Default)]
+pub struct App {
+    runtime: Runtime,
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plugins are built in the order they are added
+    pub fn add_plugin(mut self, plugin: impl Plugin) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    pub fn with_window_attributes(mut self, window_attributes: WindowAttributes) -> Self {
+        self.runtime = self.runtime.with_window_attributes(window_attributes);
+        self
+    }
+
+    pub fn with_timestep(mut self, timestep: FixedTimestep) -> Self {
+        self.runtime = self.runtime.with_timestep(timestep);
+        self
+    }
+
+    /// See [`Runtime::with_recording`]
+    pub fn with_recording(mut self, path: impl Into<PathBuf>) -> Self {
+        self.runtime = self.runtime.with_recording(path);
+        self
+    }
+
+    /// See [`Runtime::with_replay`]
+    pub fn with_replay(mut self, recording: InputRecording) -> Self {
+        self.runtime = self.runtime.with_replay(recording);
+        self
+    }
+
+    pub fn run(self) -> Result<(), EventLoopError> {
+        let plugins = self.plugins;
+        self.runtime
+            .with_setup(move |core| {
+                for plugin in plugins {
+                    println!("[core/app]: building plugin {}", plugin.name());
+                    plugin.build(core);
+                }
+            })
+            .run()
+    }
+}
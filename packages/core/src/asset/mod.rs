@@ -0,0 +1,107 @@
+pub mod prefab;
+pub mod scene;
+
+use std::{fmt, io, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::ecs::Entity;
+
+/// Why an asset couldn't be loaded, saved or spawned
+#[derive(Debug)]
+pub enum AssetError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// Written by a newer version of the engine
+    UnsupportedVersion(u32),
+    /// A mesh that isn't loaded in [`Resource`]
+    ///
+    /// [`Resource`]: crate::render::resource::Resource
+    MissingMesh(String),
+    MissingPrefab(String),
+    /// A prefab without nodes can't be instantiated
+    EmptyPrefab(String),
+    /// A node whose parent index doesn't point to an earlier node
+    InvalidParent {
+        node: usize,
+        parent: usize,
+    },
+    /// An entity that is its own ancestor through [`Parent`]
+    ///
+    /// [`Parent`]: crate::ecs::components::Parent
+    ParentCycle(Entity),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(err) => write!(f, "io error: {err}"),
+            AssetError::Parse(err) => write!(f, "parse error: {err}"),
+            AssetError::Serialize(err) => write!(f, "serialize error: {err}"),
+            AssetError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {version}")
+            }
+            AssetError::MissingMesh(path) => write!(f, "no mesh loaded as {path:?}"),
+            AssetError::MissingPrefab(name) => write!(f, "no prefab registered as {name:?}"),
+            AssetError::EmptyPrefab(name) => write!(f, "prefab {name:?} has no nodes"),
+            AssetError::InvalidParent { node, parent } => {
+                write!(
+                    f,
+                    "node {node} has parent {parent}, which isn't an earlier node"
+                )
+            }
+            AssetError::ParentCycle(entity) => {
+                write!(f, "entity {entity:?} is its own ancestor")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+impl From<io::Error> for AssetError {
+    fn from(err: io::Error) -> Self {
+        AssetError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for AssetError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        AssetError::Parse(err)
+    }
+}
+
+impl From<ron::Error> for AssetError {
+    fn from(err: ron::Error) -> Self {
+        AssetError::Serialize(err)
+    }
+}
+
+/// `Option` fields can be written without `Some(..)`
+const RON_EXTENSIONS: ron::extensions::Extensions = ron::extensions::Extensions::IMPLICIT_SOME;
+
+fn ron_options() -> ron::Options {
+    ron::Options::default().with_default_extension(RON_EXTENSIONS)
+}
+
+pub(crate) fn from_ron<T: DeserializeOwned>(ron: &str) -> Result<T, AssetError> {
+    Ok(ron_options().from_str(ron)?)
+}
+
+/// Writes the extensions in a header, for other RON readers
+pub(crate) fn to_ron<T: Serialize>(value: &T) -> Result<String, AssetError> {
+    Ok(ron::Options::default().to_string_pretty(
+        value,
+        ron::ser::PrettyConfig::new().extensions(RON_EXTENSIONS),
+    )?)
+}
+
+pub(crate) fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, AssetError> {
+    from_ron(&std::fs::read_to_string(path)?)
+}
+
+pub(crate) fn save_ron<T: Serialize>(value: &T, path: impl AsRef<Path>) -> Result<(), AssetError> {
+    std::fs::write(path, to_ron(value)?)?;
+    Ok(())
+}
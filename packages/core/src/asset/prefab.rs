@@ -0,0 +1,510 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ecs::{
+        components::{Mesh, Name, Parent, Renderable},
+        Component, Entity, World,
+    },
+    render::{light::Light, resource::Resource, transform::Transform},
+};
+
+use super::{
+    scene::{validate_parents, NodeAsset},
+    AssetError,
+};
+
+/// The version [`PrefabAsset`]s are saved with
+pub const PREFAB_VERSION: u32 = 1;
+
+/// A reusable subtree of nodes, saved as RON.
+///
+/// The first node is the root of each instance, the other nodes without a
+/// parent are attached to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrefabAsset {
+    pub version: u32,
+    pub nodes: Vec<NodeAsset>,
+}
+
+impl Default for PrefabAsset {
+    fn default() -> Self {
+        Self {
+            version: PREFAB_VERSION,
+            nodes: Vec::new(),
+        }
+    }
+}
+
+impl PrefabAsset {
+    pub fn new(nodes: Vec<NodeAsset>) -> Self {
+        Self {
+            version: PREFAB_VERSION,
+            nodes,
+        }
+    }
+
+    pub fn from_ron(ron: &str) -> Result<Self, AssetError> {
+        let prefab: PrefabAsset = super::from_ron(ron)?;
+        prefab.migrate()
+    }
+
+    pub fn to_ron(&self) -> Result<String, AssetError> {
+        super::to_ron(self)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        let prefab: PrefabAsset = super::load_ron(path)?;
+        prefab.migrate()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        super::save_ron(self, path)
+    }
+
+    fn migrate(self) -> Result<Self, AssetError> {
+        match self.version {
+            PREFAB_VERSION => Ok(self),
+            version => Err(AssetError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// The parent of each node within an instance
+    fn parent(&self, node: usize) -> Option<usize> {
+        match node {
+            0 => None,
+            _ => Some(self.nodes[node].parent.unwrap_or(0)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), AssetError> {
+        validate_parents(&self.nodes)?;
+        match self.nodes.first() {
+            Some(root) if root.parent.is_some() => Err(AssetError::InvalidParent {
+                node: 0,
+                parent: root.parent.unwrap(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Marks an entity spawned as the `node`th node of an instance of `prefab`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefabInstance {
+    pub prefab: String,
+    pub node: usize,
+    /// The entity of the instance's first node
+    pub root: Entity,
+}
+
+/// Values of a node that differ from the prefab in one instance
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeOverride {
+    pub name: Option<String>,
+    pub transform: Option<Transform>,
+    pub mesh: Option<Mesh>,
+    pub light: Option<Light>,
+}
+
+/// Per-instance overrides, by node index, `0` being the root
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrefabOverrides {
+    nodes: HashMap<usize, NodeOverride>,
+}
+
+impl PrefabOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_name(mut self, node: usize, name: impl Into<String>) -> Self {
+        self.nodes.entry(node).or_default().name = Some(name.into());
+        self
+    }
+
+    pub fn with_transform(mut self, node: usize, transform: Transform) -> Self {
+        self.nodes.entry(node).or_default().transform = Some(transform);
+        self
+    }
+
+    pub fn with_mesh(mut self, node: usize, mesh: Mesh) -> Self {
+        self.nodes.entry(node).or_default().mesh = Some(mesh);
+        self
+    }
+
+    pub fn with_light(mut self, node: usize, light: Light) -> Self {
+        self.nodes.entry(node).or_default().light = Some(light);
+        self
+    }
+
+    /// The node with the overridden values applied
+    fn apply(&self, node: usize, asset: &NodeAsset) -> NodeAsset {
+        let Some(node) = self.nodes.get(&node) else {
+            return asset.clone();
+        };
+        NodeAsset {
+            name: node.name.clone().or_else(|| asset.name.clone()),
+            transform: node.transform.unwrap_or(asset.transform),
+            parent: asset.parent,
+            mesh: node.mesh.clone().or_else(|| asset.mesh.clone()),
+            light: node.light.or(asset.light),
+        }
+    }
+}
+
+/// The registered prefabs, a resource.
+///
+/// A property of an instance counts as overridden while it differs from the
+/// prefab, whether it was set by [`PrefabOverrides`] or changed afterwards:
+/// an instance moved by gameplay keeps its transform when the prefab is
+/// updated. [`Prefabs::update`] propagates the edited properties to the others.
+#[derive(Debug, Clone, Default)]
+pub struct Prefabs {
+    prefabs: HashMap<String, PrefabAsset>,
+}
+
+impl Prefabs {
+    pub fn get(&self, name: &str) -> Option<&PrefabAsset> {
+        self.prefabs.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    /// Spawns an instance of the prefab registered as `name`, returns its root
+    pub fn instantiate(
+        world: &mut World,
+        resource: &Resource,
+        name: &str,
+        overrides: &PrefabOverrides,
+    ) -> Result<Entity, AssetError> {
+        Self::instantiate_with(world, name, overrides, |node| node.renderable(resource))
+    }
+
+    fn instantiate_with(
+        world: &mut World,
+        name: &str,
+        overrides: &PrefabOverrides,
+        renderable: impl Fn(&NodeAsset) -> Result<Option<Renderable>, AssetError>,
+    ) -> Result<Entity, AssetError> {
+        let prefab = world
+            .resource::<Prefabs>()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| AssetError::MissingPrefab(name.to_string()))?;
+        if prefab.nodes.is_empty() {
+            return Err(AssetError::EmptyPrefab(name.to_string()));
+        }
+        let nodes = (0..prefab.nodes.len())
+            .map(|i| overrides.apply(i, &prefab.nodes[i]))
+            .collect::<Vec<_>>();
+        let renderables = nodes
+            .iter()
+            .map(renderable)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut entities: Vec<Entity> = Vec::with_capacity(nodes.len());
+        for (i, (node, renderable)) in nodes.iter().zip(renderables).enumerate() {
+            let parent = prefab.parent(i).map(|parent| entities[parent]);
+            let entity = node.spawn(world, parent, renderable);
+            world.insert(
+                entity,
+                PrefabInstance {
+                    prefab: name.to_string(),
+                    node: i,
+                    root: *entities.first().unwrap_or(&entity),
+                },
+            );
+            entities.push(entity);
+        }
+        Ok(entities[0])
+    }
+
+    /// Registers `prefab` as `name`, replacing the previous version, and updates
+    /// the instances. Nodes are matched by index: the instances gain the nodes
+    /// added at the end, lose the ones removed from it and are reparented to
+    /// follow the changed parents.
+    pub fn update(
+        world: &mut World,
+        resource: &Resource,
+        name: &str,
+        prefab: PrefabAsset,
+    ) -> Result<(), AssetError> {
+        Self::update_with(world, name, prefab, |node| node.renderable(resource))
+    }
+
+    fn update_with(
+        world: &mut World,
+        name: &str,
+        prefab: PrefabAsset,
+        renderable: impl Fn(&NodeAsset) -> Result<Option<Renderable>, AssetError>,
+    ) -> Result<(), AssetError> {
+        prefab.validate()?;
+        let renderables = prefab
+            .nodes
+            .iter()
+            .map(renderable)
+            .collect::<Result<Vec<_>, _>>()?;
+        let Some(old) = world
+            .resource_mut::<Prefabs>()
+            .prefabs
+            .insert(name.to_string(), prefab.clone())
+        else {
+            return Ok(());
+        };
+
+        let mut instances: HashMap<Entity, Vec<Option<Entity>>> = HashMap::new();
+        world
+            .query::<&PrefabInstance>()
+            .for_each(|entity, instance| {
+                if instance.prefab == name {
+                    let nodes = instances.entry(instance.root).or_default();
+                    if nodes.len() <= instance.node {
+                        nodes.resize(instance.node + 1, None);
+                    }
+                    nodes[instance.node] = Some(entity);
+                }
+            });
+
+        for nodes in instances.values_mut() {
+            nodes.resize(nodes.len().max(prefab.nodes.len()), None);
+            for i in 0..nodes.len() {
+                match (nodes[i], prefab.nodes.get(i)) {
+                    (Some(entity), None) => {
+                        world.despawn(entity);
+                    }
+                    // Instances only have the nodes of the previous version
+                    (Some(entity), Some(node)) => {
+                        propagate(world, entity, &old.nodes[i], node, &renderables[i]);
+                        if prefab.parent(i) != old.parent(i) {
+                            let parent = prefab.parent(i).and_then(|parent| nodes[parent]);
+                            set_option(world, entity, parent.map(Parent));
+                        }
+                    }
+                    // Added to the prefab, or despawned from this instance
+                    (None, Some(node)) if i >= old.nodes.len() => {
+                        let parent = prefab.parent(i).and_then(|parent| nodes[parent]);
+                        let entity = node.spawn(world, parent, renderables[i].clone());
+                        let root = nodes[0].unwrap_or(entity);
+                        world.insert(
+                            entity,
+                            PrefabInstance {
+                                prefab: name.to_string(),
+                                node: i,
+                                root,
+                            },
+                        );
+                        nodes[i] = Some(entity);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Updates the properties of `entity` that still match `old`
+fn propagate(
+    world: &mut World,
+    entity: Entity,
+    old: &NodeAsset,
+    new: &NodeAsset,
+    renderable: &Option<Renderable>,
+) {
+    if world.get::<Name>(entity).map(|name| name.0.clone()) == old.name {
+        set_option(world, entity, new.name.clone().map(Name));
+    }
+    if world.get::<Transform>(entity).map(|transform| *transform) == Some(old.transform) {
+        world.insert(entity, new.transform);
+    }
+    if world.get::<Mesh>(entity).map(|mesh| mesh.clone()) == old.mesh {
+        set_option(world, entity, new.mesh.clone());
+        set_option(world, entity, renderable.clone());
+    }
+    if world.get::<Light>(entity).map(|light| *light) == old.light {
+        set_option(world, entity, new.light);
+    }
+}
+
+fn set_option<T: Component>(world: &mut World, entity: Entity, component: Option<T>) {
+    match component {
+        Some(component) => {
+            world.insert(entity, component);
+        }
+        None => {
+            world.remove::<T>(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, x: f32, parent: Option<usize>) -> NodeAsset {
+        NodeAsset {
+            name: Some(name.to_string()),
+            transform: Transform::from_translation([x, 0.0, 0.0].into()),
+            parent,
+            mesh: None,
+            light: None,
+        }
+    }
+
+    fn world_with(prefab: PrefabAsset) -> World {
+        let mut world = World::new();
+        world.insert_resource(Prefabs::default());
+        update(&mut world, prefab).unwrap();
+        world
+    }
+
+    fn instantiate(world: &mut World, overrides: &PrefabOverrides) -> Entity {
+        Prefabs::instantiate_with(world, "pillar", overrides, |_| Ok(None)).unwrap()
+    }
+
+    fn update(world: &mut World, prefab: PrefabAsset) -> Result<(), AssetError> {
+        Prefabs::update_with(world, "pillar", prefab, |_| Ok(None))
+    }
+
+    /// The entities of the instance by node index
+    fn nodes(world: &World, root: Entity) -> Vec<Entity> {
+        let mut nodes = Vec::new();
+        world
+            .query::<&PrefabInstance>()
+            .for_each(|entity, instance| {
+                if instance.root == root {
+                    nodes.push((instance.node, entity));
+                }
+            });
+        nodes.sort();
+        nodes.into_iter().map(|(_, entity)| entity).collect()
+    }
+
+    fn name(world: &World, entity: Entity) -> Option<String> {
+        world.get::<Name>(entity).map(|name| name.0.clone())
+    }
+
+    fn x(world: &World, entity: Entity) -> f32 {
+        world.get::<Transform>(entity).unwrap().translation.x
+    }
+
+    fn parent(world: &World, entity: Entity) -> Option<Entity> {
+        world.get::<Parent>(entity).map(|parent| parent.0)
+    }
+
+    fn pillar() -> PrefabAsset {
+        PrefabAsset::new(vec![
+            node("base", 0.0, None),
+            node("shaft", 1.0, None),
+            node("cap", 2.0, Some(1)),
+        ])
+    }
+
+    #[test]
+    fn instantiates_with_overrides() {
+        let mut world = world_with(pillar());
+        let root = instantiate(
+            &mut world,
+            &PrefabOverrides::new()
+                .with_transform(0, Transform::from_translation([5.0, 0.0, 0.0].into()))
+                .with_name(2, "top"),
+        );
+
+        let nodes = nodes(&world, root);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0], root);
+        assert_eq!(x(&world, root), 5.0);
+        assert_eq!(name(&world, nodes[1]).as_deref(), Some("shaft"));
+        assert_eq!(name(&world, nodes[2]).as_deref(), Some("top"));
+        assert_eq!(parent(&world, root), None);
+        assert_eq!(parent(&world, nodes[1]), Some(root));
+        assert_eq!(parent(&world, nodes[2]), Some(nodes[1]));
+    }
+
+    #[test]
+    fn instantiating_unknown_or_empty_prefabs_fails() {
+        let mut world = world_with(PrefabAsset::default());
+        assert!(matches!(
+            Prefabs::instantiate_with(&mut world, "pillar", &PrefabOverrides::new(), |_| Ok(None)),
+            Err(AssetError::EmptyPrefab(_))
+        ));
+        assert!(matches!(
+            Prefabs::instantiate_with(&mut world, "arch", &PrefabOverrides::new(), |_| Ok(None)),
+            Err(AssetError::MissingPrefab(_))
+        ));
+    }
+
+    #[test]
+    fn updates_propagate_to_the_properties_not_overridden() {
+        let mut world = world_with(pillar());
+        let plain = instantiate(&mut world, &PrefabOverrides::new());
+        let overridden = instantiate(
+            &mut world,
+            &PrefabOverrides::new()
+                .with_transform(0, Transform::from_translation([5.0, 0.0, 0.0].into())),
+        );
+        let moved = instantiate(&mut world, &PrefabOverrides::new());
+        world.get_mut::<Transform>(moved).unwrap().translation.x = 7.0;
+
+        let mut prefab = pillar();
+        prefab.nodes[0] = node("plinth", 3.0, None);
+        update(&mut world, prefab).unwrap();
+
+        assert_eq!(x(&world, plain), 3.0);
+        assert_eq!(x(&world, overridden), 5.0);
+        // Compared by value, moving an instance overrides its transform
+        assert_eq!(x(&world, moved), 7.0);
+        for root in [plain, overridden, moved] {
+            assert_eq!(name(&world, root).as_deref(), Some("plinth"));
+        }
+    }
+
+    #[test]
+    fn updates_add_and_remove_nodes() {
+        let mut world = world_with(pillar());
+        let root = instantiate(&mut world, &PrefabOverrides::new());
+
+        let mut prefab = pillar();
+        prefab.nodes.push(node("ornament", 3.0, Some(2)));
+        update(&mut world, prefab).unwrap();
+        let added = nodes(&world, root);
+        assert_eq!(added.len(), 4);
+        assert_eq!(name(&world, added[3]).as_deref(), Some("ornament"));
+        assert_eq!(parent(&world, added[3]), Some(added[2]));
+
+        update(&mut world, PrefabAsset::new(vec![node("base", 0.0, None)])).unwrap();
+        assert_eq!(nodes(&world, root), vec![root]);
+        for entity in &added[1..] {
+            assert!(!world.is_alive(*entity));
+        }
+    }
+
+    #[test]
+    fn updates_reparent_nodes() {
+        let mut world = world_with(pillar());
+        let root = instantiate(&mut world, &PrefabOverrides::new());
+
+        let mut prefab = pillar();
+        prefab.nodes[2].parent = None;
+        update(&mut world, prefab).unwrap();
+        let nodes = nodes(&world, root);
+        assert_eq!(parent(&world, nodes[2]), Some(root));
+    }
+
+    #[test]
+    fn invalid_updates_keep_the_previous_version() {
+        let mut world = world_with(pillar());
+        let mut prefab = pillar();
+        prefab.nodes[0].parent = Some(1);
+        assert!(matches!(
+            update(&mut world, prefab),
+            Err(AssetError::InvalidParent { .. })
+        ));
+        assert_eq!(world.resource::<Prefabs>().get("pillar"), Some(&pillar()));
+    }
+}
@@ -0,0 +1,368 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ecs::{
+        components::{Mesh, Name, Parent, Renderable},
+        Entity, World,
+    },
+    render::{
+        camera::{Camera, Projection},
+        light::Light,
+        resource::Resource,
+        transform::Transform,
+    },
+};
+
+use super::AssetError;
+
+/// The version [`SceneAsset`]s are saved with
+pub const SCENE_VERSION: u32 = 1;
+
+/// A scene saved as RON: entities with their transform hierarchy, meshes,
+/// materials and lights, and the camera settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneAsset {
+    pub version: u32,
+    #[serde(default)]
+    pub nodes: Vec<NodeAsset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<CameraAsset>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeAsset {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Relative to the parent
+    pub transform: Transform,
+    /// Index of an earlier node in the scene
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mesh: Option<Mesh>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub light: Option<Light>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraAsset {
+    pub position: glam::Vec3,
+    /// In degrees
+    pub yaw: f32,
+    /// In degrees
+    pub pitch: f32,
+    pub projection: Projection,
+    /// Vertical, in degrees
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl NodeAsset {
+    /// Creates the node's mesh through `resource`
+    pub(crate) fn renderable(&self, resource: &Resource) -> Result<Option<Renderable>, AssetError> {
+        let Some(mesh) = &self.mesh else {
+            return Ok(None);
+        };
+        let render_object = resource
+            .create_render_object_by_name(&mesh.path)
+            .ok_or_else(|| AssetError::MissingMesh(mesh.path.clone()))?
+            .with_material(mesh.material);
+        Ok(Some(Renderable(Arc::new(render_object))))
+    }
+
+    /// `renderable` as returned by [`NodeAsset::renderable`]
+    pub(crate) fn spawn(
+        &self,
+        world: &mut World,
+        parent: Option<Entity>,
+        renderable: Option<Renderable>,
+    ) -> Entity {
+        let mut entity = world.spawn().with(self.transform);
+        if let Some(name) = &self.name {
+            entity = entity.with(Name(name.clone()));
+        }
+        if let Some(parent) = parent {
+            entity = entity.with(Parent(parent));
+        }
+        if let (Some(mesh), Some(renderable)) = (&self.mesh, renderable) {
+            entity = entity.with(mesh.clone()).with(renderable);
+        }
+        if let Some(light) = self.light {
+            entity = entity.with(light);
+        }
+        entity.id()
+    }
+}
+
+/// Parents must come before their children
+pub(crate) fn validate_parents(nodes: &[NodeAsset]) -> Result<(), AssetError> {
+    for (i, node) in nodes.iter().enumerate() {
+        if let Some(parent) = node.parent.filter(|&parent| parent >= i) {
+            return Err(AssetError::InvalidParent { node: i, parent });
+        }
+    }
+    Ok(())
+}
+
+impl CameraAsset {
+    pub fn from_camera(camera: &Camera) -> Self {
+        Self {
+            position: camera.position(),
+            yaw: camera.yaw(),
+            pitch: camera.pitch(),
+            projection: camera.projection(),
+            fov: camera.fov(),
+            near: camera.near(),
+            far: camera.far(),
+        }
+    }
+
+    /// The aspect ratio isn't saved, it follows the window
+    pub fn to_camera(&self, aspect: f32) -> Camera {
+        let mut camera = Camera::new(self.position, self.fov, aspect, self.near, self.far);
+        camera.set_projection(self.projection);
+        camera.set_yaw(self.yaw);
+        camera.set_pitch(self.pitch);
+        camera
+    }
+}
+
+impl Default for SceneAsset {
+    fn default() -> Self {
+        Self {
+            version: SCENE_VERSION,
+            nodes: Vec::new(),
+            camera: None,
+        }
+    }
+}
+
+impl SceneAsset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_ron(ron: &str) -> Result<Self, AssetError> {
+        let scene: SceneAsset = super::from_ron(ron)?;
+        scene.migrate()
+    }
+
+    pub fn to_ron(&self) -> Result<String, AssetError> {
+        super::to_ron(self)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        let scene: SceneAsset = super::load_ron(path)?;
+        scene.migrate()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        super::save_ron(self, path)
+    }
+
+    /// Upgrades a scene saved by an older version to [`SCENE_VERSION`]
+    fn migrate(self) -> Result<Self, AssetError> {
+        match self.version {
+            SCENE_VERSION => Ok(self),
+            version => Err(AssetError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Collects the entities that have a [`Transform`], and the first [`Camera`].
+    ///
+    /// Only meshes spawned with a [`Mesh`] component can be saved, fails if
+    /// the [`Parent`]s form a cycle.
+    pub fn from_world(world: &World) -> Result<Self, AssetError> {
+        let mut entities = Vec::new();
+        world
+            .query::<(&Transform, Option<&Parent>)>()
+            .for_each(|entity, (_, parent)| entities.push((entity, parent.map(|p| p.0))));
+        // Parents are saved before their children
+        let mut order = Vec::new();
+        let mut index_of = HashMap::new();
+        while order.len() < entities.len() {
+            let len = order.len();
+            for &(entity, parent) in &entities {
+                let ready = parent.is_none_or(|parent| {
+                    index_of.contains_key(&parent)
+                        || !entities.iter().any(|(other, _)| *other == parent)
+                });
+                if !index_of.contains_key(&entity) && ready {
+                    index_of.insert(entity, order.len());
+                    order.push((entity, parent));
+                }
+            }
+            if order.len() == len {
+                let (entity, _) = entities
+                    .iter()
+                    .find(|(entity, _)| !index_of.contains_key(entity))
+                    .unwrap();
+                return Err(AssetError::ParentCycle(*entity));
+            }
+        }
+
+        let nodes = order
+            .into_iter()
+            .map(|(entity, parent)| NodeAsset {
+                name: world.get::<Name>(entity).map(|name| name.0.clone()),
+                transform: *world.get::<Transform>(entity).unwrap(),
+                parent: parent.and_then(|parent| index_of.get(&parent).copied()),
+                mesh: world.get::<Mesh>(entity).map(|mesh| mesh.clone()),
+                light: world.get::<Light>(entity).map(|light| *light),
+            })
+            .collect();
+        let camera = world
+            .query::<&Camera>()
+            .single()
+            .map(|(_, camera)| CameraAsset::from_camera(camera));
+
+        Ok(Self {
+            version: SCENE_VERSION,
+            nodes,
+            camera,
+        })
+    }
+
+    /// Spawns the nodes, resolving their meshes through `resource`, and applies
+    /// the camera settings to the first [`Camera`], spawning one if there is none.
+    ///
+    /// Nothing is spawned if a mesh or a parent is missing.
+    pub fn spawn(&self, world: &mut World, resource: &Resource) -> Result<Vec<Entity>, AssetError> {
+        self.spawn_with(world, |node| node.renderable(resource))
+    }
+
+    fn spawn_with(
+        &self,
+        world: &mut World,
+        renderable: impl Fn(&NodeAsset) -> Result<Option<Renderable>, AssetError>,
+    ) -> Result<Vec<Entity>, AssetError> {
+        validate_parents(&self.nodes)?;
+        let renderables = self
+            .nodes
+            .iter()
+            .map(renderable)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut entities: Vec<Entity> = Vec::with_capacity(self.nodes.len());
+        for (node, renderable) in self.nodes.iter().zip(renderables) {
+            let parent = node.parent.map(|parent| entities[parent]);
+            entities.push(node.spawn(world, parent, renderable));
+        }
+
+        if let Some(camera) = &self.camera {
+            let applied = match world.query::<&mut Camera>().single() {
+                Some((_, current)) => {
+                    *current = camera.to_camera(current.aspect());
+                    true
+                }
+                None => false,
+            };
+            if !applied {
+                world.spawn().with(camera.to_camera(16.0 / 9.0));
+            }
+        }
+        Ok(entities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, x: f32, parent: Option<usize>) -> NodeAsset {
+        NodeAsset {
+            name: Some(name.to_string()),
+            transform: Transform::from_translation([x, 0.0, 0.0].into()),
+            parent,
+            mesh: None,
+            light: None,
+        }
+    }
+
+    fn spawn(scene: &SceneAsset, world: &mut World) -> Result<Vec<Entity>, AssetError> {
+        scene.spawn_with(world, |_| Ok(None))
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let mut world = World::new();
+        let root = world
+            .spawn()
+            .with(Name("sun".to_string()))
+            .with(Transform::from_translation([0.0, 10.0, 0.0].into()))
+            .with(Light::directional(glam::Vec3::ONE, 2.0))
+            .id();
+        let child = world
+            .spawn()
+            .with(Transform::from_translation([1.0, 0.0, 0.0].into()))
+            .with(Parent(root))
+            .id();
+        world.spawn().with(Transform::IDENTITY).with(Parent(child));
+        world
+            .spawn()
+            .with(Camera::new([0.0, 1.0, -5.0].into(), 60.0, 1.5, 0.1, 100.0));
+
+        let scene = SceneAsset::from_world(&world).unwrap();
+        assert_eq!(scene.nodes.len(), 3);
+        assert!(scene.camera.is_some());
+        let scene = SceneAsset::from_ron(&scene.to_ron().unwrap()).unwrap();
+
+        let mut loaded = World::new();
+        let entities = spawn(&scene, &mut loaded).unwrap();
+        assert_eq!(entities.len(), 3);
+        assert_eq!(SceneAsset::from_world(&loaded).unwrap(), scene);
+    }
+
+    #[test]
+    fn saves_parents_before_their_children() {
+        let mut world = World::new();
+        let child = world.spawn().with(Transform::IDENTITY).id();
+        let parent = world.spawn().with(Transform::IDENTITY).id();
+        world.insert(child, Parent(parent));
+
+        let scene = SceneAsset::from_world(&world).unwrap();
+        assert_eq!(scene.nodes[0].parent, None);
+        assert_eq!(scene.nodes[1].parent, Some(0));
+    }
+
+    #[test]
+    fn fails_to_save_parent_cycles() {
+        let mut world = World::new();
+        let a = world.spawn().with(Transform::IDENTITY).id();
+        let b = world.spawn().with(Transform::IDENTITY).with(Parent(a)).id();
+        world.insert(a, Parent(b));
+        assert!(matches!(
+            SceneAsset::from_world(&world),
+            Err(AssetError::ParentCycle(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let ron = format!("(version: {})", SCENE_VERSION + 1);
+        assert!(matches!(
+            SceneAsset::from_ron(&ron),
+            Err(AssetError::UnsupportedVersion(version)) if version == SCENE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn rejects_parents_after_their_children() {
+        let scene = SceneAsset {
+            nodes: vec![node("a", 0.0, Some(1)), node("b", 1.0, None)],
+            ..SceneAsset::new()
+        };
+        let mut world = World::new();
+        assert!(matches!(
+            spawn(&scene, &mut world),
+            Err(AssetError::InvalidParent { node: 0, parent: 1 })
+        ));
+        assert!(world.entities().is_empty());
+    }
+}
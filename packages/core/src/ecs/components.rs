@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::render::{material::Material, primitive};
+
+use super::Entity;
+
+pub use crate::render::{camera::Camera, light::Light, transform::Transform};
+
+/// Draws the mesh at the entity's [`Transform`]
+#[derive(Clone)]
+pub struct Renderable(pub Arc<dyn primitive::Renderable>);
+
+/// The asset a [`Renderable`] was created from, kept so that the entity can be saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mesh {
+    /// The name the mesh is loaded under in [`Resource`]
+    ///
+    /// [`Resource`]: crate::render::resource::Resource
+    pub path: String,
+    #[serde(default)]
+    pub material: Material,
+}
+
+/// The [`Transform`] before the last fixed step, rendering interpolates from it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviousTransform(pub Transform);
+
+/// Makes the entity's [`Transform`] relative to another entity's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name(pub String);
@@ -0,0 +1,193 @@
+use std::marker::PhantomData;
+
+use super::storage::Component;
+
+/// A double-buffered queue of events of type `T`, stored as a resource.
+///
+/// Events stay readable until the second [`Events::update`] after they are
+/// sent, which drops the older half. [`Core`](crate::Core) updates them at the
+/// frame boundaries that follow a tick, so that the systems of both the
+/// frames and the ticks see every event, and at every frame boundary while
+/// paused so that they don't pile up.
+pub struct Events<T> {
+    previous: Vec<T>,
+    /// Id of the first event in `previous`
+    previous_start: usize,
+    current: Vec<T>,
+    current_start: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            previous_start: 0,
+            current: Vec::new(),
+            current_start: 0,
+        }
+    }
+}
+
+impl<T: Component> Events<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.current.extend(events);
+    }
+
+    /// Total number of events ever sent, the id the next event gets
+    fn event_count(&self) -> usize {
+        self.current_start + self.current.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A reader that only sees the events sent from now on
+    pub fn reader(&self) -> EventReader<T> {
+        EventReader {
+            next: self.event_count(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// All the buffered events, oldest first, without a cursor
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(self.current.iter())
+    }
+
+    /// Swaps the buffers, dropping the events sent before the previous update
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        self.previous_start = self.current_start;
+        self.current_start = self.previous_start + self.previous.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.update();
+        self.update();
+    }
+}
+
+/// A cursor into [`Events<T>`], owned by a consumer so that each consumer sees
+/// every event once.
+///
+/// A reader that isn't read for more than a frame misses the dropped events.
+pub struct EventReader<T> {
+    next: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    /// A reader that sees all the buffered events on its first read
+    fn default() -> Self {
+        Self {
+            next: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> EventReader<T> {
+    /// The events sent since the last read
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let next = self.next.max(events.previous_start);
+        self.next = events.event_count();
+        let previous = next.saturating_sub(events.previous_start);
+        let current = next.saturating_sub(events.current_start);
+        events
+            .previous
+            .get(previous..)
+            .unwrap_or_default()
+            .iter()
+            .chain(events.current.get(current..).unwrap_or_default())
+    }
+
+    /// How many events the next [`EventReader::read`] yields
+    pub fn len(&self, events: &Events<T>) -> usize {
+        events.event_count() - self.next.max(events.previous_start)
+    }
+
+    pub fn is_empty(&self, events: &Events<T>) -> bool {
+        self.len(events) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(reader: &mut EventReader<u32>, events: &Events<u32>) -> Vec<u32> {
+        reader.read(events).copied().collect()
+    }
+
+    #[test]
+    fn events_last_two_updates() {
+        let mut events = Events::new();
+        events.send(1);
+        events.update();
+        events.send_batch([2, 3]);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+
+        events.update();
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [2, 3]);
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn readers_see_each_event_once() {
+        let mut events = Events::new();
+        let mut first = EventReader::default();
+        events.send(1);
+        let mut second = events.reader();
+        events.send(2);
+        assert_eq!(first.len(&events), 2);
+        assert_eq!(read(&mut first, &events), [1, 2]);
+        assert_eq!(read(&mut second, &events), [2]);
+        assert!(first.is_empty(&events));
+
+        events.update();
+        events.send(3);
+        assert_eq!(read(&mut first, &events), [3]);
+        assert_eq!(read(&mut first, &events), []);
+        assert_eq!(read(&mut second, &events), [3]);
+    }
+
+    #[test]
+    fn late_readers_miss_dropped_events() {
+        let mut events = Events::new();
+        let mut reader = EventReader::default();
+        events.send(1);
+        events.update();
+        events.send(2);
+        events.update();
+        events.send(3);
+        assert_eq!(reader.len(&events), 2);
+        assert_eq!(read(&mut reader, &events), [2, 3]);
+    }
+
+    #[test]
+    fn clear_drops_everything() {
+        let mut events = Events::new();
+        let mut reader = EventReader::default();
+        events.send_batch([1, 2]);
+        events.clear();
+        assert!(events.is_empty());
+        assert!(reader.is_empty(&events));
+        events.send(3);
+        assert_eq!(read(&mut reader, &events), [3]);
+    }
+}
@@ -0,0 +1,19 @@
+pub mod components;
+mod entity;
+mod event;
+mod query;
+mod schedule;
+mod storage;
+mod system;
+mod world;
+
+pub use entity::Entity;
+pub use event::{EventReader, Events};
+pub use query::{Query, QueryData};
+pub use schedule::{Access, Conflict, IntoSystemConfig, Schedule, Stage, SystemConfig};
+pub use storage::{Component, Storage};
+pub use system::System;
+pub use world::{
+    Commands, EntityCommands, EntityWorldMut, Ref, RefMut, Res, ResMut, StorageMut, StorageRef,
+    World,
+};
@@ -0,0 +1,253 @@
+use super::{
+    schedule::Access,
+    storage::Component,
+    world::{StorageMut, StorageRef},
+    Entity, World,
+};
+
+/// What a [`Query`] borrows per entity: `&T`, `&mut T`, `Option<&T>`,
+/// `Option<&mut T>` or a tuple of those.
+pub trait QueryData {
+    /// The borrowed storages
+    type Fetch<'w>;
+    type Item<'a>;
+
+    /// `None` when an entity can't match because a required storage doesn't exist
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>>;
+
+    /// The candidate entities, `None` if this doesn't restrict them
+    fn entities<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [Entity]>;
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: Entity) -> Option<Self::Item<'a>>;
+
+    /// Adds the components this borrows to `access`
+    fn access(access: &mut Access);
+}
+
+impl<T: Component> QueryData for &T {
+    type Fetch<'w> = StorageRef<'w, T>;
+    type Item<'a> = &'a T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage::<T>()
+    }
+
+    fn entities<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [Entity]> {
+        Some(fetch.entities())
+    }
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: Entity) -> Option<Self::Item<'a>> {
+        fetch.get(entity)
+    }
+
+    fn access(access: &mut Access) {
+        access.add_read::<T>();
+    }
+}
+
+impl<T: Component> QueryData for &mut T {
+    type Fetch<'w> = StorageMut<'w, T>;
+    type Item<'a> = &'a mut T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage_mut::<T>()
+    }
+
+    fn entities<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [Entity]> {
+        Some(fetch.entities())
+    }
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: Entity) -> Option<Self::Item<'a>> {
+        fetch.get_mut(entity)
+    }
+
+    fn access(access: &mut Access) {
+        access.add_write::<T>();
+    }
+}
+
+impl<T: Component> QueryData for Option<&T> {
+    type Fetch<'w> = Option<StorageRef<'w, T>>;
+    type Item<'a> = Option<&'a T>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        Some(world.storage::<T>())
+    }
+
+    fn entities<'a>(_fetch: &'a Self::Fetch<'_>) -> Option<&'a [Entity]> {
+        None
+    }
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: Entity) -> Option<Self::Item<'a>> {
+        Some(fetch.as_ref().and_then(|storage| storage.get(entity)))
+    }
+
+    fn access(access: &mut Access) {
+        access.add_read::<T>();
+    }
+}
+
+impl<T: Component> QueryData for Option<&mut T> {
+    type Fetch<'w> = Option<StorageMut<'w, T>>;
+    type Item<'a> = Option<&'a mut T>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        Some(world.storage_mut::<T>())
+    }
+
+    fn entities<'a>(_fetch: &'a Self::Fetch<'_>) -> Option<&'a [Entity]> {
+        None
+    }
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: Entity) -> Option<Self::Item<'a>> {
+        Some(fetch.as_mut().and_then(|storage| storage.get_mut(entity)))
+    }
+
+    fn access(access: &mut Access) {
+        access.add_write::<T>();
+    }
+}
+
+macro_rules! impl_query_data_tuple {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<$($name: QueryData),*> QueryData for ($($name,)*) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)*);
+            type Item<'a> = ($($name::Item<'a>,)*);
+
+            fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+                Some(($($name::fetch(world)?,)*))
+            }
+
+            fn entities<'a>(fetch: &'a Self::Fetch<'_>) -> Option<&'a [Entity]> {
+                let ($($name,)*) = fetch;
+                // Drive the iteration with the smallest storage
+                [$($name::entities($name)),*]
+                    .into_iter()
+                    .flatten()
+                    .min_by_key(|entities| entities.len())
+            }
+
+            fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: Entity) -> Option<Self::Item<'a>> {
+                let ($($name,)*) = fetch;
+                Some(($($name::get($name, entity)?,)*))
+            }
+
+            fn access(access: &mut Access) {
+                $($name::access(access);)*
+            }
+        }
+    };
+}
+
+impl_query_data_tuple!(A);
+impl_query_data_tuple!(A, B);
+impl_query_data_tuple!(A, B, C);
+impl_query_data_tuple!(A, B, C, D);
+impl_query_data_tuple!(A, B, C, D, E);
+impl_query_data_tuple!(A, B, C, D, E, F);
+
+/// The storages borrowed by a query, released when it is dropped.
+pub struct Query<'w, Q: QueryData> {
+    fetch: Option<Q::Fetch<'w>>,
+}
+
+impl<'w, Q: QueryData> Query<'w, Q> {
+    pub(crate) fn new(world: &'w World) -> Self {
+        Self {
+            fetch: Q::fetch(world),
+        }
+    }
+
+    /// Calls `f` with every matching entity.
+    ///
+    /// At least one element of `Q` must be non-optional, otherwise nothing matches.
+    pub fn for_each(&mut self, mut f: impl FnMut(Entity, Q::Item<'_>)) {
+        let Some(fetch) = &mut self.fetch else {
+            return;
+        };
+        let entities = Q::entities(fetch).map(<[_]>::to_vec).unwrap_or_default();
+        for entity in entities {
+            if let Some(item) = Q::get(fetch, entity) {
+                f(entity, item);
+            }
+        }
+    }
+
+    pub fn get(&mut self, entity: Entity) -> Option<Q::Item<'_>> {
+        Q::get(self.fetch.as_mut()?, entity)
+    }
+
+    /// The matching entities
+    pub fn entities(&mut self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        self.for_each(|entity, _| entities.push(entity));
+        entities
+    }
+
+    /// The first matching entity, handy for queries expected to match once
+    pub fn single(&mut self) -> Option<(Entity, Q::Item<'_>)> {
+        let entity = *self.entities().first()?;
+        self.get(entity).map(|item| (entity, item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_match_all_required_components() {
+        let mut world = World::new();
+        let both = world.spawn().with(1u32).with(10u64).id();
+        let only_u32 = world.spawn().with(2u32).id();
+        world.spawn().with(30u64);
+
+        assert_eq!(world.query::<(&u32, &u64)>().entities(), [both]);
+        world
+            .query::<(&mut u32, &u64)>()
+            .for_each(|_, (a, b)| *a += *b as u32);
+        assert_eq!(*world.get::<u32>(both).unwrap(), 11);
+        assert_eq!(world.query::<&u32>().entities(), [both, only_u32]);
+        // No storage, no match
+        assert!(world.query::<(&u32, &i8)>().entities().is_empty());
+    }
+
+    #[test]
+    fn optional_components() {
+        let mut world = World::new();
+        let both = world.spawn().with(1u32).with(10u64).id();
+        let only_u32 = world.spawn().with(2u32).id();
+
+        let mut found = Vec::new();
+        world
+            .query::<(&u32, Option<&u64>)>()
+            .for_each(|entity, (_, b)| found.push((entity, b.copied())));
+        assert_eq!(found, [(both, Some(10)), (only_u32, None)]);
+
+        // Missing storages are fine for optional components
+        world
+            .query::<(&u32, Option<&mut i8>)>()
+            .for_each(|_, (_, c)| assert!(c.is_none()));
+        assert_eq!(world.query::<(&u32, Option<&i8>)>().entities().len(), 2);
+        // Nothing required, nothing matches
+        assert!(world.query::<Option<&u32>>().entities().is_empty());
+    }
+
+    #[test]
+    fn single_and_get() {
+        let mut world = World::new();
+        assert!(world.query::<&u32>().single().is_none());
+        let first = world.spawn().with(1u32).id();
+        let second = world.spawn().with(2u32).with(20u64).id();
+
+        assert_eq!(world.query::<&u32>().single(), Some((first, &1)));
+        assert_eq!(
+            world.query::<(&u32, &u64)>().single(),
+            Some((second, (&2, &20)))
+        );
+        assert_eq!(world.query::<&u64>().get(first), None);
+        *world.query::<&mut u32>().get(second).unwrap() = 3;
+        assert_eq!(*world.get::<u32>(second).unwrap(), 3);
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+};
+
+use super::{query::QueryData, storage::Component, System, World};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    PreUpdate,
+    Update,
+    PostUpdate,
+    /// Runs right before the world is extracted for rendering
+    PreRender,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::PreUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::PreRender,
+    ];
+}
+
+/// The components and resources a system reads and writes
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    reads: HashMap<TypeId, &'static str>,
+    writes: HashMap<TypeId, &'static str>,
+}
+
+impl Access {
+    pub fn add_read<T: Component>(&mut self) {
+        self.reads.insert(TypeId::of::<T>(), type_name::<T>());
+    }
+
+    pub fn add_write<T: Component>(&mut self) {
+        self.writes.insert(TypeId::of::<T>(), type_name::<T>());
+    }
+
+    /// Names of the types one of the accesses writes while the other uses them
+    pub fn conflicts(&self, other: &Access) -> Vec<&'static str> {
+        let mut conflicts = self
+            .writes
+            .iter()
+            .filter(|(id, _)| other.reads.contains_key(id) || other.writes.contains_key(id))
+            .chain(
+                other
+                    .writes
+                    .iter()
+                    .filter(|(id, _)| self.reads.contains_key(id)),
+            )
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        conflicts.sort();
+        conflicts.dedup();
+        conflicts
+    }
+}
+
+/// A system together with how it is scheduled, see [`IntoSystemConfig`]
+pub struct SystemConfig {
+    system: Box<dyn System>,
+    name: String,
+    /// `None` if unknown, such a system never runs in parallel
+    access: Option<Access>,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+pub trait IntoSystemConfig: Sized {
+    fn into_config(self) -> SystemConfig;
+
+    /// Systems are ordered by name, defaults to [`System::name`]
+    fn with_name(self, name: impl Into<String>) -> SystemConfig {
+        let mut config = self.into_config();
+        config.name = name.into();
+        config
+    }
+
+    /// Runs before the system called `name` in the same stage
+    fn before(self, name: impl Into<String>) -> SystemConfig {
+        let mut config = self.into_config();
+        config.before.push(name.into());
+        config
+    }
+
+    /// Runs after the system called `name` in the same stage
+    fn after(self, name: impl Into<String>) -> SystemConfig {
+        let mut config = self.into_config();
+        config.after.push(name.into());
+        config
+    }
+
+    /// Declares a read of the component or resource `T`
+    fn reads<T: Component>(self) -> SystemConfig {
+        let mut config = self.into_config();
+        config
+            .access
+            .get_or_insert_with(Access::default)
+            .add_read::<T>();
+        config
+    }
+
+    /// Declares a write of the component or resource `T`
+    fn writes<T: Component>(self) -> SystemConfig {
+        let mut config = self.into_config();
+        config
+            .access
+            .get_or_insert_with(Access::default)
+            .add_write::<T>();
+        config
+    }
+
+    /// Declares the components borrowed by `world.query::<Q>()`
+    fn queries<Q: QueryData>(self) -> SystemConfig {
+        let mut config = self.into_config();
+        Q::access(config.access.get_or_insert_with(Access::default));
+        config
+    }
+}
+
+impl IntoSystemConfig for SystemConfig {
+    fn into_config(self) -> SystemConfig {
+        self
+    }
+}
+
+impl<S: System + 'static> IntoSystemConfig for S {
+    fn into_config(self) -> SystemConfig {
+        SystemConfig {
+            name: self.name().to_string(),
+            system: Box::new(self),
+            access: None,
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+}
+
+/// Two systems of a stage that may touch the same data in either order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub stage: Stage,
+    pub systems: (String, String),
+    /// Empty when one of the systems didn't declare its access
+    pub types: Vec<&'static str>,
+}
+
+#[derive(Default)]
+struct StageSystems {
+    systems: Vec<SystemConfig>,
+    /// Indices into `systems`, each batch runs after the previous one
+    batches: Option<Vec<Vec<usize>>>,
+}
+
+impl StageSystems {
+    /// The systems that must run before each system
+    fn dependencies(&self, stage: Stage) -> Vec<Vec<usize>> {
+        let index_of = |name: &str| {
+            let index = self.systems.iter().position(|system| system.name == name);
+            if index.is_none() {
+                println!("[core/schedule]: no system called {name:?} in {stage:?}, ignoring the constraint");
+            }
+            index
+        };
+
+        let mut dependencies = vec![Vec::new(); self.systems.len()];
+        for (i, system) in self.systems.iter().enumerate() {
+            for after in &system.after {
+                dependencies[i].extend(index_of(after));
+            }
+            for before in &system.before {
+                if let Some(j) = index_of(before) {
+                    dependencies[j].push(i);
+                }
+            }
+        }
+        dependencies
+    }
+
+    /// Topologically sorted batches of systems that don't conflict with each other
+    fn build(&mut self, stage: Stage) {
+        let dependencies = self.dependencies(stage);
+
+        let mut batch_of: Vec<Option<usize>> = vec![None; self.systems.len()];
+        let mut batches: Vec<Vec<usize>> = Vec::new();
+        while batch_of.iter().any(Option::is_none) {
+            // Among the systems whose dependencies are placed, in insertion order
+            let ready = (0..self.systems.len())
+                .find(|&i| {
+                    batch_of[i].is_none()
+                        && dependencies[i].iter().all(|&dep| batch_of[dep].is_some())
+                })
+                .unwrap_or_else(|| {
+                    let cycle = (0..self.systems.len())
+                        .filter(|&i| batch_of[i].is_none())
+                        .map(|i| self.systems[i].name.as_str())
+                        .collect::<Vec<_>>();
+                    panic!("[core/schedule]: ordering cycle in {stage:?} between {cycle:?}")
+                });
+
+            // After its dependencies, and after the systems placed before it that it
+            // conflicts with, so that unordered conflicting systems run in insertion order
+            let batch = (0..self.systems.len())
+                .filter_map(|other| {
+                    let placed = batch_of[other]?;
+                    (dependencies[ready].contains(&other) || self.conflict(ready, other).is_some())
+                        .then_some(placed + 1)
+                })
+                .max()
+                .unwrap_or(0);
+            if batch == batches.len() {
+                batches.push(Vec::new());
+            }
+            batches[batch].push(ready);
+            batch_of[ready] = Some(batch);
+        }
+        self.batches = Some(batches);
+    }
+
+    fn conflict(&self, a: usize, b: usize) -> Option<Vec<&'static str>> {
+        match (&self.systems[a].access, &self.systems[b].access) {
+            (Some(a), Some(b)) => {
+                let types = a.conflicts(b);
+                (!types.is_empty()).then_some(types)
+            }
+            _ => Some(Vec::new()),
+        }
+    }
+
+    /// Whether `to` is reachable from `from` through dependencies
+    fn depends_on(dependencies: &[Vec<usize>], from: usize, to: usize) -> bool {
+        let mut stack = vec![from];
+        let mut visited = vec![false; dependencies.len()];
+        while let Some(i) = stack.pop() {
+            if i == to {
+                return true;
+            }
+            if !std::mem::replace(&mut visited[i], true) {
+                stack.extend(&dependencies[i]);
+            }
+        }
+        false
+    }
+
+    fn conflicts(&self, stage: Stage) -> Vec<Conflict> {
+        let dependencies = self.dependencies(stage);
+        let mut conflicts = Vec::new();
+        for a in 0..self.systems.len() {
+            for b in a + 1..self.systems.len() {
+                if Self::depends_on(&dependencies, a, b) || Self::depends_on(&dependencies, b, a) {
+                    continue;
+                }
+                if let Some(types) = self.conflict(a, b) {
+                    conflicts.push(Conflict {
+                        stage,
+                        systems: (self.systems[a].name.clone(), self.systems[b].name.clone()),
+                        types,
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Runs systems by [`Stage`], honoring their before/after constraints.
+///
+/// Structural changes queued with [`World::commands`] are applied after each stage.
+#[derive(Default)]
+pub struct Schedule {
+    stages: HashMap<Stage, StageSystems>,
+    parallel: bool,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs systems without conflicting access at the same time, requires the `parallel` feature
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.set_parallel(parallel);
+        self
+    }
+
+    pub fn set_parallel(&mut self, parallel: bool) {
+        if parallel && !cfg!(feature = "parallel") {
+            println!(
+                "[core/schedule]: the parallel feature is disabled, running systems sequentially"
+            );
+        }
+        self.parallel = parallel;
+    }
+
+    pub fn add_system(&mut self, stage: Stage, system: impl IntoSystemConfig) {
+        let stage = self.stages.entry(stage).or_default();
+        stage.systems.push(system.into_config());
+        stage.batches = None;
+    }
+
+    /// Pairs of systems that touch the same data without an order between them,
+    /// so the result may depend on which runs first.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        Stage::ALL
+            .iter()
+            .filter_map(|stage| Some(self.stages.get(stage)?.conflicts(*stage)))
+            .flatten()
+            .collect()
+    }
+
+    pub fn run_stage(&mut self, stage: Stage, world: &mut World) {
+        let Some(systems) = self.stages.get_mut(&stage) else {
+            return;
+        };
+        if systems.batches.is_none() {
+            systems.build(stage);
+            // Systems that didn't declare their access conflict with everything, don't spam about them
+            for conflict in systems
+                .conflicts(stage)
+                .into_iter()
+                .filter(|conflict| !conflict.types.is_empty())
+            {
+                println!(
+                    "[core/schedule]: {:?} and {:?} in {stage:?} conflict on {:?} without an order",
+                    conflict.systems.0, conflict.systems.1, conflict.types
+                );
+            }
+        }
+
+        let StageSystems { systems, batches } = systems;
+        for batch in batches.as_ref().unwrap() {
+            if self.parallel && batch.len() > 1 {
+                run_parallel(systems, batch, world);
+            } else {
+                for &i in batch {
+                    systems[i].system.run(world);
+                }
+            }
+        }
+        world.apply_commands();
+    }
+
+    pub fn run(&mut self, world: &mut World) {
+        for stage in Stage::ALL {
+            self.run_stage(stage, world);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn run_parallel(systems: &mut [SystemConfig], batch: &[usize], world: &World) {
+    rayon::scope(|scope| {
+        for (_, config) in systems
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| batch.contains(i))
+        {
+            scope.spawn(|_| config.system.run(world));
+        }
+    });
+}
+
+#[cfg(not(feature = "parallel"))]
+fn run_parallel(systems: &mut [SystemConfig], batch: &[usize], world: &World) {
+    for &i in batch {
+        systems[i].system.run(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The names of the systems that ran, in order
+    type Log = Vec<&'static str>;
+
+    fn logged(name: &'static str) -> SystemConfig {
+        (move |world: &World| world.resource_mut::<Log>().push(name))
+            .with_name(name)
+            .writes::<Log>()
+    }
+
+    fn noop(name: &str) -> SystemConfig {
+        (|_: &World| ()).with_name(name)
+    }
+
+    fn run(schedule: &mut Schedule) -> Log {
+        let mut world = World::new();
+        world.insert_resource(Log::new());
+        schedule.run(&mut world);
+        let log = world.resource::<Log>().clone();
+        log
+    }
+
+    fn batches(schedule: &Schedule, stage: Stage) -> Vec<Vec<usize>> {
+        schedule.stages[&stage].batches.clone().unwrap()
+    }
+
+    #[test]
+    fn runs_in_insertion_order_unless_constrained() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, logged("c").after("b"));
+        schedule.add_system(Stage::Update, logged("b"));
+        schedule.add_system(Stage::Update, logged("a").before("b"));
+        schedule.add_system(Stage::Update, logged("d"));
+        schedule.add_system(Stage::PreUpdate, logged("first"));
+        assert_eq!(run(&mut schedule), ["first", "a", "b", "c", "d"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ordering cycle")]
+    fn ordering_cycles_panic() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, logged("a").after("b"));
+        schedule.add_system(Stage::Update, logged("b").after("a"));
+        run(&mut schedule);
+    }
+
+    #[test]
+    fn batches_systems_without_conflicts() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, noop("read").reads::<u32>());
+        schedule.add_system(Stage::Update, noop("read_too").reads::<u32>());
+        schedule.add_system(Stage::Update, noop("write").writes::<u32>());
+        schedule.add_system(Stage::Update, noop("other").writes::<u64>());
+        schedule.add_system(Stage::Update, noop("after_other").after("other"));
+        run(&mut schedule);
+        // The undeclared system runs alone, after everything inserted before it
+        assert_eq!(
+            batches(&schedule, Stage::Update),
+            [vec![0, 1, 3], vec![2], vec![4]]
+        );
+    }
+
+    #[test]
+    fn reports_unordered_conflicts() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, noop("a").writes::<u32>().reads::<u64>());
+        schedule.add_system(Stage::Update, noop("b").reads::<u32>().reads::<u64>());
+        schedule.add_system(Stage::Update, noop("c").writes::<u64>().after("a"));
+        schedule.add_system(Stage::Update, noop("d").before("c"));
+        assert_eq!(
+            schedule.conflicts(),
+            [
+                Conflict {
+                    stage: Stage::Update,
+                    systems: ("a".to_string(), "b".to_string()),
+                    types: vec!["u32"],
+                },
+                Conflict {
+                    stage: Stage::Update,
+                    systems: ("a".to_string(), "d".to_string()),
+                    types: vec![],
+                },
+                Conflict {
+                    stage: Stage::Update,
+                    systems: ("b".to_string(), "c".to_string()),
+                    types: vec!["u64"],
+                },
+                Conflict {
+                    stage: Stage::Update,
+                    systems: ("b".to_string(), "d".to_string()),
+                    types: vec![],
+                },
+            ]
+        );
+
+        // Ordered through `c`
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, noop("a").writes::<u32>());
+        schedule.add_system(Stage::Update, noop("b").writes::<u32>().after("c"));
+        schedule.add_system(Stage::Update, noop("c").after("a"));
+        assert!(schedule.conflicts().is_empty());
+    }
+}
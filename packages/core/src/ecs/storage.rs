@@ -0,0 +1,163 @@
+use std::any::Any;
+
+use super::Entity;
+
+/// Anything that can be attached to an entity
+pub trait Component: Any + Send + Sync {}
+
+impl<T: Any + Send + Sync> Component for T {}
+
+/// A sparse set of the `T` components, densely packed for iteration.
+pub struct Storage<T> {
+    sparse: Vec<Option<u32>>,
+    dense: Vec<T>,
+    entities: Vec<Entity>,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            entities: Vec::new(),
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        let index = (*self.sparse.get(entity.index() as usize)?)? as usize;
+        (self.entities[index] == entity).then_some(index)
+    }
+
+    /// Returns the component `entity` had before
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(index) = self.dense_index(entity) {
+            return Some(std::mem::replace(&mut self.dense[index], component));
+        }
+        // The slot may still point at a despawned entity with the same index
+        self.remove_index(entity.index() as usize);
+
+        let slot = entity.index() as usize;
+        if self.sparse.len() <= slot {
+            self.sparse.resize(slot + 1, None);
+        }
+        self.sparse[slot] = Some(self.dense.len() as u32);
+        self.dense.push(component);
+        self.entities.push(entity);
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.dense_index(entity)?;
+        self.remove_index(entity.index() as usize)
+    }
+
+    fn remove_index(&mut self, slot: usize) -> Option<T> {
+        let index = self.sparse.get_mut(slot)?.take()? as usize;
+        let component = self.dense.swap_remove(index);
+        self.entities.swap_remove(index);
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[moved.index() as usize] = Some(index as u32);
+        }
+        Some(component)
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dense_index(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.dense_index(entity).map(|index| &self.dense[index])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.dense_index(entity).map(|index| &mut self.dense[index])
+    }
+
+    /// The entities that have a `T`, in storage order
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.dense.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(self.dense.iter_mut())
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+}
+
+/// Type erased [`Storage`], so that the world can despawn entities without knowing their components
+pub(crate) trait AnyStorage: Send + Sync {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> AnyStorage for Storage<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::World;
+
+    #[test]
+    fn removing_keeps_the_others_reachable() {
+        let mut world = World::new();
+        let entities = (0..4u32)
+            .map(|i| world.spawn().with(i).id())
+            .collect::<Vec<_>>();
+        let mut storage = world.storage_mut::<u32>().unwrap();
+        // Swaps the last one in
+        assert_eq!(storage.remove(entities[1]), Some(1));
+        assert_eq!(storage.len(), 3);
+        assert_eq!(storage.entities(), [entities[0], entities[3], entities[2]]);
+        for (i, entity) in entities.iter().enumerate().filter(|(i, _)| *i != 1) {
+            assert_eq!(storage.get(*entity), Some(&(i as u32)));
+        }
+        assert!(!storage.contains(entities[1]));
+        assert_eq!(storage.remove(entities[1]), None);
+    }
+
+    #[test]
+    fn stale_entities_dont_match_their_slot() {
+        let mut world = World::new();
+        let stale = world.spawn().id();
+        world.despawn(stale);
+        let entity = world.spawn().id();
+        assert_eq!(entity.index(), stale.index());
+
+        let mut storage = Storage::default();
+        assert_eq!(storage.insert(stale, 1u32), None);
+        // Replaces the stale entity's component
+        assert_eq!(storage.insert(entity, 2u32), None);
+        assert_eq!(storage.len(), 1);
+        assert_eq!(storage.get(stale), None);
+        assert_eq!(storage.remove(stale), None);
+        assert_eq!(storage.get(entity), Some(&2));
+        *storage.get_mut(entity).unwrap() += 1;
+        assert_eq!(storage.iter().collect::<Vec<_>>(), [(entity, &3)]);
+    }
+}
@@ -0,0 +1,18 @@
+use super::World;
+
+/// Game logic run by [`Core::tick`], borrowing what it needs from the world
+///
+/// [`Core::tick`]: crate::Core::tick
+pub trait System: Send + Sync {
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn run(&mut self, world: &World);
+}
+
+impl<F: FnMut(&World) + Send + Sync> System for F {
+    fn run(&mut self, world: &World) {
+        self(world)
+    }
+}
@@ -0,0 +1,484 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use super::{
+    entity::Entities,
+    event::Events,
+    query::{Query, QueryData},
+    storage::{AnyStorage, Component, Storage},
+    Entity,
+};
+
+type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+/// Entities with their components, plus singleton resources.
+///
+/// Components and resources are borrowed through locks, so systems only need a
+/// `&World`. Conflicting borrows panic instead of blocking. Structural changes
+/// made from a `&World` go through [`World::commands`].
+#[derive(Default)]
+pub struct World {
+    entities: Mutex<Entities>,
+    storages: HashMap<TypeId, RwLock<Box<dyn AnyStorage>>>,
+    resources: HashMap<TypeId, RwLock<Box<dyn Any + Send + Sync>>>,
+    commands: Mutex<Vec<Command>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self) -> EntityWorldMut<'_> {
+        let entity = self.entities.get_mut().unwrap().alloc();
+        EntityWorldMut {
+            world: self,
+            entity,
+        }
+    }
+
+    /// Returns `false` if `entity` was already despawned
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.entities.get_mut().unwrap().free(entity) {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.get_mut().unwrap().remove_entity(entity);
+        }
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.lock().unwrap().is_alive(entity)
+    }
+
+    pub fn entities(&self) -> Vec<Entity> {
+        self.entities.lock().unwrap().iter().collect()
+    }
+
+    /// Returns the component `entity` had before
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.is_alive(entity), "insert on a despawned entity");
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RwLock::new(Box::new(Storage::<T>::default())))
+            .get_mut()
+            .unwrap()
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap()
+            .insert(entity, component)
+    }
+
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
+            .unwrap()
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap()
+            .remove(entity)
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.storage::<T>()
+            .is_some_and(|storage| storage.contains(entity))
+    }
+
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        let storage = self.storage::<T>()?;
+        storage.contains(entity).then_some(Ref { storage, entity })
+    }
+
+    pub fn get_mut<T: Component>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
+        let storage = self.storage_mut::<T>()?;
+        storage
+            .contains(entity)
+            .then_some(RefMut { storage, entity })
+    }
+
+    /// All `T` components, `None` if no entity ever had one
+    pub fn storage<T: Component>(&self) -> Option<StorageRef<'_, T>> {
+        let guard = self
+            .storages
+            .get(&TypeId::of::<T>())?
+            .try_read()
+            .unwrap_or_else(|_| panic!("{} is already borrowed mutably", type_name::<T>()));
+        Some(StorageRef {
+            guard,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn storage_mut<T: Component>(&self) -> Option<StorageMut<'_, T>> {
+        let guard = self
+            .storages
+            .get(&TypeId::of::<T>())?
+            .try_write()
+            .unwrap_or_else(|_| panic!("{} is already borrowed", type_name::<T>()));
+        Some(StorageMut {
+            guard,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Entities that have all the components of `Q`, e.g. `world.query::<(&Transform, &mut Velocity)>()`
+    pub fn query<Q: QueryData>(&self) -> Query<'_, Q> {
+        Query::new(self)
+    }
+
+    pub fn insert_resource<R: Component>(&mut self, resource: R) {
+        self.resources
+            .insert(TypeId::of::<R>(), RwLock::new(Box::new(resource)));
+    }
+
+    pub fn remove_resource<R: Component>(&mut self) -> Option<R> {
+        let resource = self.resources.remove(&TypeId::of::<R>())?;
+        Some(*resource.into_inner().unwrap().downcast::<R>().unwrap())
+    }
+
+    pub fn contains_resource<R: Component>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn get_resource<R: Component>(&self) -> Option<Res<'_, R>> {
+        let guard = self
+            .resources
+            .get(&TypeId::of::<R>())?
+            .try_read()
+            .unwrap_or_else(|_| panic!("{} is already borrowed mutably", type_name::<R>()));
+        Some(Res {
+            guard,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn get_resource_mut<R: Component>(&self) -> Option<ResMut<'_, R>> {
+        let guard = self
+            .resources
+            .get(&TypeId::of::<R>())?
+            .try_write()
+            .unwrap_or_else(|_| panic!("{} is already borrowed", type_name::<R>()));
+        Some(ResMut {
+            guard,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Panics if there is no `R`
+    pub fn resource<R: Component>(&self) -> Res<'_, R> {
+        self.get_resource()
+            .unwrap_or_else(|| panic!("missing resource {}", type_name::<R>()))
+    }
+
+    /// Panics if there is no `R`
+    pub fn resource_mut<R: Component>(&self) -> ResMut<'_, R> {
+        self.get_resource_mut()
+            .unwrap_or_else(|| panic!("missing resource {}", type_name::<R>()))
+    }
+
+    /// Sends to the [`Events<T>`] resource, panics if `T` isn't registered as an event
+    pub fn send_event<T: Component>(&self, event: T) {
+        self.resource_mut::<Events<T>>().send(event);
+    }
+
+    /// Queues structural changes, applied by the next [`World::apply_commands`]
+    pub fn commands(&self) -> Commands<'_> {
+        Commands { world: self }
+    }
+
+    pub fn apply_commands(&mut self) {
+        // Commands may queue more commands
+        loop {
+            let commands = std::mem::take(self.commands.get_mut().unwrap());
+            if commands.is_empty() {
+                break;
+            }
+            for command in commands {
+                command(self);
+            }
+        }
+    }
+}
+
+pub struct EntityWorldMut<'w> {
+    world: &'w mut World,
+    entity: Entity,
+}
+
+impl EntityWorldMut<'_> {
+    pub fn with<T: Component>(self, component: T) -> Self {
+        self.world.insert(self.entity, component);
+        self
+    }
+
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Commands<'w> {
+    world: &'w World,
+}
+
+impl<'w> Commands<'w> {
+    pub fn add(&self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.world.commands.lock().unwrap().push(Box::new(command));
+    }
+
+    /// The entity is alive right away, its components are inserted later
+    pub fn spawn(&self) -> EntityCommands<'w> {
+        let entity = self.world.entities.lock().unwrap().alloc();
+        EntityCommands {
+            commands: *self,
+            entity,
+        }
+    }
+
+    pub fn entity(&self, entity: Entity) -> EntityCommands<'w> {
+        EntityCommands {
+            commands: *self,
+            entity,
+        }
+    }
+
+    pub fn despawn(&self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    pub fn insert_resource<R: Component>(&self, resource: R) {
+        self.add(move |world| world.insert_resource(resource));
+    }
+}
+
+pub struct EntityCommands<'w> {
+    commands: Commands<'w>,
+    entity: Entity,
+}
+
+impl EntityCommands<'_> {
+    pub fn with<T: Component>(self, component: T) -> Self {
+        let entity = self.entity;
+        self.commands.add(move |world| {
+            if world.is_alive(entity) {
+                world.insert(entity, component);
+            }
+        });
+        self
+    }
+
+    pub fn remove<T: Component>(self) -> Self {
+        let entity = self.entity;
+        self.commands.add(move |world| {
+            world.remove::<T>(entity);
+        });
+        self
+    }
+
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+}
+
+pub struct StorageRef<'w, T> {
+    guard: RwLockReadGuard<'w, Box<dyn AnyStorage>>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Component> Deref for StorageRef<'_, T> {
+    type Target = Storage<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.as_any().downcast_ref().unwrap()
+    }
+}
+
+pub struct StorageMut<'w, T> {
+    guard: RwLockWriteGuard<'w, Box<dyn AnyStorage>>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Component> Deref for StorageMut<'_, T> {
+    type Target = Storage<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.as_any().downcast_ref().unwrap()
+    }
+}
+
+impl<T: Component> DerefMut for StorageMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.as_any_mut().downcast_mut().unwrap()
+    }
+}
+
+/// A borrowed component
+pub struct Ref<'w, T> {
+    storage: StorageRef<'w, T>,
+    entity: Entity,
+}
+
+impl<T: Component> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.storage.get(self.entity).unwrap()
+    }
+}
+
+pub struct RefMut<'w, T> {
+    storage: StorageMut<'w, T>,
+    entity: Entity,
+}
+
+impl<T: Component> Deref for RefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.storage.get(self.entity).unwrap()
+    }
+}
+
+impl<T: Component> DerefMut for RefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.storage.get_mut(self.entity).unwrap()
+    }
+}
+
+/// A borrowed resource
+pub struct Res<'w, R> {
+    guard: RwLockReadGuard<'w, Box<dyn Any + Send + Sync>>,
+    _marker: PhantomData<R>,
+}
+
+impl<R: Component> Deref for Res<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        self.guard.downcast_ref().unwrap()
+    }
+}
+
+pub struct ResMut<'w, R> {
+    guard: RwLockWriteGuard<'w, Box<dyn Any + Send + Sync>>,
+    _marker: PhantomData<R>,
+}
+
+impl<R: Component> Deref for ResMut<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        self.guard.downcast_ref().unwrap()
+    }
+}
+
+impl<R: Component> DerefMut for ResMut<'_, R> {
+    fn deref_mut(&mut self) -> &mut R {
+        self.guard.downcast_mut().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn despawned_slots_are_reused_with_a_new_generation() {
+        let mut world = World::new();
+        let a = world.spawn().with(1u32).id();
+        let b = world.spawn().with(2u32).id();
+        assert!(world.despawn(a));
+        assert!(!world.despawn(a));
+        assert!(!world.is_alive(a));
+
+        let c = world.spawn().id();
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert!(world.is_alive(c));
+        // The stale handle doesn't see the new entity's components
+        world.insert(c, 3u32);
+        assert!(world.get::<u32>(a).is_none());
+        assert_eq!(*world.get::<u32>(c).unwrap(), 3);
+        assert_eq!(world.entities(), [c, b]);
+    }
+
+    #[test]
+    fn inserts_and_removes_components() {
+        let mut world = World::new();
+        let entity = world.spawn().with(1u32).id();
+        assert_eq!(world.insert(entity, 2u32), Some(1));
+        assert_eq!(world.insert(entity, "name"), None);
+        *world.get_mut::<u32>(entity).unwrap() += 1;
+        assert_eq!(*world.get::<u32>(entity).unwrap(), 3);
+
+        assert_eq!(world.remove::<u32>(entity), Some(3));
+        assert_eq!(world.remove::<u32>(entity), None);
+        assert!(!world.has::<u32>(entity));
+        assert!(world.has::<&str>(entity));
+        assert_eq!(world.remove::<u64>(entity), None);
+
+        world.despawn(entity);
+        assert!(world.storage::<&str>().unwrap().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "insert on a despawned entity")]
+    fn inserting_on_despawned_entities_panics() {
+        let mut world = World::new();
+        let entity = world.spawn().id();
+        world.despawn(entity);
+        world.insert(entity, 1u32);
+    }
+
+    #[test]
+    fn resources() {
+        let mut world = World::new();
+        assert!(world.get_resource::<u32>().is_none());
+        world.insert_resource(1u32);
+        *world.resource_mut::<u32>() += 1;
+        assert_eq!(*world.resource::<u32>(), 2);
+        // Shared borrows don't conflict
+        let (a, b) = (world.resource::<u32>(), world.resource::<u32>());
+        assert_eq!(*a + *b, 4);
+        drop((a, b));
+
+        assert_eq!(world.remove_resource::<u32>(), Some(2));
+        assert!(!world.contains_resource::<u32>());
+    }
+
+    #[test]
+    #[should_panic(expected = "u32 is already borrowed")]
+    fn conflicting_resource_borrows_panic() {
+        let mut world = World::new();
+        world.insert_resource(1u32);
+        let _read = world.resource::<u32>();
+        let _write = world.resource_mut::<u32>();
+    }
+
+    #[test]
+    fn commands_apply_in_order() {
+        let mut world = World::new();
+        let entity = world.spawn().with(1u32).id();
+        let commands = world.commands();
+        commands.entity(entity).remove::<u32>().with(2u64);
+        let spawned = commands.spawn().with(3u32).id();
+        commands.add(|world| world.commands().insert_resource(4u32));
+        assert!(world.is_alive(spawned));
+        assert!(!world.has::<u32>(spawned));
+
+        world.apply_commands();
+        assert!(!world.has::<u32>(entity));
+        assert_eq!(*world.get::<u64>(entity).unwrap(), 2);
+        assert_eq!(*world.get::<u32>(spawned).unwrap(), 3);
+        assert_eq!(*world.resource::<u32>(), 4);
+    }
+}
@@ -0,0 +1,616 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use winit::{event::MouseButton, keyboard::KeyCode, keyboard::ModifiersState};
+
+use crate::asset::{self, AssetError};
+
+use super::{
+    gamepad::{GamepadAxis, GamepadButton},
+    interaction::Interaction,
+};
+
+/// The version bindings files are saved with
+pub const BINDINGS_VERSION: u32 = 1;
+
+/// Something an action can be bound to.
+///
+/// Buttons read `1.0` while held, analog sources their raw value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputSource {
+    Key(KeyCode),
+    MouseButton(MouseButton),
+    /// Horizontal cursor movement in pixels since the last frame
+    MouseMotionX,
+    /// Vertical cursor movement in pixels since the last frame, +Y down
+    MouseMotionY,
+    /// Scrolled lines since the last frame, see [`InputSystem::scroll`](super::InputSystem::scroll)
+    ScrollX,
+    /// +Y away from the user
+    ScrollY,
+    GamepadButton(GamepadButton),
+    GamepadAxis(GamepadAxis),
+}
+
+impl InputSource {
+    /// Keys and mouse buttons, but not the modifier keys themselves
+    pub fn takes_modifiers(&self) -> bool {
+        match self {
+            InputSource::Key(key) => !matches!(
+                key,
+                KeyCode::ShiftLeft
+                    | KeyCode::ShiftRight
+                    | KeyCode::ControlLeft
+                    | KeyCode::ControlRight
+                    | KeyCode::AltLeft
+                    | KeyCode::AltRight
+                    | KeyCode::SuperLeft
+                    | KeyCode::SuperRight
+            ),
+            InputSource::MouseButton(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A source bound to an action, active while exactly `modifiers` are held too.
+///
+/// Only keys and mouse buttons check the modifiers, see [`InputSource::takes_modifiers`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub source: InputSource,
+    #[serde(
+        default,
+        skip_serializing_if = "ModifiersState::is_empty",
+        with = "modifiers_serde"
+    )]
+    pub modifiers: ModifiersState,
+    /// Whether it also matches while modifiers other than `modifiers` are held
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub extra_modifiers: bool,
+    /// What the source's value is multiplied by, `y` only matters for 2D axes
+    #[serde(
+        default = "default_direction",
+        skip_serializing_if = "is_default_direction"
+    )]
+    pub direction: glam::Vec2,
+    #[serde(default, skip_serializing_if = "is_press")]
+    pub interaction: Interaction,
+}
+
+/// Modifiers are saved as a list like `[Control, Shift]`
+pub(crate) mod modifiers_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use winit::keyboard::ModifiersState;
+
+    #[derive(Serialize, Deserialize)]
+    enum Modifier {
+        Control,
+        Shift,
+        Alt,
+        Super,
+    }
+
+    const MODIFIERS: [(Modifier, ModifiersState); 4] = [
+        (Modifier::Control, ModifiersState::CONTROL),
+        (Modifier::Shift, ModifiersState::SHIFT),
+        (Modifier::Alt, ModifiersState::ALT),
+        (Modifier::Super, ModifiersState::SUPER),
+    ];
+
+    pub fn serialize<S: Serializer>(
+        modifiers: &ModifiersState,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        MODIFIERS
+            .iter()
+            .filter(|(_, state)| modifiers.contains(*state))
+            .map(|(modifier, _)| modifier)
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ModifiersState, D::Error> {
+        Ok(Vec::<Modifier>::deserialize(deserializer)?
+            .into_iter()
+            .fold(ModifiersState::empty(), |modifiers, modifier| {
+                modifiers | MODIFIERS[modifier as usize].1
+            }))
+    }
+}
+
+fn default_direction() -> glam::Vec2 {
+    glam::Vec2::X
+}
+
+fn is_default_direction(direction: &glam::Vec2) -> bool {
+    *direction == glam::Vec2::X
+}
+
+fn is_press(interaction: &Interaction) -> bool {
+    *interaction == Interaction::Press
+}
+
+impl Binding {
+    pub fn new(source: InputSource) -> Self {
+        Self {
+            source,
+            modifiers: ModifiersState::empty(),
+            extra_modifiers: false,
+            direction: glam::Vec2::X,
+            interaction: Interaction::Press,
+        }
+    }
+
+    pub fn key(key: KeyCode) -> Self {
+        Self::new(InputSource::Key(key))
+    }
+
+    pub fn mouse_button(button: MouseButton) -> Self {
+        Self::new(InputSource::MouseButton(button))
+    }
+
+    pub fn gamepad_button(button: GamepadButton) -> Self {
+        Self::new(InputSource::GamepadButton(button))
+    }
+
+    pub fn gamepad_axis(axis: GamepadAxis) -> Self {
+        Self::new(InputSource::GamepadAxis(axis))
+    }
+
+    /// Extra modifiers don't match, so that `S` doesn't fire along with Ctrl+S
+    pub fn with_modifiers(mut self, modifiers: ModifiersState) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// Matches while other modifiers are held too, e.g. for movement keys
+    /// that still work while Shift is held to sprint
+    pub fn with_extra_modifiers(mut self) -> Self {
+        self.extra_modifiers = true;
+        self
+    }
+
+    /// Whether the binding matches while `held` are held
+    pub fn matches_modifiers(&self, held: ModifiersState) -> bool {
+        if self.extra_modifiers || !self.source.takes_modifiers() {
+            held.contains(self.modifiers)
+        } else {
+            held == self.modifiers
+        }
+    }
+
+    /// For 1D axes, `-1.0` for the key of the negative direction
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.direction = glam::Vec2::new(scale, 0.0);
+        self
+    }
+
+    /// For 2D axes, e.g. `Vec2::Y` for the key moving forward
+    pub fn with_direction(mut self, direction: glam::Vec2) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// For buttons, e.g. [`Interaction::Hold`] for a key held half a second
+    pub fn with_interaction(mut self, interaction: Interaction) -> Self {
+        self.interaction = interaction;
+        self
+    }
+
+    /// The bound source and those its interaction needs
+    pub fn sources(&self) -> impl Iterator<Item = InputSource> + '_ {
+        std::iter::once(self.source).chain(self.interaction.sources().iter().copied())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionKind {
+    Button,
+    /// Sums the scaled values of its bindings
+    Axis,
+    /// Sums the values of its bindings along their directions
+    Axis2d,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Action {
+    pub kind: ActionKind,
+    pub bindings: Vec<Binding>,
+}
+
+/// Bound to more than one action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingConflict {
+    pub source: InputSource,
+    pub modifiers: ModifiersState,
+    pub interaction: Interaction,
+    /// Sorted by name
+    pub actions: Vec<String>,
+}
+
+/// How [`InputMap`]s are saved, sorted so that files diff nicely
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingsFile {
+    version: u32,
+    actions: BTreeMap<String, Action>,
+    /// The actions of the input contexts other than gameplay, by context
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    contexts: BTreeMap<String, BTreeMap<String, Action>>,
+}
+
+impl BindingsFile {
+    fn sorted(map: &InputMap) -> BTreeMap<String, Action> {
+        map.actions
+            .iter()
+            .map(|(name, action)| (name.clone(), action.clone()))
+            .collect()
+    }
+}
+
+/// Loads the gameplay bindings and those of the other contexts, see [`save_context_bindings`]
+pub(crate) fn load_context_bindings(
+    path: impl AsRef<Path>,
+) -> Result<(InputMap, HashMap<String, InputMap>), AssetError> {
+    let file: BindingsFile = asset::from_ron(&std::fs::read_to_string(path)?)?;
+    if file.version != BINDINGS_VERSION {
+        return Err(AssetError::UnsupportedVersion(file.version));
+    }
+    let contexts = file
+        .contexts
+        .into_iter()
+        .map(|(name, actions)| {
+            let map = InputMap {
+                actions: actions.into_iter().collect(),
+            };
+            (name, map)
+        })
+        .collect();
+    Ok((
+        InputMap {
+            actions: file.actions.into_iter().collect(),
+        },
+        contexts,
+    ))
+}
+
+/// Saves the gameplay bindings with those of the other contexts in one file
+pub(crate) fn save_context_bindings<'a>(
+    gameplay: &InputMap,
+    contexts: impl IntoIterator<Item = (&'a str, &'a InputMap)>,
+    path: impl AsRef<Path>,
+) -> Result<(), AssetError> {
+    let ron = asset::to_ron(&BindingsFile {
+        version: BINDINGS_VERSION,
+        actions: BindingsFile::sorted(gameplay),
+        contexts: contexts
+            .into_iter()
+            .map(|(name, map)| (name.to_string(), BindingsFile::sorted(map)))
+            .collect(),
+    })?;
+    std::fs::write(path, ron)?;
+    Ok(())
+}
+
+/// Named actions and their bindings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputMap {
+    actions: HashMap<String, Action>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an action without bindings, or changes the kind of an existing one
+    pub fn add_action(&mut self, name: impl Into<String>, kind: ActionKind) -> &mut Action {
+        let action = self.actions.entry(name.into()).or_insert(Action {
+            kind,
+            bindings: Vec::new(),
+        });
+        action.kind = kind;
+        action
+    }
+
+    /// Adds a binding to the action `name`, which must exist
+    pub fn bind(&mut self, name: &str, binding: Binding) {
+        self.actions
+            .get_mut(name)
+            .unwrap_or_else(|| panic!("[core/input]: no action called {name:?}"))
+            .bindings
+            .push(binding);
+    }
+
+    /// Removes the bindings of `name` to `source`
+    pub fn unbind(&mut self, name: &str, source: InputSource) {
+        if let Some(action) = self.actions.get_mut(name) {
+            action.bindings.retain(|binding| binding.source != source);
+        }
+    }
+
+    pub fn remove_action(&mut self, name: &str) -> Option<Action> {
+        self.actions.remove(name)
+    }
+
+    /// Replaces the `index`th binding of the action `name`
+    pub fn rebind(&mut self, name: &str, index: usize, binding: Binding) {
+        let action = self
+            .actions
+            .get_mut(name)
+            .unwrap_or_else(|| panic!("[core/input]: no action called {name:?}"));
+        action.bindings[index] = binding;
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Action> {
+        self.actions.get(name)
+    }
+
+    /// Replaces the actions of `other`, keeping the others
+    pub fn merge(&mut self, other: InputMap) {
+        self.actions.extend(other.actions);
+    }
+
+    /// Sources bound with the same interaction to several actions, with
+    /// modifiers that can be held for all of them at once. Bindings taking
+    /// extra modifiers overlap the ones with more modifiers, e.g. W with Ctrl+W.
+    pub fn conflicts(&self) -> Vec<BindingConflict> {
+        let mut bindings_of: HashMap<(InputSource, &Interaction), Vec<(&String, &Binding)>> =
+            HashMap::new();
+        for (name, action) in &self.actions {
+            for binding in &action.bindings {
+                bindings_of
+                    .entry((binding.source, &binding.interaction))
+                    .or_default()
+                    .push((name, binding));
+            }
+        }
+        // By the fewest modifiers held for both
+        let mut actions_of: HashMap<(InputSource, ModifiersState, &Interaction), Vec<String>> =
+            HashMap::new();
+        for ((source, interaction), bindings) in &bindings_of {
+            for (i, (name, binding)) in bindings.iter().enumerate() {
+                for (other_name, other) in &bindings[i + 1..] {
+                    let held = binding.modifiers | other.modifiers;
+                    if name == other_name
+                        || !binding.matches_modifiers(held)
+                        || !other.matches_modifiers(held)
+                    {
+                        continue;
+                    }
+                    let actions = actions_of.entry((*source, held, interaction)).or_default();
+                    for name in [name, other_name] {
+                        if !actions.contains(name) {
+                            actions.push(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        let mut conflicts = actions_of
+            .into_iter()
+            .map(|((source, modifiers, interaction), mut actions)| {
+                actions.sort();
+                BindingConflict {
+                    source,
+                    modifiers,
+                    interaction: interaction.clone(),
+                    actions,
+                }
+            })
+            .collect::<Vec<_>>();
+        conflicts.sort_by(|a, b| {
+            a.actions
+                .cmp(&b.actions)
+                .then(a.modifiers.bits().cmp(&b.modifiers.bits()))
+        });
+        conflicts
+    }
+
+    /// The actions of the other contexts in the file are ignored
+    pub fn from_ron(ron: &str) -> Result<Self, AssetError> {
+        let file: BindingsFile = asset::from_ron(ron)?;
+        if file.version != BINDINGS_VERSION {
+            return Err(AssetError::UnsupportedVersion(file.version));
+        }
+        Ok(Self {
+            actions: file.actions.into_iter().collect(),
+        })
+    }
+
+    pub fn to_ron(&self) -> Result<String, AssetError> {
+        asset::to_ron(&BindingsFile {
+            version: BINDINGS_VERSION,
+            actions: BindingsFile::sorted(self),
+            contexts: BTreeMap::new(),
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        std::fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Action)> {
+        self.actions
+            .iter()
+            .map(|(name, action)| (name.as_str(), action))
+    }
+}
+
+/// The state of an action, see [`InputSystem::action`]
+///
+/// [`InputSystem::action`]: super::InputSystem::action
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionState {
+    pub(crate) pressed: bool,
+    pub(crate) was_pressed: bool,
+    /// A bound button went down since the last update, maybe already released
+    pub(crate) tapped: bool,
+    pub(crate) value: glam::Vec2,
+}
+
+impl ActionState {
+    /// Held, or for axes, away from zero
+    pub fn pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Pressed since the last update, see [`InputSystem::update`]
+    ///
+    /// [`InputSystem::update`]: super::InputSystem::update
+    pub fn just_pressed(&self) -> bool {
+        !self.was_pressed && (self.pressed || self.tapped)
+    }
+
+    /// Also set along with [`ActionState::just_pressed`] for a tap shorter than an update
+    pub fn just_released(&self) -> bool {
+        !self.pressed && (self.was_pressed || self.tapped)
+    }
+
+    /// The value of a button or 1D axis
+    pub fn value(&self) -> f32 {
+        self.value.x
+    }
+
+    pub fn axis_2d(&self) -> glam::Vec2 {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn modifiers_match_exactly() {
+        let save = Binding::key(KeyCode::KeyS).with_modifiers(ModifiersState::CONTROL);
+        let back = Binding::key(KeyCode::KeyS);
+        assert!(save.matches_modifiers(ModifiersState::CONTROL));
+        assert!(!save.matches_modifiers(ModifiersState::empty()));
+        assert!(!save.matches_modifiers(ModifiersState::CONTROL | ModifiersState::SHIFT));
+        assert!(back.matches_modifiers(ModifiersState::empty()));
+        assert!(!back.matches_modifiers(ModifiersState::CONTROL));
+    }
+
+    #[test]
+    fn extra_modifiers_match_when_allowed() {
+        let forward = Binding::key(KeyCode::KeyW).with_extra_modifiers();
+        assert!(forward.matches_modifiers(ModifiersState::SHIFT));
+        let save = Binding::key(KeyCode::KeyS)
+            .with_modifiers(ModifiersState::CONTROL)
+            .with_extra_modifiers();
+        assert!(save.matches_modifiers(ModifiersState::CONTROL | ModifiersState::SHIFT));
+        assert!(!save.matches_modifiers(ModifiersState::SHIFT));
+    }
+
+    #[test]
+    fn only_keys_and_mouse_buttons_check_modifiers() {
+        assert!(Binding::key(KeyCode::ShiftLeft).matches_modifiers(ModifiersState::SHIFT));
+        assert!(Binding::new(InputSource::MouseMotionX).matches_modifiers(ModifiersState::ALT));
+        assert!(!Binding::mouse_button(MouseButton::Left).matches_modifiers(ModifiersState::ALT));
+    }
+
+    #[test]
+    fn conflicts_compare_modifiers_and_interactions() {
+        let mut map = InputMap::new();
+        map.add_action("move", ActionKind::Axis2d);
+        map.bind("move", Binding::key(KeyCode::KeyW));
+        map.add_action("dash", ActionKind::Button);
+        map.bind(
+            "dash",
+            Binding::key(KeyCode::KeyW)
+                .with_interaction(Interaction::DoubleTap(Duration::from_millis(250))),
+        );
+        map.add_action("save", ActionKind::Button);
+        map.bind(
+            "save",
+            Binding::key(KeyCode::KeyW).with_modifiers(ModifiersState::CONTROL),
+        );
+        assert!(map.conflicts().is_empty());
+
+        map.add_action("jump", ActionKind::Button);
+        map.bind("jump", Binding::key(KeyCode::KeyW));
+        assert_eq!(
+            map.conflicts(),
+            [BindingConflict {
+                source: InputSource::Key(KeyCode::KeyW),
+                modifiers: ModifiersState::empty(),
+                interaction: Interaction::Press,
+                actions: vec!["jump".to_string(), "move".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn extra_modifiers_conflict_with_more_modifiers() {
+        let mut map = InputMap::new();
+        map.add_action("move", ActionKind::Axis2d);
+        map.bind("move", Binding::key(KeyCode::KeyW).with_extra_modifiers());
+        map.add_action("close", ActionKind::Button);
+        map.bind(
+            "close",
+            Binding::key(KeyCode::KeyW).with_modifiers(ModifiersState::CONTROL),
+        );
+        assert_eq!(
+            map.conflicts(),
+            [BindingConflict {
+                source: InputSource::Key(KeyCode::KeyW),
+                modifiers: ModifiersState::CONTROL,
+                interaction: Interaction::Press,
+                actions: vec!["close".to_string(), "move".to_string()],
+            }]
+        );
+
+        // Ctrl+W with extra modifiers doesn't match a plain W
+        let mut map = InputMap::new();
+        map.add_action("move", ActionKind::Axis2d);
+        map.bind("move", Binding::key(KeyCode::KeyW));
+        map.add_action("close", ActionKind::Button);
+        map.bind(
+            "close",
+            Binding::key(KeyCode::KeyW)
+                .with_modifiers(ModifiersState::CONTROL)
+                .with_extra_modifiers(),
+        );
+        assert!(map.conflicts().is_empty());
+
+        // Both take extra modifiers, Ctrl+Shift+W matches them
+        map.bind(
+            "move",
+            Binding::key(KeyCode::KeyW)
+                .with_modifiers(ModifiersState::SHIFT)
+                .with_extra_modifiers(),
+        );
+        assert_eq!(
+            map.conflicts()
+                .iter()
+                .map(|conflict| conflict.modifiers)
+                .collect::<Vec<_>>(),
+            [ModifiersState::CONTROL | ModifiersState::SHIFT]
+        );
+    }
+
+    #[test]
+    fn extra_modifiers_round_trip() {
+        let mut map = InputMap::new();
+        map.add_action("move", ActionKind::Axis2d);
+        map.bind("move", Binding::key(KeyCode::KeyW).with_extra_modifiers());
+        map.bind("move", Binding::key(KeyCode::KeyS));
+        let ron = map.to_ron().unwrap();
+        assert_eq!(ron.matches("extra_modifiers").count(), 1);
+        assert_eq!(InputMap::from_ron(&ron).unwrap(), map);
+    }
+}
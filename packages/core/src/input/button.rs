@@ -0,0 +1,87 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    hash::Hash,
+    time::Duration,
+};
+
+/// The held state of buttons with their edges since the last update.
+///
+/// A button pressed and released between two updates is both just pressed and
+/// just released, so that quick taps aren't lost.
+#[derive(Debug, Clone)]
+pub struct ButtonInput<T> {
+    /// How long each held button has been held
+    pressed: HashMap<T, Duration>,
+    just_pressed: HashSet<T>,
+    just_released: HashSet<T>,
+}
+
+impl<T> Default for ButtonInput<T> {
+    fn default() -> Self {
+        Self {
+            pressed: HashMap::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> ButtonInput<T> {
+    /// Pressing a held button again, like a key repeat, does nothing
+    pub fn press(&mut self, button: T) {
+        if let Entry::Vacant(entry) = self.pressed.entry(button) {
+            entry.insert(Duration::ZERO);
+            self.just_pressed.insert(button);
+        }
+    }
+
+    pub fn release(&mut self, button: T) {
+        if self.pressed.remove(&button).is_some() {
+            self.just_released.insert(button);
+        }
+    }
+
+    pub fn pressed(&self, button: T) -> bool {
+        self.pressed.contains_key(&button)
+    }
+
+    pub fn just_pressed(&self, button: T) -> bool {
+        self.just_pressed.contains(&button)
+    }
+
+    pub fn just_released(&self, button: T) -> bool {
+        self.just_released.contains(&button)
+    }
+
+    /// `None` if not held, counted in the deltas passed to [`ButtonInput::update`]
+    pub fn held_duration(&self, button: T) -> Option<Duration> {
+        self.pressed.get(&button).copied()
+    }
+
+    pub fn get_pressed(&self) -> impl Iterator<Item = T> + '_ {
+        self.pressed.keys().copied()
+    }
+
+    pub fn get_just_pressed(&self) -> impl Iterator<Item = T> + '_ {
+        self.just_pressed.iter().copied()
+    }
+
+    pub fn get_just_released(&self) -> impl Iterator<Item = T> + '_ {
+        self.just_released.iter().copied()
+    }
+
+    /// Clears the edges and adds `delta` to the held durations
+    pub fn update(&mut self, delta: Duration) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        for duration in self.pressed.values_mut() {
+            *duration += delta;
+        }
+    }
+
+    /// Releases everything, e.g. when the window loses focus
+    pub fn release_all(&mut self) {
+        self.just_released
+            .extend(self.pressed.drain().map(|(button, _)| button));
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use super::action::{ActionKind, Binding, InputMap, InputSource};
+
+/// The context at the bottom of the stack, always active
+pub const GAMEPLAY: &str = "gameplay";
+
+/// Which inputs a context hides from the contexts below it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Consume {
+    /// Lets everything through, e.g. for a HUD on top of the gameplay
+    #[default]
+    None,
+    /// The sources bound to its own actions
+    Bound,
+    /// Everything, e.g. for menus and consoles
+    All,
+}
+
+/// A set of actions active while the context is on the stack, see
+/// [`InputSystem::push_context`](super::InputSystem::push_context)
+#[derive(Debug, Clone, Default)]
+pub struct InputContext {
+    map: InputMap,
+    /// What [`InputContext::reset_to_defaults`] restores
+    defaults: InputMap,
+    consume: Consume,
+}
+
+impl InputContext {
+    pub fn new(consume: Consume) -> Self {
+        Self {
+            consume,
+            ..Default::default()
+        }
+    }
+
+    pub fn map(&self) -> &InputMap {
+        &self.map
+    }
+
+    pub fn map_mut(&mut self) -> &mut InputMap {
+        &mut self.map
+    }
+
+    /// Adds an action to both the current and the default bindings
+    pub fn add_default_action(
+        &mut self,
+        name: &str,
+        kind: ActionKind,
+        bindings: impl IntoIterator<Item = Binding>,
+    ) {
+        let bindings = bindings.into_iter().collect::<Vec<_>>();
+        for map in [&mut self.map, &mut self.defaults] {
+            map.add_action(name, kind).bindings = bindings.clone();
+        }
+    }
+
+    pub fn with_default_action(
+        mut self,
+        name: &str,
+        kind: ActionKind,
+        bindings: impl IntoIterator<Item = Binding>,
+    ) -> Self {
+        self.add_default_action(name, kind, bindings);
+        self
+    }
+
+    pub fn defaults(&self) -> &InputMap {
+        &self.defaults
+    }
+
+    pub fn reset_to_defaults(&mut self) {
+        self.map = self.defaults.clone();
+    }
+
+    /// Restores the default bindings of the action `name`
+    pub fn reset_action(&mut self, name: &str) {
+        match self.defaults.get(name) {
+            Some(action) => {
+                let action = action.clone();
+                self.map.add_action(name, action.kind).bindings = action.bindings;
+            }
+            None => {
+                self.map.remove_action(name);
+            }
+        }
+    }
+
+    pub fn consume(&self) -> Consume {
+        self.consume
+    }
+
+    pub fn set_consume(&mut self, consume: Consume) {
+        self.consume = consume;
+    }
+
+    /// Whether the contexts below don't see `source`
+    pub fn consumes(&self, source: InputSource) -> bool {
+        match self.consume {
+            Consume::None => false,
+            Consume::Bound => self.map.iter().any(|(_, action)| {
+                action
+                    .bindings
+                    .iter()
+                    .any(|binding| binding.sources().any(|bound| bound == source))
+            }),
+            Consume::All => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::KeyCode;
+
+    use super::*;
+    use crate::input::{event::InputEvent, InputSystem};
+
+    /// Gameplay walks on W, the menu on top selects with `menu_key`
+    fn input_system(consume: Consume, menu_key: KeyCode) -> InputSystem {
+        let mut input_system = InputSystem::default();
+        input_system.add_default_action("walk", ActionKind::Button, [Binding::key(KeyCode::KeyW)]);
+        input_system.add_context(
+            "menu",
+            InputContext::new(consume).with_default_action(
+                "select",
+                ActionKind::Button,
+                [Binding::key(menu_key)],
+            ),
+        );
+        input_system.push_context("menu");
+        input_system.handle_input(InputEvent::key_pressed(KeyCode::KeyW));
+        input_system
+    }
+
+    fn walking(input_system: &InputSystem) -> bool {
+        input_system.context_action(GAMEPLAY, "walk").pressed()
+    }
+
+    #[test]
+    fn consume_none_lets_everything_through() {
+        let input_system = input_system(Consume::None, KeyCode::KeyW);
+        assert!(walking(&input_system));
+        assert!(input_system.action("select").pressed());
+        assert!(!input_system.is_context_blocked(GAMEPLAY));
+    }
+
+    #[test]
+    fn consume_bound_hides_the_bound_sources() {
+        let bound = input_system(Consume::Bound, KeyCode::KeyW);
+        assert!(!walking(&bound));
+        assert!(bound.action("select").pressed());
+
+        let unbound = input_system(Consume::Bound, KeyCode::KeyE);
+        assert!(walking(&unbound));
+        assert!(!unbound.is_context_blocked(GAMEPLAY));
+    }
+
+    #[test]
+    fn consume_all_hides_everything() {
+        let input_system = input_system(Consume::All, KeyCode::KeyE);
+        assert!(!walking(&input_system));
+        assert!(input_system.is_context_blocked(GAMEPLAY));
+        assert!(!input_system.is_context_blocked("menu"));
+    }
+
+    #[test]
+    fn popping_lets_the_inputs_through_again() {
+        let mut input_system = input_system(Consume::All, KeyCode::KeyW);
+        assert!(input_system.action("select").pressed());
+        assert_eq!(input_system.pop_context().as_deref(), Some("menu"));
+        assert_eq!(input_system.pop_context(), None);
+
+        // W is still held
+        assert!(walking(&input_system));
+        assert!(!input_system.is_context_blocked(GAMEPLAY));
+        // Inactive contexts read as released
+        assert!(!input_system.action("select").pressed());
+        assert!(input_system.is_context_blocked("menu"));
+    }
+
+    #[test]
+    fn consumes() {
+        let context = InputContext::new(Consume::Bound).with_default_action(
+            "select",
+            ActionKind::Button,
+            [Binding::key(KeyCode::Enter)],
+        );
+        assert!(context.consumes(InputSource::Key(KeyCode::Enter)));
+        assert!(!context.consumes(InputSource::Key(KeyCode::KeyW)));
+        assert!(InputContext::new(Consume::All).consumes(InputSource::Key(KeyCode::KeyW)));
+        assert!(!InputContext::new(Consume::None).consumes(InputSource::Key(KeyCode::KeyW)));
+    }
+}
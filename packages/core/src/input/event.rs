@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use winit::{
+    event::{
+        DeviceEvent, ElementState, Ime, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+    },
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+};
+
+use super::{action::modifiers_serde, gamepad::GamepadEvent, mouse::ScrollDelta};
+
+/// An input event as the engine handles it, converted from winit or polled from
+/// the gamepad backend.
+///
+/// Unlike winit events, these can be created from code, saved and replayed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key {
+        key: KeyCode,
+        pressed: bool,
+        /// A key held down long enough to repeat
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        repeat: bool,
+        /// The text typed by the press, with the layout and modifiers applied
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    Modifiers(#[serde(with = "modifiers_serde")] ModifiersState),
+    /// The window lost focus, the releases that follow won't be seen
+    FocusLost,
+    FocusGained,
+    /// The cursor position in physical pixels from the top left of the window
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    /// Raw motion from the mouse, not bound by the window nor accelerated
+    MouseMotion {
+        dx: f64,
+        dy: f64,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    Scroll(ScrollDelta),
+    /// A finger on a touch screen, `id` is kept from `Started` to `Ended` or `Cancelled`
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f64,
+        y: f64,
+    },
+    /// A touchpad pinch, positive to zoom in
+    PinchGesture(f64),
+    /// A touchpad rotation in degrees, counterclockwise
+    RotationGesture(f32),
+    /// A touchpad pan in physical pixels
+    PanGesture {
+        dx: f32,
+        dy: f32,
+    },
+    /// Composition from the input method, e.g. for Chinese text
+    Ime(Ime),
+    Gamepad(GamepadEvent),
+}
+
+impl InputEvent {
+    pub fn key_pressed(key: KeyCode) -> Self {
+        InputEvent::Key {
+            key,
+            pressed: true,
+            repeat: false,
+            text: None,
+        }
+    }
+
+    pub fn key_released(key: KeyCode) -> Self {
+        InputEvent::Key {
+            key,
+            pressed: false,
+            repeat: false,
+            text: None,
+        }
+    }
+
+    /// `None` for the events that aren't input, or that the engine doesn't handle
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let PhysicalKey::Code(key) = event.physical_key else {
+                    return None;
+                };
+                InputEvent::Key {
+                    key,
+                    pressed: event.state == ElementState::Pressed,
+                    repeat: event.repeat,
+                    text: event.text.as_ref().map(|text| text.to_string()),
+                }
+            }
+            WindowEvent::Ime(ime) => InputEvent::Ime(ime.clone()),
+            WindowEvent::ModifiersChanged(modifiers) => InputEvent::Modifiers(modifiers.state()),
+            WindowEvent::Focused(false) => InputEvent::FocusLost,
+            WindowEvent::Focused(true) => InputEvent::FocusGained,
+            WindowEvent::CursorMoved { position, .. } => InputEvent::CursorMoved {
+                x: position.x,
+                y: position.y,
+            },
+            WindowEvent::MouseInput { state, button, .. } => InputEvent::MouseButton {
+                button: *button,
+                pressed: *state == ElementState::Pressed,
+            },
+            WindowEvent::MouseWheel { delta, .. } => InputEvent::Scroll(match delta {
+                MouseScrollDelta::LineDelta(x, y) => ScrollDelta::Lines { x: *x, y: *y },
+                MouseScrollDelta::PixelDelta(delta) => ScrollDelta::Pixels {
+                    x: delta.x,
+                    y: delta.y,
+                },
+            }),
+            WindowEvent::Touch(touch) => InputEvent::Touch {
+                id: touch.id,
+                phase: touch.phase,
+                x: touch.location.x,
+                y: touch.location.y,
+            },
+            WindowEvent::PinchGesture { delta, .. } => InputEvent::PinchGesture(*delta),
+            WindowEvent::RotationGesture { delta, .. } => InputEvent::RotationGesture(*delta),
+            WindowEvent::PanGesture { delta, .. } => InputEvent::PanGesture {
+                dx: delta.x,
+                dy: delta.y,
+            },
+            _ => return None,
+        })
+    }
+
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match event {
+            DeviceEvent::MouseMotion { delta: (dx, dy) } => {
+                Some(InputEvent::MouseMotion { dx: *dx, dy: *dy })
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,535 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::button::ButtonInput;
+
+/// Buttons of a standard gamepad, named by position: `South` is A on Xbox and Cross on PlayStation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Axes of a standard gamepad, sticks in `-1.0..=1.0` with +Y up, triggers in `0.0..=1.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// The other axis of the same stick, `None` for triggers
+    pub fn stick_partner(self) -> Option<GamepadAxis> {
+        match self {
+            GamepadAxis::LeftStickX => Some(GamepadAxis::LeftStickY),
+            GamepadAxis::LeftStickY => Some(GamepadAxis::LeftStickX),
+            GamepadAxis::RightStickX => Some(GamepadAxis::RightStickY),
+            GamepadAxis::RightStickY => Some(GamepadAxis::RightStickX),
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => None,
+        }
+    }
+
+    fn is_x(self) -> bool {
+        matches!(self, GamepadAxis::LeftStickX | GamepadAxis::RightStickX)
+    }
+}
+
+/// Identifies a connected gamepad, given by the [`GamepadBackend`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GamepadEvent {
+    Connected {
+        id: GamepadId,
+        name: String,
+    },
+    Disconnected {
+        id: GamepadId,
+    },
+    Button {
+        id: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    },
+    /// The raw value, dead zones are applied when reading it
+    Axis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// A vibration of the strong (low frequency) and weak (high frequency) motors,
+/// magnitudes in `0.0..=1.0`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rumble {
+    pub strong: f32,
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+impl Rumble {
+    pub fn new(strong: f32, weak: f32, duration: Duration) -> Self {
+        Self {
+            strong,
+            weak,
+            duration,
+        }
+    }
+}
+
+/// Where gamepad events come from, polled by [`Core`](crate::Core) at the start of each frame
+pub trait GamepadBackend {
+    /// Appends the events since the last poll to `events`
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+
+    /// Starts a rumble, replacing the current one, `false` if the gamepad can't rumble
+    fn rumble(&mut self, _id: GamepadId, _rumble: Rumble) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Default)]
+struct VirtualGamepadState {
+    events: Vec<GamepadEvent>,
+    connected: HashMap<GamepadId, String>,
+    rumbles: Vec<(GamepadId, Rumble)>,
+}
+
+/// A [`GamepadBackend`] driven from code, for tests and replays.
+///
+/// Clones share their state: one goes to [`Core::set_gamepad_backend`](crate::Core::set_gamepad_backend),
+/// another one drives it.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualGamepad {
+    state: Arc<Mutex<VirtualGamepadState>>,
+}
+
+impl VirtualGamepad {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, VirtualGamepadState> {
+        self.state.lock().unwrap()
+    }
+
+    pub fn connect(&self, id: GamepadId, name: impl Into<String>) {
+        let name = name.into();
+        let mut state = self.state();
+        state.connected.insert(id, name.clone());
+        state.events.push(GamepadEvent::Connected { id, name });
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        let mut state = self.state();
+        if state.connected.remove(&id).is_some() {
+            state.events.push(GamepadEvent::Disconnected { id });
+        }
+    }
+
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.state().events.push(GamepadEvent::Button {
+            id,
+            button,
+            pressed: true,
+        });
+    }
+
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.state().events.push(GamepadEvent::Button {
+            id,
+            button,
+            pressed: false,
+        });
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.state()
+            .events
+            .push(GamepadEvent::Axis { id, axis, value });
+    }
+
+    /// The rumbles played since the last call, oldest first
+    pub fn take_rumbles(&self) -> Vec<(GamepadId, Rumble)> {
+        std::mem::take(&mut self.state().rumbles)
+    }
+}
+
+impl GamepadBackend for VirtualGamepad {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.append(&mut self.state().events);
+    }
+
+    /// Records the rumble for [`VirtualGamepad::take_rumbles`] if `id` is connected
+    fn rumble(&mut self, id: GamepadId, rumble: Rumble) -> bool {
+        let mut state = self.state();
+        if !state.connected.contains_key(&id) {
+            return false;
+        }
+        state.rumbles.push((id, rumble));
+        true
+    }
+}
+
+/// Maps a dead zone corrected magnitude in `0.0..=1.0` to the value read
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    Linear,
+    /// `x^n`, above `1.0` for finer control near the center
+    Power(f32),
+}
+
+impl ResponseCurve {
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => x,
+            ResponseCurve::Power(n) => x.powf(n),
+        }
+    }
+}
+
+/// Dead zones, as fractions of the full range.
+///
+/// Inputs below `radial` in magnitude read zero, each axis below `axial` reads
+/// zero on its own, and the range between `radial` and `outer` is rescaled to
+/// `0.0..=1.0` so that values start from zero when leaving the dead zone.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DeadZone {
+    pub radial: f32,
+    pub axial: f32,
+    pub outer: f32,
+}
+
+impl Default for DeadZone {
+    fn default() -> Self {
+        Self {
+            radial: 0.1,
+            axial: 0.0,
+            outer: 1.0,
+        }
+    }
+}
+
+impl DeadZone {
+    pub fn new(radial: f32, axial: f32) -> Self {
+        Self {
+            radial,
+            axial,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_outer(mut self, outer: f32) -> Self {
+        self.outer = outer;
+        self
+    }
+
+    /// Applies the dead zones and `curve` to a stick
+    pub fn apply_stick(&self, stick: glam::Vec2, curve: ResponseCurve) -> glam::Vec2 {
+        let stick = glam::Vec2::select(
+            stick.abs().cmplt(glam::Vec2::splat(self.axial)),
+            glam::Vec2::ZERO,
+            stick,
+        );
+        let magnitude = stick.length();
+        if magnitude <= self.radial {
+            return glam::Vec2::ZERO;
+        }
+        stick / magnitude * curve.apply(self.rescale(magnitude))
+    }
+
+    /// Applies the radial dead zone and `curve` to a trigger or a lone axis
+    pub fn apply_axis(&self, value: f32, curve: ResponseCurve) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.radial.max(self.axial) {
+            return 0.0;
+        }
+        value.signum() * curve.apply(self.rescale(magnitude))
+    }
+
+    fn rescale(&self, magnitude: f32) -> f32 {
+        ((magnitude - self.radial) / (self.outer - self.radial).max(f32::EPSILON)).clamp(0.0, 1.0)
+    }
+}
+
+/// How raw gamepad axes are filtered, shared by all gamepads
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GamepadSettings {
+    pub left_stick: DeadZone,
+    pub right_stick: DeadZone,
+    pub triggers: DeadZone,
+    pub curve: ResponseCurve,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            left_stick: DeadZone::default(),
+            right_stick: DeadZone::default(),
+            triggers: DeadZone::new(0.05, 0.0),
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+/// The state of a connected gamepad
+#[derive(Debug, Clone, Default)]
+pub struct Gamepad {
+    name: String,
+    buttons: ButtonInput<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+impl Gamepad {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn buttons(&self) -> &ButtonInput<GamepadButton> {
+        &self.buttons
+    }
+
+    pub(crate) fn buttons_mut(&mut self) -> &mut ButtonInput<GamepadButton> {
+        &mut self.buttons
+    }
+
+    /// The value last reported by the backend
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn set_raw_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes.insert(axis, value);
+    }
+
+    /// The value of `axis` with the dead zones and response curve of `settings` applied
+    pub fn axis(&self, axis: GamepadAxis, settings: &GamepadSettings) -> f32 {
+        let Some(partner) = axis.stick_partner() else {
+            return settings
+                .triggers
+                .apply_axis(self.raw_axis(axis), settings.curve);
+        };
+        let (x, y) = if axis.is_x() {
+            (axis, partner)
+        } else {
+            (partner, axis)
+        };
+        let dead_zone = match x {
+            GamepadAxis::LeftStickX => settings.left_stick,
+            _ => settings.right_stick,
+        };
+        let stick = dead_zone.apply_stick(
+            glam::Vec2::new(self.raw_axis(x), self.raw_axis(y)),
+            settings.curve,
+        );
+        if axis.is_x() {
+            stick.x
+        } else {
+            stick.y
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{
+        action::{ActionKind, Binding},
+        InputSystem,
+    };
+
+    const PAD: GamepadId = GamepadId(0);
+    const OTHER_PAD: GamepadId = GamepadId(1);
+
+    /// What [`Core`](crate::Core) does at the start of a frame
+    fn poll(backend: &mut VirtualGamepad, input_system: &mut InputSystem) {
+        let mut events = Vec::new();
+        backend.poll(&mut events);
+        for event in events {
+            input_system.handle_gamepad_event(event);
+        }
+        for (id, rumble) in input_system.take_rumbles() {
+            backend.rumble(id, rumble);
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    fn input_system() -> InputSystem {
+        let mut input_system = InputSystem::default();
+        input_system.add_default_action(
+            "jump",
+            ActionKind::Button,
+            [Binding::gamepad_button(GamepadButton::South)],
+        );
+        input_system.add_default_action(
+            "move",
+            ActionKind::Axis,
+            [Binding::gamepad_axis(GamepadAxis::LeftStickX)],
+        );
+        input_system
+    }
+
+    #[test]
+    fn hotplug() {
+        let mut backend = VirtualGamepad::new();
+        let mut input_system = input_system();
+        backend.connect(PAD, "first");
+        backend.connect(OTHER_PAD, "second");
+        poll(&mut backend, &mut input_system);
+        assert_eq!(input_system.gamepads().count(), 2);
+        assert_eq!(input_system.gamepad(OTHER_PAD).unwrap().name(), "second");
+
+        backend.press(PAD, GamepadButton::South);
+        backend.press(OTHER_PAD, GamepadButton::South);
+        poll(&mut backend, &mut input_system);
+        assert!(input_system.action("jump").just_pressed());
+
+        // Still held on the other gamepad
+        backend.disconnect(PAD);
+        poll(&mut backend, &mut input_system);
+        assert!(input_system.gamepad(PAD).is_none());
+        assert!(input_system.action("jump").pressed());
+
+        backend.disconnect(OTHER_PAD);
+        // Already gone, no second event
+        backend.disconnect(OTHER_PAD);
+        poll(&mut backend, &mut input_system);
+        assert_eq!(input_system.gamepads().count(), 0);
+        assert!(!input_system.action("jump").pressed());
+    }
+
+    #[test]
+    fn axes_go_through_the_dead_zones() {
+        let mut backend = VirtualGamepad::new();
+        let mut input_system = input_system();
+        backend.connect(PAD, "pad");
+        backend.set_axis(PAD, GamepadAxis::LeftStickX, 0.05);
+        poll(&mut backend, &mut input_system);
+        assert_eq!(
+            input_system
+                .gamepad(PAD)
+                .unwrap()
+                .raw_axis(GamepadAxis::LeftStickX),
+            0.05
+        );
+        assert_eq!(input_system.action("move").value(), 0.0);
+
+        backend.set_axis(PAD, GamepadAxis::LeftStickX, -0.55);
+        poll(&mut backend, &mut input_system);
+        assert_close(input_system.action("move").value(), -0.5);
+
+        input_system.gamepad_settings_mut().curve = ResponseCurve::Power(2.0);
+        assert_close(input_system.action("move").value(), -0.25);
+    }
+
+    #[test]
+    fn radial_dead_zone() {
+        let dead_zone = DeadZone::new(0.2, 0.0);
+        let curve = ResponseCurve::Linear;
+        assert_eq!(
+            dead_zone.apply_stick(glam::Vec2::new(0.1, 0.1), curve),
+            glam::Vec2::ZERO
+        );
+        // Rescaled along the stick's direction
+        let stick = dead_zone.apply_stick(glam::Vec2::new(0.36, 0.48), curve);
+        assert_close(stick.length(), 0.5);
+        assert_close(stick.x / stick.y, 0.75);
+        assert_eq!(dead_zone.apply_axis(-0.2, curve), 0.0);
+        assert_close(dead_zone.apply_axis(-0.6, curve), -0.5);
+    }
+
+    #[test]
+    fn axial_dead_zone() {
+        let dead_zone = DeadZone::new(0.0, 0.2);
+        let curve = ResponseCurve::Linear;
+        // Pushing up keeps a straight line despite some drift sideways
+        assert_eq!(
+            dead_zone.apply_stick(glam::Vec2::new(0.15, 0.8), curve),
+            glam::Vec2::new(0.0, 0.8)
+        );
+        assert_eq!(
+            dead_zone.apply_stick(glam::Vec2::new(0.3, 0.4), curve),
+            glam::Vec2::new(0.3, 0.4)
+        );
+        assert_eq!(dead_zone.apply_axis(0.15, curve), 0.0);
+    }
+
+    #[test]
+    fn outer_dead_zone() {
+        let dead_zone = DeadZone::new(0.1, 0.0).with_outer(0.9);
+        let curve = ResponseCurve::Linear;
+        assert_close(dead_zone.apply_axis(0.5, curve), 0.5);
+        assert_eq!(dead_zone.apply_axis(0.95, curve), 1.0);
+        assert_close(
+            dead_zone.apply_stick(glam::Vec2::new(0.0, -1.0), curve).y,
+            -1.0,
+        );
+    }
+
+    #[test]
+    fn power_curve() {
+        let dead_zone = DeadZone::new(0.0, 0.0);
+        let curve = ResponseCurve::Power(2.0);
+        assert_close(dead_zone.apply_axis(0.5, curve), 0.25);
+        assert_close(dead_zone.apply_axis(-0.5, curve), -0.25);
+        assert_eq!(dead_zone.apply_axis(1.0, curve), 1.0);
+        let stick = dead_zone.apply_stick(glam::Vec2::new(0.3, 0.4), curve);
+        assert_close(stick.length(), 0.25);
+    }
+
+    #[test]
+    fn rumble_needs_a_connected_gamepad() {
+        let mut backend = VirtualGamepad::new();
+        let mut input_system = input_system();
+        backend.connect(PAD, "pad");
+        poll(&mut backend, &mut input_system);
+
+        let rumble = Rumble::new(1.0, 0.5, Duration::from_millis(200));
+        input_system.rumble(PAD, rumble);
+        input_system.rumble(OTHER_PAD, rumble);
+        poll(&mut backend, &mut input_system);
+        assert_eq!(backend.take_rumbles(), [(PAD, rumble)]);
+        assert!(!backend.rumble(OTHER_PAD, rumble));
+
+        backend.disconnect(PAD);
+        poll(&mut backend, &mut input_system);
+        assert!(!backend.rumble(PAD, rumble));
+        assert!(backend.take_rumbles().is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Replay, Ticks},
+    Axis, Button, EventType, Gilrs,
+};
+
+use super::gamepad::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, Rumble};
+
+/// A [`GamepadBackend`] for real gamepads, using gilrs
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    /// The gamepads reported connected, gilrs gives no event for the ones
+    /// connected at startup
+    connected: HashMap<GamepadId, gilrs::GamepadId>,
+    /// The effect playing on each gamepad, it stops when dropped
+    effects: HashMap<GamepadId, Effect>,
+}
+
+impl GilrsBackend {
+    pub fn new() -> Result<Self, Box<gilrs::Error>> {
+        Ok(Self {
+            gilrs: Gilrs::new().map_err(Box::new)?,
+            connected: HashMap::new(),
+            effects: HashMap::new(),
+        })
+    }
+
+    fn connect(&mut self, id: gilrs::GamepadId, events: &mut Vec<GamepadEvent>) {
+        let gamepad_id = GamepadId(id.into());
+        if self.connected.insert(gamepad_id, id).is_none() {
+            events.push(GamepadEvent::Connected {
+                id: gamepad_id,
+                name: self.gilrs.gamepad(id).name().to_string(),
+            });
+        }
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        let ids = self.gilrs.gamepads().map(|(id, _)| id).collect::<Vec<_>>();
+        for id in ids {
+            self.connect(id, events);
+        }
+        while let Some(event) = self.gilrs.next_event() {
+            let id = GamepadId(event.id.into());
+            match event.event {
+                EventType::Connected => self.connect(event.id, events),
+                EventType::Disconnected => {
+                    self.effects.remove(&id);
+                    if self.connected.remove(&id).is_some() {
+                        events.push(GamepadEvent::Disconnected { id });
+                    }
+                }
+                EventType::ButtonPressed(button, _) | EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = map_button(button) {
+                        events.push(GamepadEvent::Button {
+                            id,
+                            button,
+                            pressed: matches!(event.event, EventType::ButtonPressed(..)),
+                        });
+                    }
+                }
+                // The analog triggers are buttons with a value in gilrs
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                    events.push(GamepadEvent::Axis {
+                        id,
+                        axis: GamepadAxis::LeftTrigger,
+                        value,
+                    });
+                }
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                    events.push(GamepadEvent::Axis {
+                        id,
+                        axis: GamepadAxis::RightTrigger,
+                        value,
+                    });
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = map_axis(axis) {
+                        events.push(GamepadEvent::Axis { id, axis, value });
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn rumble(&mut self, id: GamepadId, rumble: Rumble) -> bool {
+        let Some(&gilrs_id) = self.connected.get(&id) else {
+            return false;
+        };
+        if !self.gilrs.gamepad(gilrs_id).is_ff_supported() {
+            return false;
+        }
+        let scheduling = Replay {
+            play_for: Ticks::from_ms(rumble.duration.as_millis() as u32),
+            ..Default::default()
+        };
+        let magnitude = |value: f32| (value.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(rumble.strong),
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(rumble.weak),
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .gamepads(&[gilrs_id])
+            .finish(&mut self.gilrs);
+        match effect.and_then(|effect| effect.play().map(|_| effect)) {
+            Ok(effect) => {
+                self.effects.insert(id, effect);
+                true
+            }
+            Err(err) => {
+                println!("[core/input]: failed to rumble gamepad {id:?}: {err}");
+                false
+            }
+        }
+    }
+}
+
+fn map_button(button: Button) -> Option<GamepadButton> {
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::West => GamepadButton::West,
+        Button::North => GamepadButton::North,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+fn map_axis(axis: Axis) -> Option<GamepadAxis> {
+    Some(match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        _ => return None,
+    })
+}
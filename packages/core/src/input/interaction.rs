@@ -0,0 +1,423 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::action::InputSource;
+
+/// How many presses are kept for [`Interaction::Sequence`]
+const MAX_PRESSES: usize = 32;
+
+/// How a bound button has to be pushed to drive its action.
+///
+/// Times are real times, [`Core`](crate::Core) stamps each input with when it
+/// arrived, so pausing or scaling the game time doesn't change them. Triggers
+/// last for the input frame they happen in, see
+/// [`InputSystem::update`](super::InputSystem::update). Modifier chords like
+/// Ctrl+S are [`Binding::with_modifiers`](super::action::Binding::with_modifiers).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interaction {
+    /// Active while held
+    #[default]
+    Press,
+    /// Active while held, once held this long
+    Hold(Duration),
+    /// Triggered when released within this long of the press
+    Tap(Duration),
+    /// Triggered when pressed again within this long of the last press
+    DoubleTap(Duration),
+    /// Active while held along with all of these
+    Chord(Vec<InputSource>),
+    /// Triggered when the bound button is pressed right after
+    /// `steps`, in order, without other presses between them, each within
+    /// `window` of the one before
+    Sequence {
+        steps: Vec<InputSource>,
+        window: Duration,
+    },
+}
+
+impl Interaction {
+    /// Whether the action reads `1.0` for the input frame it triggers in
+    /// rather than the value of the source
+    pub fn is_trigger(&self) -> bool {
+        matches!(
+            self,
+            Interaction::Tap(_) | Interaction::DoubleTap(_) | Interaction::Sequence { .. }
+        )
+    }
+
+    /// The other sources it needs
+    pub fn sources(&self) -> &[InputSource] {
+        match self {
+            Interaction::Chord(sources) => sources,
+            Interaction::Sequence { steps, .. } => steps,
+            _ => &[],
+        }
+    }
+}
+
+/// When a button source last went down and up
+#[derive(Debug, Clone, Copy, Default)]
+struct PressTimes {
+    held: bool,
+    pressed_at: Option<Duration>,
+    /// The press before `pressed_at`, for double taps
+    previous_pressed_at: Option<Duration>,
+    /// `pressed_at` ended a double tap, so the next press doesn't pair with it
+    double_tapped: bool,
+    released_at: Option<Duration>,
+    /// The input frames of the last press and release, for triggers
+    pressed_in: u64,
+    released_in: u64,
+}
+
+/// A button press, see [`InputHistory::press`]
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    source: InputSource,
+    at: Duration,
+    frame: u64,
+}
+
+/// The timestamped presses and releases of the button sources that
+/// [`Interaction`]s are evaluated from
+#[derive(Debug, Clone, Default)]
+pub struct InputHistory {
+    now: Duration,
+    /// When the current input frame began
+    frame_start: Duration,
+    /// How many times [`InputHistory::advance`] ended an input frame
+    frame: u64,
+    times: HashMap<InputSource, PressTimes>,
+    /// The latest presses, oldest first
+    presses: VecDeque<Press>,
+}
+
+impl InputHistory {
+    /// The time of the latest input, or of the end of the last input frame,
+    /// on the clock of the deltas passed to [`InputHistory::advance`]
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Ends the input frame `delta` after the previous one
+    pub fn advance(&mut self, delta: Duration) {
+        self.frame_start += delta;
+        self.now = self.now.max(self.frame_start);
+        self.frame += 1;
+    }
+
+    /// Stamps the inputs that follow with `elapsed` since the input frame
+    /// began, the clock never goes back
+    pub fn set_time(&mut self, elapsed: Duration) {
+        self.now = self.now.max(self.frame_start + elapsed);
+    }
+
+    /// Pressing a held button again, like a key repeat, does nothing
+    pub fn press(&mut self, source: InputSource) {
+        let times = self.times.entry(source).or_default();
+        if times.held {
+            return;
+        }
+        times.held = true;
+        times.previous_pressed_at = match std::mem::take(&mut times.double_tapped) {
+            true => None,
+            false => times.pressed_at,
+        };
+        times.pressed_at = Some(self.now);
+        times.pressed_in = self.frame;
+        if self.presses.len() == MAX_PRESSES {
+            self.presses.pop_front();
+        }
+        self.presses.push_back(Press {
+            source,
+            at: self.now,
+            frame: self.frame,
+        });
+    }
+
+    pub fn release(&mut self, source: InputSource) {
+        if let Some(times) = self.times.get_mut(&source).filter(|times| times.held) {
+            times.held = false;
+            times.released_at = Some(self.now);
+            times.released_in = self.frame;
+        }
+    }
+
+    /// Keeps the last press of `source`, which ended a double tap, from
+    /// starting another one: a triple tap only triggers once
+    pub fn consume_double_tap(&mut self, source: InputSource) {
+        if let Some(times) = self.times.get_mut(&source) {
+            times.double_tapped = true;
+        }
+    }
+
+    /// Whether `interaction` on `source` is active now, `held` telling
+    /// whether a source is held as the actions see it
+    pub fn is_active(
+        &self,
+        source: InputSource,
+        interaction: &Interaction,
+        held: impl Fn(InputSource) -> bool,
+    ) -> bool {
+        let times = self.times.get(&source).copied().unwrap_or_default();
+        match interaction {
+            Interaction::Press => true,
+            Interaction::Hold(duration) => {
+                held(source)
+                    && times
+                        .pressed_at
+                        .is_some_and(|pressed_at| self.now.saturating_sub(pressed_at) >= *duration)
+            }
+            Interaction::Tap(duration) => match (times.pressed_at, times.released_at) {
+                (Some(pressed_at), Some(released_at)) => {
+                    !times.held
+                        && times.released_in == self.frame
+                        && released_at.saturating_sub(pressed_at) <= *duration
+                }
+                _ => false,
+            },
+            Interaction::DoubleTap(window) => match (times.previous_pressed_at, times.pressed_at) {
+                (Some(previous), Some(pressed_at)) => {
+                    times.pressed_in == self.frame && pressed_at.saturating_sub(previous) <= *window
+                }
+                _ => false,
+            },
+            Interaction::Chord(sources) => sources.iter().all(|source| held(*source)),
+            Interaction::Sequence { steps, window } => {
+                let Some(presses) = self.presses.len().checked_sub(steps.len() + 1) else {
+                    return false;
+                };
+                let presses = self.presses.range(presses..).collect::<Vec<_>>();
+                let last = presses[steps.len()];
+                last.source == source
+                    && last.frame == self.frame
+                    && presses[..steps.len()]
+                        .iter()
+                        .zip(steps)
+                        .all(|(press, step)| press.source == *step)
+                    && presses
+                        .windows(2)
+                        .all(|pair| pair[1].at.saturating_sub(pair[0].at) <= *window)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::{KeyCode, ModifiersState};
+
+    use super::*;
+    use crate::input::{
+        action::{ActionKind, Binding},
+        event::InputEvent,
+        InputSystem,
+    };
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn key(input_system: &mut InputSystem, key: KeyCode, pressed: bool) {
+        input_system.handle_input(InputEvent::Key {
+            key,
+            pressed,
+            repeat: false,
+            text: None,
+        });
+    }
+
+    fn tap_key(input_system: &mut InputSystem, code: KeyCode) {
+        key(input_system, code, true);
+        key(input_system, code, false);
+    }
+
+    /// With the action `act` bound to F
+    fn input_system(interaction: Interaction) -> InputSystem {
+        let mut input_system = InputSystem::default();
+        input_system.add_default_action(
+            "act",
+            ActionKind::Button,
+            [Binding::key(KeyCode::KeyF).with_interaction(interaction)],
+        );
+        input_system
+    }
+
+    #[test]
+    fn hold_threshold() {
+        let mut input_system = input_system(Interaction::Hold(ms(500)));
+        key(&mut input_system, KeyCode::KeyF, true);
+        input_system.update(ms(300));
+        assert!(!input_system.action("act").pressed());
+        input_system.update(ms(200));
+        assert!(input_system.action("act").just_pressed());
+        input_system.update(ms(16));
+        assert!(input_system.action("act").pressed());
+        key(&mut input_system, KeyCode::KeyF, false);
+        assert!(input_system.action("act").just_released());
+
+        // The inputs between two updates are stamped with their own time
+        input_system.update(ms(16));
+        key(&mut input_system, KeyCode::KeyF, true);
+        input_system.update(ms(400));
+        input_system.set_event_time(ms(99));
+        key(&mut input_system, KeyCode::KeyG, true);
+        assert!(!input_system.action("act").pressed());
+        input_system.set_event_time(ms(100));
+        key(&mut input_system, KeyCode::KeyG, false);
+        assert!(input_system.action("act").pressed());
+    }
+
+    #[test]
+    fn tap_max() {
+        let mut input_system = input_system(Interaction::Tap(ms(200)));
+        key(&mut input_system, KeyCode::KeyF, true);
+        input_system.update(ms(150));
+        assert!(!input_system.action("act").pressed());
+        key(&mut input_system, KeyCode::KeyF, false);
+        assert!(input_system.action("act").just_pressed());
+        assert_eq!(input_system.action("act").value(), 1.0);
+        // Triggers last for the input frame
+        input_system.update(ms(16));
+        assert!(input_system.action("act").just_released());
+
+        key(&mut input_system, KeyCode::KeyF, true);
+        input_system.update(ms(150));
+        // Within the same frame, but too late
+        input_system.set_event_time(ms(60));
+        key(&mut input_system, KeyCode::KeyF, false);
+        assert!(!input_system.action("act").pressed());
+    }
+
+    #[test]
+    fn double_tap_window() {
+        let mut input_system = input_system(Interaction::DoubleTap(ms(250)));
+        tap_key(&mut input_system, KeyCode::KeyF);
+        assert!(!input_system.action("act").pressed());
+        input_system.update(ms(200));
+        key(&mut input_system, KeyCode::KeyF, true);
+        assert!(input_system.action("act").just_pressed());
+        // Holding the second press doesn't keep it going
+        input_system.update(ms(16));
+        assert!(!input_system.action("act").pressed());
+
+        key(&mut input_system, KeyCode::KeyF, false);
+        input_system.update(ms(300));
+        key(&mut input_system, KeyCode::KeyF, true);
+        assert!(!input_system.action("act").pressed());
+
+        // Both presses in a single frame
+        key(&mut input_system, KeyCode::KeyF, false);
+        input_system.update(ms(300));
+        tap_key(&mut input_system, KeyCode::KeyF);
+        input_system.set_event_time(ms(100));
+        key(&mut input_system, KeyCode::KeyF, true);
+        assert!(input_system.action("act").pressed());
+    }
+
+    #[test]
+    fn triple_taps_trigger_once() {
+        let mut input_system = input_system(Interaction::DoubleTap(ms(250)));
+        let mut triggers = 0;
+        for _ in 0..3 {
+            tap_key(&mut input_system, KeyCode::KeyF);
+            triggers += input_system.action("act").just_pressed() as u32;
+            input_system.update(ms(100));
+        }
+        assert_eq!(triggers, 1);
+
+        // The fourth tap pairs with the third
+        tap_key(&mut input_system, KeyCode::KeyF);
+        assert!(input_system.action("act").just_pressed());
+    }
+
+    #[test]
+    fn chord_and_modifiers() {
+        let mut input_system =
+            input_system(Interaction::Chord(vec![InputSource::Key(KeyCode::KeyG)]));
+        input_system.add_default_action("fire", ActionKind::Button, [Binding::key(KeyCode::KeyS)]);
+        input_system.add_default_action(
+            "save",
+            ActionKind::Button,
+            [Binding::key(KeyCode::KeyS).with_modifiers(ModifiersState::CONTROL)],
+        );
+
+        key(&mut input_system, KeyCode::KeyF, true);
+        assert!(!input_system.action("act").pressed());
+        key(&mut input_system, KeyCode::KeyG, true);
+        assert!(input_system.action("act").pressed());
+        key(&mut input_system, KeyCode::KeyG, false);
+        assert!(!input_system.action("act").pressed());
+
+        key(&mut input_system, KeyCode::KeyS, true);
+        assert!(input_system.action("fire").pressed());
+        assert!(!input_system.action("save").pressed());
+        key(&mut input_system, KeyCode::KeyS, false);
+
+        input_system.handle_input(InputEvent::Modifiers(ModifiersState::CONTROL));
+        key(&mut input_system, KeyCode::KeyS, true);
+        assert!(input_system.action("save").pressed());
+        assert!(!input_system.action("fire").pressed());
+
+        // Not with more modifiers than bound
+        input_system.handle_input(InputEvent::Modifiers(
+            ModifiersState::CONTROL | ModifiersState::SHIFT,
+        ));
+        assert!(!input_system.action("save").pressed());
+    }
+
+    #[test]
+    fn sequence_timeout() {
+        let mut input_system = input_system(Interaction::Sequence {
+            steps: vec![
+                InputSource::Key(KeyCode::KeyA),
+                InputSource::Key(KeyCode::KeyS),
+            ],
+            window: ms(200),
+        });
+        tap_key(&mut input_system, KeyCode::KeyA);
+        input_system.update(ms(150));
+        tap_key(&mut input_system, KeyCode::KeyS);
+        input_system.update(ms(150));
+        key(&mut input_system, KeyCode::KeyF, true);
+        assert!(input_system.action("act").just_pressed());
+        input_system.update(ms(16));
+        assert!(!input_system.action("act").pressed());
+        key(&mut input_system, KeyCode::KeyF, false);
+
+        // Too slow between two steps
+        tap_key(&mut input_system, KeyCode::KeyA);
+        input_system.update(ms(250));
+        tap_key(&mut input_system, KeyCode::KeyS);
+        key(&mut input_system, KeyCode::KeyF, true);
+        assert!(!input_system.action("act").pressed());
+        key(&mut input_system, KeyCode::KeyF, false);
+
+        // Another press in between
+        input_system.update(ms(16));
+        tap_key(&mut input_system, KeyCode::KeyA);
+        tap_key(&mut input_system, KeyCode::KeyD);
+        tap_key(&mut input_system, KeyCode::KeyS);
+        key(&mut input_system, KeyCode::KeyF, true);
+        assert!(!input_system.action("act").pressed());
+    }
+
+    #[test]
+    fn the_clock_never_goes_back() {
+        let mut history = InputHistory::default();
+        history.set_time(ms(30));
+        history.set_time(ms(10));
+        assert_eq!(history.now(), ms(30));
+        // A late input pushed the clock past the end of the frame
+        history.advance(ms(20));
+        assert_eq!(history.now(), ms(30));
+        history.advance(ms(20));
+        assert_eq!(history.now(), ms(40));
+        history.set_time(ms(5));
+        assert_eq!(history.now(), ms(45));
+    }
+}
@@ -1,86 +1,788 @@
+pub mod action;
+pub mod button;
+pub mod context;
+pub mod event;
+pub mod gamepad;
+#[cfg(feature = "gilrs")]
+pub mod gilrs_backend;
+pub mod interaction;
+pub mod mouse;
+pub mod record;
+pub mod text;
+pub mod touch;
+
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use action::{ActionKind, ActionState, Binding, InputMap, InputSource};
+use button::ButtonInput;
+use context::{InputContext, GAMEPLAY};
+use event::InputEvent;
+use gamepad::{Gamepad, GamepadEvent, GamepadId, GamepadSettings, Rumble};
+use interaction::{InputHistory, Interaction};
+use mouse::{CursorMode, MouseSettings, ScrollDelta};
+use text::TextInput;
+use touch::Touches;
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, KeyEvent, MouseButton},
-    keyboard::{KeyCode, PhysicalKey},
+    event::{ElementState, MouseButton},
+    keyboard::{KeyCode, ModifiersState},
+};
+
+use crate::{
+    app::Plugin,
+    asset::AssetError,
+    ecs::{IntoSystemConfig, Stage, World},
+    Core,
 };
 
-pub enum GameCommand {
-    Forward = 1 << 0,
-    Backward = 1 << 1,
-    Left = 1 << 2,
-    Right = 1 << 3,
-    Up = 1 << 4,
-    Down = 1 << 5,
-    MouseLeft = 1 << 6,
-    MouseRight = 1 << 7,
-    Other = 1 << 8,
+/// Sent when a key goes down, not for key repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPressed {
+    pub key: KeyCode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyReleased {
+    pub key: KeyCode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseButtonPressed {
+    pub button: MouseButton,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseButtonReleased {
+    pub button: MouseButton,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamepadConnected {
+    pub id: GamepadId,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GamepadDisconnected {
+    pub id: GamepadId,
 }
 
+/// Adds the [`InputSystem`] bookkeeping: the cursor delta is reset after
+/// anything reading it. [`Core::render`] ends the input frame of the actions.
+///
+/// With the `gilrs` feature, this also installs the gilrs gamepad backend.
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, core: &mut Core) {
+        #[cfg(feature = "gilrs")]
+        match gilrs_backend::GilrsBackend::new() {
+            Ok(backend) => core.set_gamepad_backend(backend),
+            Err(err) => println!("[core/input]: no gamepad support: {err}"),
+        }
+        core.add_system(
+            Stage::PreRender,
+            reset_cursor_delta
+                .with_name("reset_cursor_delta")
+                .writes::<InputSystem>(),
+        );
+    }
+}
+
+fn reset_cursor_delta(world: &World) {
+    world.resource_mut::<InputSystem>().reset_cursor_delta();
+}
+
+/// The state of the keyboard, mouse and gamepad, and of the actions bound to them.
+///
+/// Actions belong to input contexts. The gameplay context is always active,
+/// the others while pushed on top of it, see [`InputSystem::push_context`].
 #[derive(Default)]
 pub struct InputSystem {
-    game_command: u32,
-    cursor_delta: glam::Vec2,
-    last_cursor: glam::Vec2,
+    gameplay: InputContext,
+    contexts: HashMap<String, InputContext>,
+    /// The active contexts above gameplay, topmost last
+    stack: Vec<String>,
+    listening: bool,
+    captured: Option<Binding>,
+    /// Whether each action of each context was pressed at the last [`InputSystem::update`]
+    was_pressed: HashMap<(String, String), bool>,
+    history: InputHistory,
+    keys: ButtonInput<KeyCode>,
+    modifiers: ModifiersState,
+    mouse_buttons: ButtonInput<MouseButton>,
+    cursor_position: glam::Vec2,
+    /// Raw mouse motion since the last [`InputSystem::reset_cursor_delta`]
+    mouse_motion: glam::Vec2,
+    /// The look delta of the last frame, for smoothing
+    last_cursor_delta: glam::Vec2,
+    cursor_mode: CursorMode,
+    mouse_settings: MouseSettings,
+    /// Mouse motion is reported by the device, even to unfocused windows
+    unfocused: bool,
+    scroll_lines: glam::Vec2,
+    scroll_pixels: glam::Vec2,
+    touches: Touches,
+    text_input: TextInput,
+    gamepads: HashMap<GamepadId, Gamepad>,
+    gamepad_settings: GamepadSettings,
+    /// Rumbles waiting to be sent to the gamepad backend
+    rumbles: Vec<(GamepadId, Rumble)>,
 }
 
 impl InputSystem {
-    pub fn game_command(&self) -> u32 {
-        self.game_command
+    /// The bindings of the gameplay context
+    pub fn map(&self) -> &InputMap {
+        self.gameplay.map()
     }
 
-    pub fn cursor_delta(&self) -> glam::Vec2 {
-        self.cursor_delta
+    pub fn map_mut(&mut self) -> &mut InputMap {
+        self.gameplay.map_mut()
     }
 
-    pub fn reset_cursor_delta(&mut self) {
-        self.cursor_delta = glam::Vec2::ZERO;
-    }
-
-    pub fn handle_keyboard_input(&mut self, event: KeyEvent) {
-        let game_command = match event.physical_key {
-            PhysicalKey::Code(code) => match code {
-                KeyCode::KeyW => GameCommand::Forward,
-                KeyCode::KeyS => GameCommand::Backward,
-                KeyCode::KeyA => GameCommand::Left,
-                KeyCode::KeyD => GameCommand::Right,
-                KeyCode::Space => GameCommand::Up,
-                KeyCode::ShiftLeft => GameCommand::Down,
-                _ => GameCommand::Other,
-            },
-            PhysicalKey::Unidentified(_) => GameCommand::Other,
+    /// Adds a gameplay action to both the current and the default bindings
+    pub fn add_default_action(
+        &mut self,
+        name: &str,
+        kind: ActionKind,
+        bindings: impl IntoIterator<Item = Binding>,
+    ) {
+        self.gameplay.add_default_action(name, kind, bindings);
+    }
+
+    pub fn defaults(&self) -> &InputMap {
+        self.gameplay.defaults()
+    }
+
+    /// Resets the bindings of all the contexts
+    pub fn reset_to_defaults(&mut self) {
+        self.gameplay.reset_to_defaults();
+        for context in self.contexts.values_mut() {
+            context.reset_to_defaults();
+        }
+    }
+
+    /// Restores the default bindings of the gameplay action `name`
+    pub fn reset_action(&mut self, name: &str) {
+        self.gameplay.reset_action(name);
+    }
+
+    /// Adds or replaces the context `name`, inactive until pushed
+    pub fn add_context(&mut self, name: &str, context: InputContext) {
+        if name == GAMEPLAY {
+            self.gameplay = context;
+        } else {
+            self.contexts.insert(name.to_string(), context);
+        }
+    }
+
+    pub fn context(&self, name: &str) -> Option<&InputContext> {
+        match name {
+            GAMEPLAY => Some(&self.gameplay),
+            _ => self.contexts.get(name),
+        }
+    }
+
+    pub fn context_mut(&mut self, name: &str) -> Option<&mut InputContext> {
+        match name {
+            GAMEPLAY => Some(&mut self.gameplay),
+            _ => self.contexts.get_mut(name),
+        }
+    }
+
+    /// Activates the context `name` on top of the others, moving it there if
+    /// already active. Panics if there is no such context.
+    pub fn push_context(&mut self, name: &str) {
+        if name == GAMEPLAY {
+            return;
+        }
+        if !self.contexts.contains_key(name) {
+            panic!("[core/input]: no input context called {name:?}");
+        }
+        self.stack.retain(|context| context != name);
+        self.stack.push(name.to_string());
+    }
+
+    /// Deactivates the topmost context, gameplay stays
+    pub fn pop_context(&mut self) -> Option<String> {
+        self.stack.pop()
+    }
+
+    /// Deactivates the context `name` wherever it is in the stack
+    pub fn remove_context_from_stack(&mut self, name: &str) {
+        self.stack.retain(|context| context != name);
+    }
+
+    /// The active contexts, topmost first and gameplay last
+    pub fn active_contexts(&self) -> impl Iterator<Item = &str> {
+        self.stack
+            .iter()
+            .rev()
+            .map(String::as_str)
+            .chain([GAMEPLAY])
+    }
+
+    pub fn is_context_active(&self, name: &str) -> bool {
+        self.active_contexts().any(|context| context == name)
+    }
+
+    /// The active contexts above `name`, `None` if it isn't active
+    fn contexts_above(&self, name: &str) -> Option<impl Iterator<Item = &InputContext>> {
+        let above = match name {
+            GAMEPLAY => 0,
+            _ => self.stack.iter().position(|context| context == name)? + 1,
         };
-        if let GameCommand::Other = game_command {
+        Some(self.stack[above..].iter().map(|name| &self.contexts[name]))
+    }
+
+    /// Whether the context `name` is inactive or under one consuming all the
+    /// inputs, for what reads the inputs without actions, like gestures
+    pub fn is_context_blocked(&self, name: &str) -> bool {
+        self.contexts_above(name)
+            .is_none_or(|mut above| above.any(|context| context.consume() == context::Consume::All))
+    }
+
+    /// Replaces the bindings of the actions saved at `path`, see
+    /// [`InputSystem::save_bindings`]. Contexts not added yet are skipped.
+    pub fn load_bindings(&mut self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        let (gameplay, contexts) = action::load_context_bindings(path)?;
+        self.gameplay.map_mut().merge(gameplay);
+        for (name, map) in contexts {
+            match self.context_mut(&name) {
+                Some(context) => context.map_mut().merge(map),
+                None => println!("[core/input]: skipped the bindings of the context {name:?}"),
+            }
+        }
+        for name in [GAMEPLAY]
+            .into_iter()
+            .chain(self.contexts.keys().map(String::as_str))
+        {
+            for conflict in self.context(name).unwrap().map().conflicts() {
+                println!(
+                    "[core/input]: {:?} with modifiers {:?} and {:?} is bound to {:?} in {name:?}",
+                    conflict.source, conflict.modifiers, conflict.interaction, conflict.actions
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the bindings of all the contexts
+    pub fn save_bindings(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        action::save_context_bindings(
+            self.gameplay.map(),
+            self.contexts
+                .iter()
+                .map(|(name, context)| (name.as_str(), context.map())),
+            path,
+        )
+    }
+
+    /// Captures the next key, mouse button, gamepad button or gamepad axis
+    /// pushed past half way, for rebinding screens, see [`InputSystem::take_captured`]
+    pub fn listen_for_input(&mut self) {
+        self.listening = true;
+        self.captured = None;
+    }
+
+    pub fn cancel_listening(&mut self) {
+        self.listening = false;
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.listening
+    }
+
+    /// The binding captured since [`InputSystem::listen_for_input`], with the modifiers held then
+    pub fn take_captured(&mut self) -> Option<Binding> {
+        self.captured.take()
+    }
+
+    fn capture(&mut self, binding: Binding) {
+        if !self.listening {
             return;
         }
+        self.listening = false;
+        self.captured = Some(if binding.source.takes_modifiers() {
+            binding.with_modifiers(self.modifiers)
+        } else {
+            binding
+        });
+    }
+
+    /// The state of the action `name`, panics if there is no such action.
+    ///
+    /// The topmost active context with the action owns it, then the inactive
+    /// ones, where it reads as released.
+    pub fn action(&self, name: &str) -> ActionState {
+        self.get_action(name)
+            .unwrap_or_else(|| panic!("[core/input]: no action called {name:?}"))
+    }
 
-        match event.state {
-            ElementState::Pressed => self.game_command |= game_command as u32,
-            ElementState::Released => self.game_command &= !(game_command as u32),
+    pub fn get_action(&self, name: &str) -> Option<ActionState> {
+        let context = self
+            .active_contexts()
+            .chain(self.contexts.keys().map(String::as_str))
+            .find(|context| self.context(context).unwrap().map().get(name).is_some())?;
+        self.get_context_action(context, name)
+    }
+
+    /// The state of the action `name` of the context `context`, panics if there is no such action
+    pub fn context_action(&self, context: &str, name: &str) -> ActionState {
+        self.get_context_action(context, name)
+            .unwrap_or_else(|| panic!("[core/input]: no action called {name:?} in {context:?}"))
+    }
+
+    /// Inactive contexts' actions read as released, and sources consumed by
+    /// the contexts above don't count
+    pub fn get_context_action(&self, context: &str, name: &str) -> Option<ActionState> {
+        let action = self.context(context)?.map().get(name)?;
+        let above = self
+            .contexts_above(context)
+            .map(Iterator::collect::<Vec<_>>);
+        let held = |source| self.value(source) != 0.0;
+        let bindings = action.bindings.iter().filter(|binding| {
+            binding.matches_modifiers(self.modifiers)
+                && above.as_ref().is_some_and(|above| {
+                    !above
+                        .iter()
+                        .any(|context| binding.sources().any(|source| context.consumes(source)))
+                })
+                && self
+                    .history
+                    .is_active(binding.source, &binding.interaction, held)
+        });
+        let value = bindings
+            .clone()
+            .map(|binding| {
+                let value = if binding.interaction.is_trigger() {
+                    1.0
+                } else {
+                    self.value(binding.source)
+                };
+                value * binding.direction
+            })
+            .sum::<glam::Vec2>();
+        Some(ActionState {
+            pressed: value != glam::Vec2::ZERO,
+            was_pressed: self
+                .was_pressed
+                .get(&(context.to_string(), name.to_string()))
+                .copied()
+                .unwrap_or_default(),
+            // Triggers are already pulses, and holds and chords only count once active
+            tapped: bindings.clone().any(|binding| {
+                binding.interaction == Interaction::Press && self.just_pressed(binding.source)
+            }),
+            value,
+        })
+    }
+
+    /// The value of `source`, `1.0` for held buttons.
+    ///
+    /// Gamepad sources combine all the gamepads, with their dead zones applied.
+    pub fn value(&self, source: InputSource) -> f32 {
+        let held = match source {
+            // Typing doesn't drive actions
+            InputSource::Key(key) => !self.text_input.is_focused() && self.keys.pressed(key),
+            InputSource::MouseButton(button) => self.mouse_buttons.pressed(button),
+            InputSource::GamepadButton(button) => self
+                .gamepads
+                .values()
+                .any(|gamepad| gamepad.buttons().pressed(button)),
+            InputSource::MouseMotionX => return self.cursor_delta().x,
+            InputSource::MouseMotionY => return self.cursor_delta().y,
+            InputSource::ScrollX => return self.scroll().x,
+            InputSource::ScrollY => return self.scroll().y,
+            // The gamepad pushed the furthest wins
+            InputSource::GamepadAxis(axis) => {
+                return self
+                    .gamepads
+                    .values()
+                    .map(|gamepad| gamepad.axis(axis, &self.gamepad_settings))
+                    .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+                    .unwrap_or_default()
+            }
+        };
+        if held {
+            1.0
+        } else {
+            0.0
         }
     }
 
-    pub fn handle_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
-        if self.game_command & GameCommand::MouseRight as u32 != 0 {
-            self.cursor_delta.x = position.x as f32 - self.last_cursor.x;
-            self.cursor_delta.y = position.y as f32 - self.last_cursor.y;
+    /// Whether a button source was pressed since the last update, even if released since
+    fn just_pressed(&self, source: InputSource) -> bool {
+        match source {
+            InputSource::Key(key) => !self.text_input.is_focused() && self.keys.just_pressed(key),
+            InputSource::MouseButton(button) => self.mouse_buttons.just_pressed(button),
+            InputSource::GamepadButton(button) => self
+                .gamepads
+                .values()
+                .any(|gamepad| gamepad.buttons().just_pressed(button)),
+            _ => false,
         }
-        self.last_cursor.x = position.x as f32;
-        self.last_cursor.y = position.y as f32;
     }
 
-    pub fn handle_mouse_input(&mut self, button: MouseButton, state: ElementState) {
-        let game_command = match button {
-            MouseButton::Left => GameCommand::MouseLeft,
-            MouseButton::Right => GameCommand::MouseRight,
-            _ => GameCommand::Other,
+    /// Ends the input frame `delta` after the previous one, run once per
+    /// rendered frame after all its systems saw the edges: clears the just
+    /// pressed and just released edges, consumes the double taps that
+    /// triggered, and counts `delta` as held time.
+    ///
+    /// A frame can run several ticks or none, so systems acting on the edges
+    /// once belong in [`Stage::PreRender`].
+    pub fn update(&mut self, delta: Duration) {
+        let this = &*self;
+        let was_pressed = [GAMEPLAY]
+            .into_iter()
+            .chain(this.contexts.keys().map(String::as_str))
+            .flat_map(|context| {
+                this.context(context)
+                    .unwrap()
+                    .map()
+                    .iter()
+                    .map(move |(name, _)| {
+                        let pressed = this.context_action(context, name).pressed();
+                        ((context.to_string(), name.to_string()), pressed)
+                    })
+            })
+            .collect();
+        let double_tapped = [&self.gameplay]
+            .into_iter()
+            .chain(self.contexts.values())
+            .flat_map(|context| context.map().iter())
+            .flat_map(|(_, action)| &action.bindings)
+            .filter(|binding| {
+                matches!(binding.interaction, Interaction::DoubleTap(_))
+                    && self
+                        .history
+                        .is_active(binding.source, &binding.interaction, |_| false)
+            })
+            .map(|binding| binding.source)
+            .collect::<Vec<_>>();
+        for source in double_tapped {
+            self.history.consume_double_tap(source);
+        }
+        self.was_pressed = was_pressed;
+        self.history.advance(delta);
+        self.keys.update(delta);
+        self.mouse_buttons.update(delta);
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.buttons_mut().update(delta);
+        }
+        self.touches.update(delta);
+    }
+
+    /// Stamps the inputs handled next with `elapsed` since the last
+    /// [`InputSystem::update`] for the [`Interaction`]s, they happen at the
+    /// update otherwise
+    pub fn set_event_time(&mut self, elapsed: Duration) {
+        self.history.set_time(elapsed);
+    }
+
+    /// The timestamped button presses that interactions are evaluated from
+    pub fn history(&self) -> &InputHistory {
+        &self.history
+    }
+
+    pub fn keys(&self) -> &ButtonInput<KeyCode> {
+        &self.keys
+    }
+
+    pub fn mouse_buttons(&self) -> &ButtonInput<MouseButton> {
+        &self.mouse_buttons
+    }
+
+    pub fn gamepad(&self, id: GamepadId) -> Option<&Gamepad> {
+        self.gamepads.get(&id)
+    }
+
+    /// The connected gamepads, in no particular order
+    pub fn gamepads(&self) -> impl Iterator<Item = (GamepadId, &Gamepad)> {
+        self.gamepads.iter().map(|(id, gamepad)| (*id, gamepad))
+    }
+
+    pub fn gamepad_settings(&self) -> &GamepadSettings {
+        &self.gamepad_settings
+    }
+
+    pub fn gamepad_settings_mut(&mut self) -> &mut GamepadSettings {
+        &mut self.gamepad_settings
+    }
+
+    /// Rumbles the gamepad `id` if its backend supports it, from the next frame on
+    pub fn rumble(&mut self, id: GamepadId, rumble: Rumble) {
+        self.rumbles.push((id, rumble));
+    }
+
+    /// The rumbles requested since the last call, for the gamepad backend
+    pub fn take_rumbles(&mut self) -> Vec<(GamepadId, Rumble)> {
+        std::mem::take(&mut self.rumbles)
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Gives focus to the text input: keys type into it instead of driving
+    /// actions, and the IME is enabled
+    pub fn start_text_input(&mut self) {
+        self.text_input.set_focused(true);
+    }
+
+    pub fn stop_text_input(&mut self) {
+        self.text_input.set_focused(false);
+    }
+
+    pub fn text_input(&self) -> &TextInput {
+        &self.text_input
+    }
+
+    pub fn text_input_mut(&mut self) -> &mut TextInput {
+        &mut self.text_input
+    }
+
+    /// The cursor position in physical pixels from the top left of the window
+    pub fn cursor_position(&self) -> glam::Vec2 {
+        self.cursor_position
+    }
+
+    /// Raw mouse motion this frame, unaffected by the cursor mode and the settings
+    pub fn mouse_motion(&self) -> glam::Vec2 {
+        self.mouse_motion
+    }
+
+    /// The mouse motion this frame to look around with, +Y down, after the
+    /// [`MouseSettings`]. Zero unless the cursor is locked or the free look button is held.
+    pub fn cursor_delta(&self) -> glam::Vec2 {
+        let looking = self.cursor_mode == CursorMode::Locked
+            || self
+                .mouse_settings
+                .free_look_button
+                .is_some_and(|button| self.mouse_buttons.pressed(button));
+        let motion = if looking {
+            self.mouse_motion
+        } else {
+            glam::Vec2::ZERO
         };
-        if let GameCommand::Other = game_command {
+        self.mouse_settings.apply(motion, self.last_cursor_delta)
+    }
+
+    /// Ends the pointer frame, after anything reading the deltas: clears the
+    /// mouse motion, the scroll and the gestures
+    pub fn reset_cursor_delta(&mut self) {
+        self.last_cursor_delta = self.cursor_delta();
+        self.mouse_motion = glam::Vec2::ZERO;
+        self.scroll_lines = glam::Vec2::ZERO;
+        self.scroll_pixels = glam::Vec2::ZERO;
+        self.touches.reset_gestures();
+    }
+
+    /// Wheel scrolling this frame
+    pub fn scroll_lines(&self) -> glam::Vec2 {
+        self.scroll_lines
+    }
+
+    /// Touchpad scrolling this frame
+    pub fn scroll_pixels(&self) -> glam::Vec2 {
+        self.scroll_pixels
+    }
+
+    /// All the scrolling this frame in lines, pixels converted with
+    /// [`MouseSettings::pixels_per_line`]
+    pub fn scroll(&self) -> glam::Vec2 {
+        self.scroll_lines + self.scroll_pixels / self.mouse_settings.pixels_per_line
+    }
+
+    /// The fingers on the screen and the gestures recognized this frame
+    pub fn touches(&self) -> &Touches {
+        &self.touches
+    }
+
+    pub fn touches_mut(&mut self) -> &mut Touches {
+        &mut self.touches
+    }
+
+    pub fn cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
+    pub fn set_cursor_mode(&mut self, cursor_mode: CursorMode) {
+        self.cursor_mode = cursor_mode;
+    }
+
+    pub fn mouse_settings(&self) -> &MouseSettings {
+        &self.mouse_settings
+    }
+
+    pub fn mouse_settings_mut(&mut self) -> &mut MouseSettings {
+        &mut self.mouse_settings
+    }
+
+    pub fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key {
+                key,
+                pressed,
+                repeat,
+                text,
+            } => {
+                self.handle_key(key, pressed, repeat);
+                if pressed {
+                    self.text_input.handle_key(key, text.as_deref());
+                }
+            }
+            InputEvent::Ime(ime) => self.text_input.handle_ime(ime),
+            InputEvent::Modifiers(modifiers) => self.handle_modifiers_changed(modifiers),
+            InputEvent::FocusLost => self.handle_focus_lost(),
+            InputEvent::FocusGained => self.unfocused = false,
+            InputEvent::CursorMoved { x, y } => {
+                self.handle_cursor_moved(PhysicalPosition::new(x, y))
+            }
+            InputEvent::MouseMotion { dx, dy } => {
+                self.handle_mouse_motion(glam::Vec2::new(dx as f32, dy as f32))
+            }
+            InputEvent::MouseButton { button, pressed } => self.handle_mouse_input(
+                button,
+                if pressed {
+                    ElementState::Pressed
+                } else {
+                    ElementState::Released
+                },
+            ),
+            InputEvent::Scroll(delta) => self.handle_scroll(delta),
+            InputEvent::Touch { id, phase, x, y } => {
+                self.touches
+                    .handle_touch(id, phase, glam::Vec2::new(x as f32, y as f32))
+            }
+            InputEvent::PinchGesture(delta) => self.touches.handle_pinch_gesture(delta as f32),
+            InputEvent::RotationGesture(delta) => {
+                self.touches.handle_rotation_gesture(delta.to_radians())
+            }
+            InputEvent::PanGesture { dx, dy } => {
+                self.touches.handle_pan_gesture(glam::Vec2::new(dx, dy))
+            }
+            InputEvent::Gamepad(event) => self.handle_gamepad_event(event),
+        }
+    }
+
+    /// Tracks the key, see [`TextInput::handle_key`] for the text it types
+    pub fn handle_key(&mut self, key: KeyCode, pressed: bool, repeat: bool) {
+        if !pressed {
+            self.keys.release(key);
+            self.history.release(InputSource::Key(key));
             return;
         }
+        if !repeat && !self.text_input.is_focused() {
+            self.capture(Binding::key(key));
+            self.history.press(InputSource::Key(key));
+        }
+        self.keys.press(key);
+    }
+
+    /// Releases everything, as the releases won't be seen while unfocused,
+    /// and frees the cursor
+    pub fn handle_focus_lost(&mut self) {
+        let held = self
+            .keys
+            .get_pressed()
+            .map(InputSource::Key)
+            .chain(
+                self.mouse_buttons
+                    .get_pressed()
+                    .map(InputSource::MouseButton),
+            )
+            .collect::<Vec<_>>();
+        for source in held {
+            self.history.release(source);
+        }
+        self.keys.release_all();
+        self.mouse_buttons.release_all();
+        self.modifiers = ModifiersState::empty();
+        self.cursor_mode = CursorMode::Free;
+        self.mouse_motion = glam::Vec2::ZERO;
+        self.touches.cancel_all();
+        self.unfocused = true;
+    }
+
+    pub fn handle_modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn handle_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        self.cursor_position = glam::Vec2::new(position.x as f32, position.y as f32);
+    }
 
+    /// Raw motion, which keeps going at the window edges and while the cursor is locked
+    pub fn handle_mouse_motion(&mut self, delta: glam::Vec2) {
+        if !self.unfocused {
+            self.mouse_motion += delta;
+        }
+    }
+
+    pub fn handle_scroll(&mut self, delta: ScrollDelta) {
+        match delta {
+            ScrollDelta::Lines { x, y } => self.scroll_lines += glam::Vec2::new(x, y),
+            ScrollDelta::Pixels { x, y } => {
+                self.scroll_pixels += glam::Vec2::new(x as f32, y as f32)
+            }
+        }
+    }
+
+    pub fn handle_mouse_input(&mut self, button: MouseButton, state: ElementState) {
         match state {
-            ElementState::Pressed => self.game_command |= game_command as u32,
-            ElementState::Released => self.game_command &= !(game_command as u32),
+            ElementState::Pressed => {
+                self.capture(Binding::mouse_button(button));
+                self.mouse_buttons.press(button);
+                self.history.press(InputSource::MouseButton(button));
+            }
+            ElementState::Released => {
+                self.mouse_buttons.release(button);
+                self.history.release(InputSource::MouseButton(button));
+            }
+        }
+    }
+
+    pub fn handle_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected { id, name } => {
+                println!("[core/input]: gamepad {id:?} connected: {name}");
+                self.gamepads.insert(id, Gamepad::new(name));
+            }
+            GamepadEvent::Disconnected { id } => {
+                println!("[core/input]: gamepad {id:?} disconnected");
+                self.gamepads.remove(&id);
+            }
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            } => {
+                let buttons = self.gamepads.entry(id).or_default().buttons_mut();
+                if !pressed {
+                    buttons.release(button);
+                    // Held on another gamepad
+                    if !self
+                        .gamepads
+                        .values()
+                        .any(|gamepad| gamepad.buttons().pressed(button))
+                    {
+                        self.history.release(InputSource::GamepadButton(button));
+                    }
+                } else if !buttons.pressed(button) {
+                    buttons.press(button);
+                    self.history.press(InputSource::GamepadButton(button));
+                    self.capture(Binding::gamepad_button(button));
+                }
+            }
+            GamepadEvent::Axis { id, axis, value } => {
+                let gamepad = self.gamepads.entry(id).or_default();
+                gamepad.set_raw_axis(axis, value);
+                let value = gamepad.axis(axis, &self.gamepad_settings);
+                if value.abs() > 0.5 {
+                    self.capture(Binding::gamepad_axis(axis).with_scale(value.signum()));
+                }
+            }
         }
     }
 }
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use winit::event::MouseButton;
+
+/// How the cursor is held by the window, applied by [`Core`](crate::Core) each frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CursorMode {
+    #[default]
+    Free,
+    /// Kept inside the window and visible, e.g. for edge scrolling
+    Confined,
+    /// Hidden and held in place, mouse motion always turns the camera.
+    ///
+    /// Confined instead on the platforms that can't lock it.
+    Locked,
+}
+
+/// How far the content scrolls, +Y away from the user (up on a wheel)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScrollDelta {
+    /// Wheel notches
+    Lines { x: f32, y: f32 },
+    /// Touchpads, in physical pixels
+    Pixels { x: f64, y: f64 },
+}
+
+/// How raw mouse motion becomes the look delta, see [`InputSystem::cursor_delta`](super::InputSystem::cursor_delta)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MouseSettings {
+    /// Multiplies the raw motion, the default turns the fly camera 0.3 degrees per count
+    pub sensitivity: f32,
+    pub invert_y: bool,
+    /// How much of the previous frame's delta is kept, `0.0` for none, below `1.0`.
+    ///
+    /// Smoothing is per frame, so it lasts longer at higher frame rates.
+    pub smoothing: f32,
+    /// While the cursor isn't locked, motion only counts while this is held
+    pub free_look_button: Option<MouseButton>,
+    /// How many pixels of a touchpad scroll make a line of a wheel scroll
+    pub pixels_per_line: f32,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.3,
+            invert_y: false,
+            smoothing: 0.0,
+            free_look_button: Some(MouseButton::Right),
+            pixels_per_line: 20.0,
+        }
+    }
+}
+
+impl MouseSettings {
+    /// Scales and inverts `motion`, smoothing it with `previous`, the last frame's result
+    pub fn apply(&self, motion: glam::Vec2, previous: glam::Vec2) -> glam::Vec2 {
+        let mut motion = motion * self.sensitivity;
+        if self.invert_y {
+            motion.y = -motion.y;
+        }
+        previous.lerp(motion, 1.0 - self.smoothing.clamp(0.0, 0.99))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::TouchPhase;
+
+    use super::*;
+    use crate::input::{
+        action::{ActionKind, Binding, InputSource},
+        event::InputEvent,
+        InputSystem,
+    };
+
+    #[test]
+    fn scroll_adds_lines_and_pixels() {
+        let mut input_system = InputSystem::default();
+        input_system.add_default_action(
+            "zoom",
+            ActionKind::Axis,
+            [Binding::new(InputSource::ScrollY)],
+        );
+        input_system.handle_input(InputEvent::Scroll(ScrollDelta::Lines { x: 0.0, y: 1.0 }));
+        input_system.handle_input(InputEvent::Scroll(ScrollDelta::Lines { x: 0.0, y: 1.0 }));
+        input_system.handle_input(InputEvent::Scroll(ScrollDelta::Pixels {
+            x: 10.0,
+            y: -10.0,
+        }));
+        assert_eq!(input_system.scroll_lines(), glam::Vec2::new(0.0, 2.0));
+        assert_eq!(input_system.scroll_pixels(), glam::Vec2::new(10.0, -10.0));
+        assert_eq!(input_system.scroll(), glam::Vec2::new(0.5, 1.5));
+        assert_eq!(input_system.action("zoom").value(), 1.5);
+
+        input_system.reset_cursor_delta();
+        assert_eq!(input_system.scroll(), glam::Vec2::ZERO);
+        assert_eq!(input_system.action("zoom").value(), 0.0);
+    }
+
+    #[test]
+    fn touches_reach_the_gestures() {
+        let mut input_system = InputSystem::default();
+        let touch = |id, phase, x, y| InputEvent::Touch { id, phase, x, y };
+        input_system.handle_input(touch(0, TouchPhase::Started, 0.0, 0.0));
+        input_system.handle_input(touch(0, TouchPhase::Moved, 0.0, 40.0));
+        input_system.handle_input(InputEvent::PinchGesture(1.0));
+        input_system.handle_input(InputEvent::RotationGesture(90.0));
+        let gestures = input_system.touches().gestures();
+        assert_eq!(gestures.drag, glam::Vec2::new(0.0, 40.0));
+        assert_eq!(gestures.pinch, 2.0);
+        assert_eq!(gestures.rotation, std::f32::consts::FRAC_PI_2);
+
+        // Fingers stay down across frames, gestures don't
+        input_system.reset_cursor_delta();
+        assert_eq!(input_system.touches().len(), 1);
+        assert_eq!(input_system.touches().gestures().drag, glam::Vec2::ZERO);
+    }
+}
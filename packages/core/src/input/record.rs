@@ -0,0 +1,187 @@
+use std::{path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::asset::{self, AssetError};
+
+use super::event::InputEvent;
+
+/// The version [`InputRecording`]s are saved with
+pub const RECORDING_VERSION: u32 = 2;
+
+/// One frame of an [`InputRecording`], with the inputs received before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// The inputs, with their time since the input frame began at the
+    /// previous render
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<(Duration, InputEvent)>,
+    pub frame_time: Duration,
+    /// How long after the previous one the input frame ended, see
+    /// [`InputSystem::update`](super::InputSystem::update)
+    #[serde(default)]
+    pub input_delta: Duration,
+    /// The delta time of each simulation tick of the frame
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ticks: Vec<Duration>,
+    /// The interpolation the frame was rendered with
+    pub alpha: f32,
+}
+
+/// The input stream and frame timings of a session, saved as RON.
+///
+/// Replaying it with [`Core::replay_frame`](crate::Core::replay_frame) runs
+/// the same frames and ticks with the same inputs in between, so a game that
+/// only depends on those plays out the same.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputRecording {
+    pub version: u32,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Default for InputRecording {
+    fn default() -> Self {
+        Self {
+            version: RECORDING_VERSION,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl InputRecording {
+    pub fn from_ron(ron: &str) -> Result<Self, AssetError> {
+        let recording: InputRecording = asset::from_ron(ron)?;
+        recording.migrate()
+    }
+
+    pub fn to_ron(&self) -> Result<String, AssetError> {
+        asset::to_ron(self)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        let recording: InputRecording = asset::load_ron(path)?;
+        recording.migrate()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        asset::save_ron(self, path)
+    }
+
+    fn migrate(mut self) -> Result<Self, AssetError> {
+        match self.version {
+            // Inputs were timed from the start of the recording, and the
+            // input frames ended with the frames
+            1 => {
+                let mut frame_start = Duration::ZERO;
+                for frame in &mut self.frames {
+                    for (time, _) in &mut frame.inputs {
+                        *time = time.saturating_sub(frame_start);
+                    }
+                    frame.input_delta = frame.frame_time;
+                    frame_start += frame.frame_time;
+                }
+                self.version = RECORDING_VERSION;
+                Ok(self)
+            }
+            RECORDING_VERSION => Ok(self),
+            version => Err(AssetError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// The total frame time
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.frame_time).sum()
+    }
+}
+
+/// Builds an [`InputRecording`] from what [`Core`](crate::Core) goes through
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    /// The inputs received since the last frame began
+    inputs: Vec<(Duration, InputEvent)>,
+    recording: InputRecording,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `elapsed` is the time since the input frame began
+    pub fn record_input(&mut self, elapsed: Duration, event: InputEvent) {
+        self.inputs.push((elapsed, event));
+    }
+
+    pub fn begin_frame(&mut self, frame_time: Duration) {
+        self.recording.frames.push(RecordedFrame {
+            inputs: std::mem::take(&mut self.inputs),
+            frame_time,
+            input_delta: Duration::ZERO,
+            ticks: Vec::new(),
+            alpha: 0.0,
+        });
+    }
+
+    /// Ticks and renders before the first frame aren't recorded
+    pub fn record_tick(&mut self, delta: Duration) {
+        if let Some(frame) = self.recording.frames.last_mut() {
+            frame.ticks.push(delta);
+        }
+    }
+
+    pub fn record_render(&mut self, alpha: f32, input_delta: Duration) {
+        if let Some(frame) = self.recording.frames.last_mut() {
+            frame.alpha = alpha;
+            frame.input_delta = input_delta;
+        }
+    }
+
+    /// The recording so far, the inputs after the last frame are left out
+    pub fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::KeyCode;
+
+    use super::*;
+
+    fn frame(inputs: Vec<(Duration, InputEvent)>) -> RecordedFrame {
+        RecordedFrame {
+            inputs,
+            frame_time: Duration::from_millis(20),
+            input_delta: Duration::ZERO,
+            ticks: Vec::new(),
+            alpha: 0.0,
+        }
+    }
+
+    #[test]
+    fn migrates_version_1_input_times() {
+        let press = InputEvent::Key {
+            key: KeyCode::KeyW,
+            pressed: true,
+            repeat: false,
+            text: None,
+        };
+        let recording = InputRecording {
+            version: 1,
+            frames: vec![
+                frame(vec![(Duration::from_millis(5), press.clone())]),
+                frame(Vec::new()),
+                frame(vec![(Duration::from_millis(47), press.clone())]),
+            ],
+        };
+        let recording = InputRecording::from_ron(&recording.to_ron().unwrap()).unwrap();
+        assert_eq!(recording.version, RECORDING_VERSION);
+        assert_eq!(recording.frames[0].inputs[0].0, Duration::from_millis(5));
+        assert_eq!(recording.frames[2].inputs[0].0, Duration::from_millis(7));
+        assert!(recording
+            .frames
+            .iter()
+            .all(|frame| frame.input_delta == Duration::from_millis(20)));
+    }
+}
@@ -0,0 +1,276 @@
+use winit::{event::Ime, keyboard::KeyCode};
+
+/// Text being composed in the IME, shown at the cursor until committed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preedit {
+    pub text: String,
+    /// The selected byte range of `text`, `None` to hide the cursor
+    pub cursor: Option<(usize, usize)>,
+}
+
+/// A line of text edited by the keyboard and the IME, for consoles and text boxes.
+///
+/// While it has focus, keys stop driving actions, see [`InputSystem::start_text_input`](super::InputSystem::start_text_input).
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    focused: bool,
+    text: String,
+    /// Byte offset in `text`, always on a char boundary
+    cursor: usize,
+    preedit: Option<Preedit>,
+    /// Lines submitted with Enter, oldest first
+    submitted: Vec<String>,
+    /// Where the IME should show its candidates, in physical pixels
+    ime_cursor_area: Option<(glam::Vec2, glam::Vec2)>,
+}
+
+impl TextInput {
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        if !focused {
+            self.preedit = None;
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, moving the cursor to its end
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text(String::new());
+    }
+
+    /// The cursor as a byte offset into [`TextInput::text`]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The text being composed, to draw at the cursor
+    pub fn preedit(&self) -> Option<&Preedit> {
+        self.preedit.as_ref()
+    }
+
+    /// The oldest line submitted with Enter and not taken yet
+    pub fn take_submitted(&mut self) -> Option<String> {
+        (!self.submitted.is_empty()).then(|| self.submitted.remove(0))
+    }
+
+    pub fn ime_cursor_area(&self) -> Option<(glam::Vec2, glam::Vec2)> {
+        self.ime_cursor_area
+    }
+
+    /// Tells the IME where the text cursor is drawn, so that its candidate
+    /// window doesn't cover it
+    pub fn set_ime_cursor_area(&mut self, position: glam::Vec2, size: glam::Vec2) {
+        self.ime_cursor_area = Some((position, size));
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        let text = text.chars().filter(|c| !c.is_control()).collect::<String>();
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Handles a key press, `text` being what it types
+    pub fn handle_key(&mut self, key: KeyCode, text: Option<&str>) {
+        // Keys edit the composition while there is one
+        if !self.focused || self.preedit.is_some() {
+            return;
+        }
+        match key {
+            KeyCode::Backspace => {
+                if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                    self.text.remove(self.cursor);
+                }
+            }
+            KeyCode::Delete => {
+                if self.cursor < self.text.len() {
+                    self.text.remove(self.cursor);
+                }
+            }
+            KeyCode::ArrowLeft => {
+                if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                }
+            }
+            KeyCode::ArrowRight => {
+                if let Some(c) = self.text[self.cursor..].chars().next() {
+                    self.cursor += c.len_utf8();
+                }
+            }
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Enter | KeyCode::NumpadEnter => {
+                self.submitted.push(std::mem::take(&mut self.text));
+                self.cursor = 0;
+            }
+            _ => {
+                if let Some(text) = text {
+                    self.insert(text);
+                }
+            }
+        }
+    }
+
+    pub fn handle_ime(&mut self, ime: Ime) {
+        if !self.focused {
+            return;
+        }
+        match ime {
+            Ime::Preedit(text, cursor) => {
+                self.preedit = (!text.is_empty()).then_some(Preedit { text, cursor });
+            }
+            Ime::Commit(text) => {
+                self.preedit = None;
+                self.insert(&text);
+            }
+            Ime::Enabled | Ime::Disabled => self.preedit = None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{
+        action::{ActionKind, Binding},
+        event::InputEvent,
+        InputSystem,
+    };
+
+    fn focused(text: &str) -> TextInput {
+        let mut input = TextInput::default();
+        input.set_focused(true);
+        input.set_text(text);
+        input
+    }
+
+    fn keys(input: &mut TextInput, keys: &[KeyCode]) {
+        for &key in keys {
+            input.handle_key(key, None);
+        }
+    }
+
+    #[test]
+    fn moves_the_cursor_by_chars() {
+        let mut input = focused("a中b");
+        assert_eq!(input.cursor(), 5);
+        keys(&mut input, &[KeyCode::ArrowLeft]);
+        assert_eq!(input.cursor(), 4);
+        keys(&mut input, &[KeyCode::ArrowLeft]);
+        assert_eq!(input.cursor(), 1);
+        keys(&mut input, &[KeyCode::ArrowLeft, KeyCode::ArrowLeft]);
+        assert_eq!(input.cursor(), 0);
+        keys(&mut input, &[KeyCode::ArrowRight, KeyCode::ArrowRight]);
+        assert_eq!(input.cursor(), 4);
+        keys(&mut input, &[KeyCode::Home]);
+        assert_eq!(input.cursor(), 0);
+        keys(&mut input, &[KeyCode::End, KeyCode::ArrowRight]);
+        assert_eq!(input.cursor(), 5);
+
+        keys(&mut input, &[KeyCode::ArrowLeft]);
+        input.handle_key(KeyCode::KeyX, Some("文"));
+        assert_eq!(input.text(), "a中文b");
+        assert_eq!(input.cursor(), 7);
+    }
+
+    #[test]
+    fn backspace_and_delete_remove_whole_chars() {
+        let mut input = focused("你好x");
+        keys(&mut input, &[KeyCode::Backspace]);
+        assert_eq!(input.text(), "你好");
+        keys(&mut input, &[KeyCode::ArrowLeft, KeyCode::Delete]);
+        assert_eq!(input.text(), "你");
+        assert_eq!(input.cursor(), 3);
+        keys(&mut input, &[KeyCode::Delete]);
+        assert_eq!(input.text(), "你");
+        keys(&mut input, &[KeyCode::Backspace, KeyCode::Backspace]);
+        assert_eq!(input.text(), "");
+        assert_eq!(input.cursor(), 0);
+    }
+
+    #[test]
+    fn preedit_suppresses_key_edits() {
+        let mut input = focused("ab");
+        input.handle_ime(Ime::Preedit("ni".to_string(), Some((2, 2))));
+        keys(&mut input, &[KeyCode::Backspace, KeyCode::ArrowLeft]);
+        input.handle_key(KeyCode::KeyH, Some("h"));
+        assert_eq!(input.text(), "ab");
+        assert_eq!(input.cursor(), 2);
+        assert_eq!(input.preedit().unwrap().text, "ni");
+
+        input.handle_ime(Ime::Commit("你".to_string()));
+        assert_eq!(input.preedit(), None);
+        assert_eq!(input.text(), "ab你");
+        keys(&mut input, &[KeyCode::Backspace]);
+        assert_eq!(input.text(), "ab");
+
+        // An empty preedit ends the composition
+        input.handle_ime(Ime::Preedit("h".to_string(), None));
+        input.handle_ime(Ime::Preedit(String::new(), None));
+        assert_eq!(input.preedit(), None);
+        input.handle_ime(Ime::Preedit("h".to_string(), None));
+        input.set_focused(false);
+        assert_eq!(input.preedit(), None);
+    }
+
+    #[test]
+    fn submits_lines_with_enter() {
+        let mut input = focused("");
+        input.handle_key(KeyCode::KeyA, Some("a"));
+        input.handle_ime(Ime::Commit("\tb".to_string()));
+        keys(&mut input, &[KeyCode::Enter]);
+        assert_eq!(input.text(), "");
+        assert_eq!(input.cursor(), 0);
+        input.handle_key(KeyCode::KeyC, Some("c"));
+        keys(&mut input, &[KeyCode::NumpadEnter]);
+
+        assert_eq!(input.take_submitted().as_deref(), Some("ab"));
+        assert_eq!(input.take_submitted().as_deref(), Some("c"));
+        assert_eq!(input.take_submitted(), None);
+    }
+
+    #[test]
+    fn ignores_input_without_focus() {
+        let mut input = TextInput::default();
+        input.handle_key(KeyCode::KeyA, Some("a"));
+        input.handle_ime(Ime::Commit("你".to_string()));
+        keys(&mut input, &[KeyCode::Enter]);
+        assert_eq!(input.text(), "");
+        assert_eq!(input.take_submitted(), None);
+    }
+
+    #[test]
+    fn focus_suppresses_actions() {
+        let mut input_system = InputSystem::default();
+        input_system.add_default_action("jump", ActionKind::Button, [Binding::key(KeyCode::Space)]);
+        let space = |pressed| InputEvent::Key {
+            key: KeyCode::Space,
+            pressed,
+            repeat: false,
+            text: pressed.then(|| " ".to_string()),
+        };
+
+        input_system.start_text_input();
+        input_system.handle_input(space(true));
+        assert!(!input_system.action("jump").pressed());
+        assert_eq!(input_system.text_input().text(), " ");
+        input_system.handle_input(space(false));
+
+        input_system.stop_text_input();
+        input_system.handle_input(space(true));
+        assert!(input_system.action("jump").just_pressed());
+        assert_eq!(input_system.text_input().text(), " ");
+    }
+}
@@ -0,0 +1,307 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use winit::event::TouchPhase;
+
+/// A finger on the screen, identified by the id it keeps until lifted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    pub id: u64,
+    /// Where the finger went down, in physical pixels
+    pub start_position: glam::Vec2,
+    pub position: glam::Vec2,
+    /// How long the finger has been down, counted in frames like held buttons
+    pub duration: Duration,
+    /// Whether it went further than [`GestureSettings::tap_distance`] or
+    /// joined other fingers, so it isn't a tap
+    pub moved: bool,
+}
+
+/// The thresholds telling taps from drags
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GestureSettings {
+    /// Taps are released before this
+    pub tap_duration: Duration,
+    /// And move less than this, in physical pixels
+    pub tap_distance: f32,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        Self {
+            tap_duration: Duration::from_millis(300),
+            tap_distance: 10.0,
+        }
+    }
+}
+
+/// The gestures recognized this frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gestures {
+    /// Where each tap ended
+    pub taps: Vec<glam::Vec2>,
+    /// One finger (or trackpad pan) movement in physical pixels
+    pub drag: glam::Vec2,
+    /// How much the distance between two fingers grew, `1.0` for none, above
+    /// `1.0` to zoom in
+    pub pinch: f32,
+    /// Two finger rotation in radians, counterclockwise on screen
+    pub rotation: f32,
+}
+
+impl Default for Gestures {
+    fn default() -> Self {
+        Self {
+            taps: Vec::new(),
+            drag: glam::Vec2::ZERO,
+            pinch: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+/// Tracks the fingers on the screen and recognizes taps, drags, pinches and
+/// two finger rotations from them.
+///
+/// The touchpad gestures some platforms report on their own add to the same
+/// [`Gestures`].
+#[derive(Debug, Clone, Default)]
+pub struct Touches {
+    touches: BTreeMap<u64, Touch>,
+    gestures: Gestures,
+    settings: GestureSettings,
+}
+
+impl Touches {
+    pub fn get(&self, id: u64) -> Option<&Touch> {
+        self.touches.get(&id)
+    }
+
+    /// The fingers down, ordered by id
+    pub fn iter(&self) -> impl Iterator<Item = &Touch> {
+        self.touches.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.touches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.touches.is_empty()
+    }
+
+    pub fn gestures(&self) -> &Gestures {
+        &self.gestures
+    }
+
+    pub fn settings(&self) -> &GestureSettings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut GestureSettings {
+        &mut self.settings
+    }
+
+    pub fn handle_touch(&mut self, id: u64, phase: TouchPhase, position: glam::Vec2) {
+        match phase {
+            TouchPhase::Started => {
+                let alone = self.touches.is_empty();
+                for touch in self.touches.values_mut() {
+                    touch.moved = true;
+                }
+                self.touches.insert(
+                    id,
+                    Touch {
+                        id,
+                        start_position: position,
+                        position,
+                        duration: Duration::ZERO,
+                        moved: !alone,
+                    },
+                );
+            }
+            TouchPhase::Moved => self.move_touch(id, position),
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.move_touch(id, position);
+                let Some(touch) = self.touches.remove(&id) else {
+                    return;
+                };
+                if phase == TouchPhase::Ended
+                    && !touch.moved
+                    && touch.duration < self.settings.tap_duration
+                {
+                    self.gestures.taps.push(touch.position);
+                }
+            }
+        }
+    }
+
+    fn move_touch(&mut self, id: u64, position: glam::Vec2) {
+        // The other finger of a two finger gesture, before this one moves
+        let other = self
+            .touches
+            .values()
+            .find(|touch| touch.id != id)
+            .map(|touch| touch.position);
+        let count = self.touches.len();
+        let tap_distance = self.settings.tap_distance;
+        let Some(touch) = self.touches.get_mut(&id) else {
+            return;
+        };
+        let previous = touch.position;
+        touch.position = position;
+        touch.moved |= position.distance(touch.start_position) >= tap_distance;
+
+        match (count, other) {
+            (1, _) if touch.moved => self.gestures.drag += position - previous,
+            (2, Some(other)) => {
+                let (before, after) = (previous - other, position - other);
+                if before.length() > f32::EPSILON && after.length() > f32::EPSILON {
+                    self.gestures.pinch *= after.length() / before.length();
+                    // Screen +Y is down, so flip it for counterclockwise
+                    self.gestures.rotation -= before.angle_between(after);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// A touchpad pinch, positive to zoom in
+    pub fn handle_pinch_gesture(&mut self, delta: f32) {
+        if delta.is_finite() {
+            self.gestures.pinch *= 1.0 + delta;
+        }
+    }
+
+    /// A touchpad rotation in radians, counterclockwise
+    pub fn handle_rotation_gesture(&mut self, delta: f32) {
+        self.gestures.rotation += delta;
+    }
+
+    /// A touchpad pan in physical pixels
+    pub fn handle_pan_gesture(&mut self, delta: glam::Vec2) {
+        self.gestures.drag += delta;
+    }
+
+    /// Counts `delta` as the time the fingers have been down
+    pub fn update(&mut self, delta: Duration) {
+        for touch in self.touches.values_mut() {
+            touch.duration += delta;
+        }
+    }
+
+    /// Clears the gestures of the frame
+    pub fn reset_gestures(&mut self) {
+        self.gestures = Gestures::default();
+    }
+
+    /// Lifts all the fingers without taps, e.g. when the window loses focus
+    pub fn cancel_all(&mut self) {
+        self.touches.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn tap() {
+        let mut touches = Touches::default();
+        touches.handle_touch(3, TouchPhase::Started, glam::Vec2::new(100.0, 100.0));
+        touches.update(Duration::from_millis(100));
+        touches.handle_touch(3, TouchPhase::Moved, glam::Vec2::new(104.0, 100.0));
+        assert_eq!(touches.get(3).unwrap().duration, Duration::from_millis(100));
+        touches.handle_touch(3, TouchPhase::Ended, glam::Vec2::new(104.0, 100.0));
+        assert!(touches.is_empty());
+        assert_eq!(touches.gestures().taps, [glam::Vec2::new(104.0, 100.0)]);
+        // Small moves aren't drags
+        assert_eq!(touches.gestures().drag, glam::Vec2::ZERO);
+
+        touches.reset_gestures();
+        assert_eq!(touches.gestures(), &Gestures::default());
+    }
+
+    #[test]
+    fn long_presses_and_cancels_are_not_taps() {
+        let mut touches = Touches::default();
+        touches.handle_touch(0, TouchPhase::Started, glam::Vec2::ZERO);
+        touches.update(Duration::from_millis(400));
+        touches.handle_touch(0, TouchPhase::Ended, glam::Vec2::ZERO);
+        touches.handle_touch(1, TouchPhase::Started, glam::Vec2::ZERO);
+        touches.handle_touch(1, TouchPhase::Cancelled, glam::Vec2::ZERO);
+        assert!(touches.gestures().taps.is_empty());
+    }
+
+    #[test]
+    fn drag() {
+        let mut touches = Touches::default();
+        touches.handle_touch(0, TouchPhase::Started, glam::Vec2::ZERO);
+        touches.handle_touch(0, TouchPhase::Moved, glam::Vec2::new(5.0, 0.0));
+        assert_eq!(touches.gestures().drag, glam::Vec2::ZERO);
+        // Past the tap distance, the whole move counts
+        touches.handle_touch(0, TouchPhase::Moved, glam::Vec2::new(20.0, -10.0));
+        assert_eq!(touches.gestures().drag, glam::Vec2::new(15.0, -10.0));
+        touches.handle_touch(0, TouchPhase::Ended, glam::Vec2::new(30.0, -10.0));
+        assert_eq!(touches.gestures().drag, glam::Vec2::new(25.0, -10.0));
+        assert!(touches.gestures().taps.is_empty());
+    }
+
+    #[test]
+    fn pinch() {
+        let mut touches = Touches::default();
+        touches.handle_touch(0, TouchPhase::Started, glam::Vec2::new(0.0, 0.0));
+        touches.handle_touch(1, TouchPhase::Started, glam::Vec2::new(100.0, 0.0));
+        touches.handle_touch(1, TouchPhase::Moved, glam::Vec2::new(200.0, 0.0));
+        touches.handle_touch(0, TouchPhase::Moved, glam::Vec2::new(-100.0, 0.0));
+        assert_close(touches.gestures().pinch, 3.0);
+        assert_close(touches.gestures().rotation, 0.0);
+        assert_eq!(touches.gestures().drag, glam::Vec2::ZERO);
+
+        // Neither finger of a pinch taps
+        touches.handle_touch(0, TouchPhase::Ended, glam::Vec2::new(-100.0, 0.0));
+        touches.handle_touch(1, TouchPhase::Ended, glam::Vec2::new(200.0, 0.0));
+        assert!(touches.gestures().taps.is_empty());
+    }
+
+    #[test]
+    fn rotate() {
+        let mut touches = Touches::default();
+        touches.handle_touch(0, TouchPhase::Started, glam::Vec2::ZERO);
+        touches.handle_touch(1, TouchPhase::Started, glam::Vec2::new(100.0, 0.0));
+        // Up on screen is -Y, so this turns counterclockwise
+        touches.handle_touch(1, TouchPhase::Moved, glam::Vec2::new(0.0, -100.0));
+        assert_close(touches.gestures().rotation, std::f32::consts::FRAC_PI_2);
+        assert_close(touches.gestures().pinch, 1.0);
+    }
+
+    #[test]
+    fn touchpad_gestures_add_up() {
+        let mut touches = Touches::default();
+        touches.handle_pinch_gesture(0.5);
+        touches.handle_pinch_gesture(1.0);
+        touches.handle_rotation_gesture(0.25);
+        touches.handle_pan_gesture(glam::Vec2::new(3.0, 4.0));
+        assert_close(touches.gestures().pinch, 3.0);
+        assert_close(touches.gestures().rotation, 0.25);
+        assert_eq!(touches.gestures().drag, glam::Vec2::new(3.0, 4.0));
+    }
+
+    #[test]
+    fn stable_ids() {
+        let mut touches = Touches::default();
+        touches.handle_touch(7, TouchPhase::Started, glam::Vec2::ZERO);
+        touches.handle_touch(2, TouchPhase::Started, glam::Vec2::ONE);
+        touches.handle_touch(7, TouchPhase::Ended, glam::Vec2::ZERO);
+        touches.handle_touch(4, TouchPhase::Started, glam::Vec2::ZERO);
+        assert_eq!(
+            touches.iter().map(|touch| touch.id).collect::<Vec<_>>(),
+            [2, 4]
+        );
+        assert_eq!(touches.get(2).unwrap().start_position, glam::Vec2::ONE);
+    }
+}
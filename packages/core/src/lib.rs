@@ -1,75 +1,410 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use input::InputSystem;
-use render::{camera::Camera, Renderer};
-use winit::{dpi::PhysicalSize, event::WindowEvent, window::Window};
+use asset::{
+    prefab::{PrefabAsset, PrefabOverrides, Prefabs},
+    scene::SceneAsset,
+    AssetError,
+};
 
+use ecs::{
+    components::PreviousTransform, Component, Entity, Events, IntoSystemConfig, Schedule, Stage,
+    World,
+};
+use input::{
+    event::InputEvent,
+    gamepad::{GamepadBackend, GamepadEvent},
+    mouse::CursorMode,
+    record::{InputRecorder, InputRecording, RecordedFrame},
+    GamepadConnected, GamepadDisconnected, InputSystem, KeyPressed, KeyReleased,
+    MouseButtonPressed, MouseButtonReleased,
+};
+use render::{camera::Camera, queue::CullingStats, transform::Transform, Renderer};
+use runtime::WindowResized;
+use time::Time;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{DeviceEvent, WindowEvent},
+    window::{CursorGrabMode, Window},
+};
+
+pub mod app;
+pub mod asset;
+pub mod ecs;
 pub mod input;
 pub mod render;
 pub mod runtime;
+pub mod time;
 
 pub struct Core {
+    window: Arc<Window>,
+    /// The mode last applied to the window
+    cursor_mode: CursorMode,
+    ime_allowed: bool,
+    ime_cursor_area: Option<(glam::Vec2, glam::Vec2)>,
     renderer: Renderer,
-    input_system: InputSystem,
-    camera: Camera,
+    world: World,
+    schedule: Schedule,
+    /// Swap the buffers of each registered [`Events`] resource
+    event_updaters: Vec<fn(&World)>,
+    /// Whether a tick ran since the events were last updated
+    ticked: bool,
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    recorder: Option<InputRecorder>,
+    /// When the input frame began, the inputs are stamped with the time since
+    input_frame_start: Instant,
 }
 
 impl Core {
     pub fn new(window: Arc<Window>) -> Self {
-        let renderer = Renderer::new(window);
-        let camera = Camera::new(
-            glam::Vec3 {
-                x: -3.0,
-                y: 0.0,
-                z: -3.0,
-            },
-            90.0,
-            16.0 / 9.0,
-            0.1,
-            100.0,
-        );
-
-        let input_system = InputSystem::default();
-        Core {
+        let renderer = Renderer::new(window.clone());
+
+        let mut world = World::new();
+        world.insert_resource(InputSystem::default());
+        world.insert_resource(Time::default());
+        world.insert_resource(Prefabs::default());
+        world.insert_resource(CullingStats::default());
+
+        let mut core = Core {
+            window,
+            cursor_mode: CursorMode::Free,
+            ime_allowed: false,
+            ime_cursor_area: None,
             renderer,
-            camera,
-            input_system,
+            world,
+            schedule: Schedule::new(),
+            event_updaters: Vec::new(),
+            ticked: false,
+            gamepad_backend: None,
+            recorder: None,
+            input_frame_start: Instant::now(),
+        };
+        core.add_event::<WindowResized>();
+        core.add_event::<KeyPressed>();
+        core.add_event::<KeyReleased>();
+        core.add_event::<MouseButtonPressed>();
+        core.add_event::<MouseButtonReleased>();
+        core.add_event::<GamepadConnected>();
+        core.add_event::<GamepadDisconnected>();
+        core
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn renderer_mut(&mut self) -> &mut Renderer {
+        &mut self.renderer
+    }
+
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+
+    /// Unordered systems of a stage run in the order they are added
+    pub fn add_system(&mut self, stage: Stage, system: impl IntoSystemConfig) {
+        self.schedule.add_system(stage, system);
+    }
+
+    /// Adds the [`Events<T>`] resource, swapped at the start of the frames that
+    /// follow a tick, and of every frame while the game time is frozen
+    pub fn add_event<T: Component>(&mut self) {
+        if self.world.contains_resource::<Events<T>>() {
+            return;
         }
+        self.world.insert_resource(Events::<T>::new());
+        self.event_updaters
+            .push(|world| world.resource_mut::<Events<T>>().update());
+    }
+
+    /// Replaces the backend gamepads are polled from each frame
+    pub fn set_gamepad_backend(&mut self, backend: impl GamepadBackend + 'static) {
+        self.gamepad_backend = Some(Box::new(backend));
+    }
+
+    /// Spawns the scene saved at `path`, see [`SceneAsset::spawn`]
+    pub fn load_scene(&mut self, path: impl AsRef<Path>) -> Result<Vec<Entity>, AssetError> {
+        SceneAsset::load(path)?.spawn(&mut self.world, self.renderer.resource())
+    }
+
+    pub fn save_scene(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        SceneAsset::from_world(&self.world)?.save(path)
+    }
+
+    /// Registers `prefab` as `name`, or updates it and its instances, see [`Prefabs::update`]
+    pub fn add_prefab(&mut self, name: &str, prefab: PrefabAsset) -> Result<(), AssetError> {
+        Prefabs::update(&mut self.world, self.renderer.resource(), name, prefab)
+    }
+
+    /// Registers or updates the prefab saved at `path` as `name`
+    pub fn load_prefab(&mut self, name: &str, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        self.add_prefab(name, PrefabAsset::load(path)?)
+    }
+
+    /// Spawns an instance of the prefab `name`, returns its root
+    pub fn instantiate_prefab(
+        &mut self,
+        name: &str,
+        overrides: &PrefabOverrides,
+    ) -> Result<Entity, AssetError> {
+        Prefabs::instantiate(&mut self.world, self.renderer.resource(), name, overrides)
     }
 
     pub fn handle_resize(&mut self, size: PhysicalSize<u32>) {
         self.renderer.handle_resize(size);
-        self.camera
-            .set_ratio(size.width as f32 / size.height as f32);
+        self.world.send_event(WindowResized {
+            width: size.width,
+            height: size.height,
+        });
     }
 
+    /// Handles the input events among window events, see [`InputEvent::from_window_event`]
     pub fn handle_input_event(&mut self, event: WindowEvent) {
-        match event {
-            WindowEvent::KeyboardInput { event, .. } => {
-                self.input_system.handle_keyboard_input(event);
-            }
-            WindowEvent::CursorMoved { position, .. } => {
-                self.input_system.handle_cursor_moved(position);
+        if let Some(event) = InputEvent::from_window_event(&event) {
+            self.handle_input(event);
+        }
+    }
+
+    /// Handles raw mouse motion, see [`InputEvent::from_device_event`]
+    pub fn handle_device_event(&mut self, event: DeviceEvent) {
+        if let Some(event) = InputEvent::from_device_event(&event) {
+            self.handle_input(event);
+        }
+    }
+
+    /// Sends the matching input events and updates the [`InputSystem`]
+    pub fn handle_input(&mut self, event: InputEvent) {
+        self.handle_input_at(self.input_frame_start.elapsed(), event);
+    }
+
+    /// Handles `event` as received `elapsed` after the input frame began
+    fn handle_input_at(&mut self, elapsed: Duration, event: InputEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_input(elapsed, event.clone());
+        }
+        match &event {
+            InputEvent::Key {
+                key,
+                pressed: true,
+                repeat: false,
+                ..
+            } => self.world.send_event(KeyPressed { key: *key }),
+            InputEvent::Key {
+                key,
+                pressed: false,
+                ..
+            } => self.world.send_event(KeyReleased { key: *key }),
+            InputEvent::MouseButton {
+                button,
+                pressed: true,
+            } => self
+                .world
+                .send_event(MouseButtonPressed { button: *button }),
+            InputEvent::MouseButton {
+                button,
+                pressed: false,
+            } => self
+                .world
+                .send_event(MouseButtonReleased { button: *button }),
+            InputEvent::Gamepad(GamepadEvent::Connected { id, name }) => {
+                self.world.send_event(GamepadConnected {
+                    id: *id,
+                    name: name.clone(),
+                })
             }
-            WindowEvent::MouseInput { state, button, .. } => {
-                self.input_system.handle_mouse_input(button, state)
+            InputEvent::Gamepad(GamepadEvent::Disconnected { id }) => {
+                self.world.send_event(GamepadDisconnected { id: *id })
             }
             _ => (),
         }
+        let mut input_system = self.world.resource_mut::<InputSystem>();
+        input_system.set_event_time(elapsed);
+        input_system.handle_input(event);
+    }
+
+    /// Starts recording the inputs, frames and ticks, discarding the current recording
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(InputRecorder::new());
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.take().map(InputRecorder::finish)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
     }
 }
 
 impl Core {
+    /// Records a frame in [`Time`], returns the game time to simulate for it.
+    ///
+    /// This is the frame boundary of events: once a tick ran since the last
+    /// boundary, the ones sent before it are dropped. Frames without ticks
+    /// keep them, so that the fixed tick systems see them too, unless the game
+    /// time is frozen and no tick is coming.
+    pub fn begin_frame(&mut self, frame_time: Duration) -> Duration {
+        self.poll_gamepads();
+        self.start_frame(frame_time)
+    }
+
+    fn start_frame(&mut self, frame_time: Duration) -> Duration {
+        let frozen = {
+            let time = self.world.resource::<Time>();
+            time.is_paused() || time.time_scale() == 0.0
+        };
+        if std::mem::take(&mut self.ticked) || frozen {
+            for update in &self.event_updaters {
+                update(&self.world);
+            }
+        }
+        self.apply_cursor_mode();
+        self.apply_text_input();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.begin_frame(frame_time);
+        }
+        self.world.resource_mut::<Time>().advance_frame(frame_time)
+    }
+
+    /// Runs a recorded frame in place of [`Core::begin_frame`], the ticks and
+    /// [`Core::render`], feeding the recorded inputs instead of the gamepad backend
+    pub fn replay_frame(&mut self, frame: &RecordedFrame) {
+        for (elapsed, event) in &frame.inputs {
+            self.handle_input_at(*elapsed, event.clone());
+        }
+        self.send_rumbles();
+        self.start_frame(frame.frame_time);
+        for delta in &frame.ticks {
+            self.tick(*delta);
+        }
+        self.render_frame(frame.alpha, frame.input_delta);
+    }
+
+    /// Advances the simulation by one step, running the update stages
     pub fn tick(&mut self, delta_time: Duration) {
-        self.camera.tick(
-            delta_time,
-            self.input_system.game_command(),
-            self.input_system.cursor_delta(),
-        );
-        self.input_system.reset_cursor_delta();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_tick(delta_time);
+        }
+        self.ticked = true;
+        self.save_previous_transforms();
+        self.world.resource_mut::<Time>().advance_tick(delta_time);
+        for stage in [Stage::PreUpdate, Stage::Update, Stage::PostUpdate] {
+            self.schedule.run_stage(stage, &mut self.world);
+        }
+        self.renderer.update(delta_time);
+    }
+
+    /// Runs [`Stage::PreRender`] and renders from the first entity with a [`Camera`],
+    /// `alpha` interpolating between the last two simulation steps.
+    ///
+    /// This ends the input frame, see [`InputSystem::update`], so that the
+    /// ticks and the systems of the frame all see its edges, even when no tick ran.
+    pub fn render(&mut self, alpha: f32) {
+        let now = Instant::now();
+        let input_delta = now - std::mem::replace(&mut self.input_frame_start, now);
+        self.render_frame(alpha, input_delta);
+    }
+
+    /// Renders, ending the input frame `input_delta` after the previous one
+    fn render_frame(&mut self, alpha: f32, input_delta: Duration) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_render(alpha, input_delta);
+        }
+        self.schedule.run_stage(Stage::PreRender, &mut self.world);
+        self.world.resource_mut::<InputSystem>().update(input_delta);
+        self.renderer.extract(&self.world, alpha);
+        if let Some((_, camera)) = self.world.query::<&Camera>().single() {
+            self.renderer.render(camera);
+        }
+        *self.world.resource_mut::<CullingStats>() = self.renderer.culling_stats();
+    }
+
+    /// Handles the gamepad events and sends the requested rumbles to the backend
+    fn poll_gamepads(&mut self) {
+        let Some(backend) = &mut self.gamepad_backend else {
+            return;
+        };
+        let mut events = Vec::new();
+        backend.poll(&mut events);
+        for event in events {
+            self.handle_input(InputEvent::Gamepad(event));
+        }
+        self.send_rumbles();
+    }
+
+    /// Grabs or releases the cursor when [`InputSystem::cursor_mode`] changed
+    fn apply_cursor_mode(&mut self) {
+        let mode = self.world.resource::<InputSystem>().cursor_mode();
+        if mode == self.cursor_mode {
+            return;
+        }
+        self.cursor_mode = mode;
+        // Platforms support either confining or locking, fall back to the other
+        let window = &self.window;
+        let result = match mode {
+            CursorMode::Free => window.set_cursor_grab(CursorGrabMode::None),
+            CursorMode::Confined => window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked)),
+            CursorMode::Locked => window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined)),
+        };
+        if let Err(err) = result {
+            println!("[core]: failed to set the cursor mode to {mode:?}: {err}");
+        }
+        window.set_cursor_visible(mode != CursorMode::Locked);
+    }
+
+    /// Enables the IME while the [`InputSystem`]'s text input has focus
+    fn apply_text_input(&mut self) {
+        let input_system = self.world.resource::<InputSystem>();
+        let text_input = input_system.text_input();
+        let allowed = text_input.is_focused();
+        if allowed != self.ime_allowed {
+            self.ime_allowed = allowed;
+            self.window.set_ime_allowed(allowed);
+        }
+        let area = text_input.ime_cursor_area();
+        if allowed && area != self.ime_cursor_area {
+            self.ime_cursor_area = area;
+            if let Some((position, size)) = area {
+                self.window.set_ime_cursor_area(
+                    PhysicalPosition::new(position.x, position.y),
+                    PhysicalSize::new(size.x, size.y),
+                );
+            }
+        }
+    }
+
+    fn send_rumbles(&mut self) {
+        let rumbles = self.world.resource_mut::<InputSystem>().take_rumbles();
+        if let Some(backend) = &mut self.gamepad_backend {
+            for (id, rumble) in rumbles {
+                backend.rumble(id, rumble);
+            }
+        }
+    }
+
+    fn save_previous_transforms(&mut self) {
+        let mut transforms = Vec::new();
+        self.world
+            .query::<&Transform>()
+            .for_each(|entity, transform| transforms.push((entity, *transform)));
+        for (entity, transform) in transforms {
+            self.world.insert(entity, PreviousTransform(transform));
+        }
     }
 
-    pub fn render(&self) {
-        self.renderer.render(&self.camera);
+    /// Also a resource, systems see the stats of the previous frame
+    pub fn culling_stats(&self) -> CullingStats {
+        self.renderer.culling_stats()
     }
 }
@@ -0,0 +1,214 @@
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: glam::Vec3,
+    pub max: glam::Vec3,
+}
+
+impl Aabb {
+    pub fn from_points(points: impl IntoIterator<Item = glam::Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |aabb, point| {
+            Some(match aabb {
+                None => Aabb {
+                    min: point,
+                    max: point,
+                },
+                Some(Aabb { min, max }) => Aabb {
+                    min: min.min(point),
+                    max: max.max(point),
+                },
+            })
+        })
+    }
+
+    pub fn center(&self) -> glam::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> glam::Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// The smallest AABB containing this box after `transform`
+    pub fn transformed(&self, transform: glam::Mat4) -> Aabb {
+        let center = transform.transform_point3(self.center());
+        let half_extents = self.half_extents();
+        let half_extents = transform.x_axis.truncate().abs() * half_extents.x
+            + transform.y_axis.truncate().abs() * half_extents.y
+            + transform.z_axis.truncate().abs() * half_extents.z;
+        Aabb {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: glam::Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// A sphere around the center of the points' AABB
+    pub fn from_points(points: impl IntoIterator<Item = glam::Vec3> + Clone) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points
+            .into_iter()
+            .map(|point| point.distance(center))
+            .fold(0.0, f32::max);
+        Some(BoundingSphere { center, radius })
+    }
+
+    /// Non-uniform scales grow the radius by the largest axis scale
+    pub fn transformed(&self, transform: glam::Mat4) -> BoundingSphere {
+        let scale = transform
+            .x_axis
+            .truncate()
+            .length()
+            .max(transform.y_axis.truncate().length())
+            .max(transform.z_axis.truncate().length());
+        BoundingSphere {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+/// The six planes of a view frustum, normals pointing inwards.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    planes: [glam::Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes of a wgpu style (`0..1` depth) view projection matrix
+    pub fn from_view_projection(view_projection: glam::Mat4) -> Self {
+        let (r0, r1, r2, r3) = (
+            view_projection.row(0),
+            view_projection.row(1),
+            view_projection.row(2),
+            view_projection.row(3),
+        );
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2]
+            .map(|plane| plane / plane.truncate().length());
+        Self { planes }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            // The corner furthest along the plane normal
+            let corner = glam::Vec3::select(normal.cmpge(glam::Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Looking down -z from the origin with a 90 degree fov, the sides are at
+    /// `|x| == -z` and `|y| == -z`
+    fn frustum() -> Frustum {
+        Frustum::from_view_projection(glam::Mat4::perspective_rh(
+            90f32.to_radians(),
+            1.0,
+            0.1,
+            100.0,
+        ))
+    }
+
+    fn sphere(center: [f32; 3], radius: f32) -> BoundingSphere {
+        BoundingSphere {
+            center: center.into(),
+            radius,
+        }
+    }
+
+    fn cube(center: [f32; 3], half_extent: f32) -> Aabb {
+        let center = glam::Vec3::from(center);
+        Aabb {
+            min: center - half_extent,
+            max: center + half_extent,
+        }
+    }
+
+    #[test]
+    fn spheres() {
+        let frustum = frustum();
+        assert!(frustum.intersects_sphere(&sphere([0.0, 0.0, -10.0], 1.0)));
+        // Straddling the right and the far planes
+        assert!(frustum.intersects_sphere(&sphere([11.0, 0.0, -10.0], 2.0)));
+        assert!(frustum.intersects_sphere(&sphere([0.0, 0.0, -100.5], 1.0)));
+
+        assert!(!frustum.intersects_sphere(&sphere([0.0, 0.0, 10.0], 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere([14.0, 0.0, -10.0], 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere([0.0, -14.0, -10.0], 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere([0.0, 0.0, -101.0], 0.5)));
+    }
+
+    #[test]
+    fn aabbs() {
+        let frustum = frustum();
+        assert!(frustum.intersects_aabb(&cube([0.0, 0.0, -10.0], 1.0)));
+        // Straddling the left and the near planes
+        assert!(frustum.intersects_aabb(&cube([-10.5, 0.0, -10.0], 1.0)));
+        assert!(frustum.intersects_aabb(&cube([0.0, 0.0, 0.0], 1.0)));
+
+        assert!(!frustum.intersects_aabb(&cube([0.0, 0.0, 10.0], 1.0)));
+        assert!(!frustum.intersects_aabb(&cube([-13.0, 0.0, -10.0], 1.0)));
+        assert!(!frustum.intersects_aabb(&cube([0.0, 13.0, -10.0], 1.0)));
+        assert!(!frustum.intersects_aabb(&cube([0.0, 0.0, -102.0], 1.0)));
+    }
+
+    #[test]
+    fn transformed_aabbs() {
+        let frustum = frustum();
+        let aabb = cube([0.0, 0.0, 0.0], 1.0);
+        let translation = glam::Mat4::from_translation([12.2, 0.0, -10.0].into());
+        // Its corners only reach past x == -z once rotated
+        let moved = aabb.transformed(translation);
+        assert_eq!(moved, cube([12.2, 0.0, -10.0], 1.0));
+        assert!(!frustum.intersects_aabb(&moved));
+
+        let rotated =
+            aabb.transformed(translation * glam::Mat4::from_rotation_y(45f32.to_radians()));
+        let half_extents = rotated.half_extents();
+        assert!((half_extents.x - 2f32.sqrt()).abs() < 1e-5);
+        assert!((half_extents.y - 1.0).abs() < 1e-5);
+        assert!((half_extents.z - 2f32.sqrt()).abs() < 1e-5);
+        assert!(frustum.intersects_aabb(&rotated));
+    }
+
+    #[test]
+    fn transformed_spheres_grow_by_the_largest_scale() {
+        let sphere = sphere([1.0, 0.0, 0.0], 1.0).transformed(
+            glam::Mat4::from_translation([0.0, 0.0, -10.0].into())
+                * glam::Mat4::from_scale([2.0, 3.0, 1.0].into()),
+        );
+        assert_eq!(sphere.center, glam::Vec3::new(2.0, 0.0, -10.0));
+        assert_eq!(sphere.radius, 3.0);
+    }
+
+    #[test]
+    fn bounds_from_points() {
+        let points = [[-1.0, 0.0, 0.0], [3.0, 2.0, 0.0], [1.0, -2.0, 4.0]].map(glam::Vec3::from);
+        let aabb = Aabb::from_points(points).unwrap();
+        assert_eq!(aabb.min, glam::Vec3::new(-1.0, -2.0, 0.0));
+        assert_eq!(aabb.max, glam::Vec3::new(3.0, 2.0, 4.0));
+        let sphere = BoundingSphere::from_points(points).unwrap();
+        assert_eq!(sphere.center, aabb.center());
+        assert!(points
+            .iter()
+            .all(|point| point.distance(sphere.center) <= sphere.radius));
+        assert_eq!(Aabb::from_points([]), None);
+    }
+}
@@ -1,6 +1,30 @@
-use std::{f32::EPSILON, time::Duration};
+use serde::{Deserialize, Serialize};
+use winit::{event::MouseButton, keyboard::KeyCode};
 
-use crate::input::GameCommand;
+use crate::{
+    app::Plugin,
+    ecs::{IntoSystemConfig, Stage, World},
+    input::{
+        action::{ActionKind, Binding, InputSource},
+        context::GAMEPLAY,
+        gamepad::GamepadAxis,
+        mouse::CursorMode,
+        touch::Gestures,
+        InputSystem,
+    },
+    render::bounds::BoundingSphere,
+    time::Time,
+    Core,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Projection {
+    Perspective,
+    /// Shows `height` world units vertically, the width follows the aspect ratio
+    Orthographic {
+        height: f32,
+    },
+}
 
 #[derive(Debug)]
 pub struct Camera {
@@ -8,6 +32,8 @@ pub struct Camera {
     yaw: f32,
     pitch: f32,
 
+    projection: Projection,
+    /// Vertical field of view, in degrees
     fov: f32,
     aspect: f32,
     near: f32,
@@ -24,12 +50,14 @@ pub struct Camera {
 }
 
 impl Camera {
+    /// `fov` is the vertical field of view in degrees
     pub fn new(position: glam::Vec3, fov: f32, aspect: f32, near: f32, far: f32) -> Self {
         let yaw: f32 = 0.0;
         let pitch: f32 = 0.0;
 
+        let projection = Projection::Perspective;
         let view_mat = glam::Mat4::look_at_rh(position, position + glam::Vec3::Z, glam::Vec3::Y);
-        let projection_mat = glam::Mat4::perspective_rh(fov, aspect, near, far);
+        let projection_mat = projection_mat(projection, fov, aspect, near, far);
         let view_projection_mat = projection_mat * view_mat;
 
         let front = glam::Vec3::new(
@@ -46,6 +74,7 @@ impl Camera {
             position,
             yaw,
             pitch,
+            projection,
             fov,
             aspect,
             near,
@@ -60,42 +89,104 @@ impl Camera {
         }
     }
 
-    pub fn tick(&mut self, delta_time: Duration, game_command: u32, cursor_delta: glam::Vec2) {
-        // println!("{:?} {:?}", self.position, delta_time.as_secs_f32());
-        let velocity = 1.0 * delta_time.as_secs_f32();
-        if game_command & GameCommand::Forward as u32 != 0 {
-            self.position += self.forward * velocity;
-        }
-        if game_command & GameCommand::Backward as u32 != 0 {
-            self.position -= self.forward * velocity;
-        }
-        if game_command & GameCommand::Left as u32 != 0 {
-            self.position -= self.right * velocity;
-        }
-        if game_command & GameCommand::Right as u32 != 0 {
-            self.position += self.right * velocity;
-        }
-        if game_command & GameCommand::Up as u32 != 0 {
-            self.position += glam::Vec3::Y * velocity;
-        }
-        if game_command & GameCommand::Down as u32 != 0 {
-            self.position -= glam::Vec3::Y * velocity;
-        }
+    /// A camera looking down the -Z axis with +Y up, suited for 2D scenes in the XY plane.
+    pub fn new_orthographic(
+        position: glam::Vec3,
+        height: f32,
+        aspect: f32,
+        near: f32,
+        far: f32,
+    ) -> Self {
+        let mut camera = Self::new(position, 90.0, aspect, near, far);
+        camera.yaw = -90.0;
+        camera.set_projection(Projection::Orthographic { height });
+        camera
+    }
+
+    /// Moves in real time, so that the camera keeps flying while the game is paused.
+    ///
+    /// `movement` is right, up and forward. `cursor_delta` turns the camera once,
+    /// in degrees, `turn` turns it continuously, in degrees per second.
+    pub fn tick(
+        &mut self,
+        time: &Time,
+        movement: glam::Vec3,
+        cursor_delta: glam::Vec2,
+        turn: glam::Vec2,
+    ) {
+        // println!("{:?} {:?}", self.position, time.real_delta_secs());
+        let velocity = 1.0 * time.real_delta_secs();
+        self.position +=
+            (self.right * movement.x + glam::Vec3::Y * movement.y + self.forward * movement.z)
+                * velocity;
 
-        let mouse_sensitivity = 0.3;
-        if cursor_delta.x.abs() > EPSILON || cursor_delta.y.abs() > EPSILON {
-            self.yaw += cursor_delta.x * mouse_sensitivity;
-            self.pitch += -1.0 * cursor_delta.y * mouse_sensitivity;
+        let look = cursor_delta + turn * time.real_delta_secs();
+        if look.x.abs() > f32::EPSILON || look.y.abs() > f32::EPSILON {
+            self.yaw += look.x;
+            self.pitch -= look.y;
             self.pitch = self.pitch.clamp(-89.0, 89.0);
         }
         self.update_view_mat();
     }
 
+    /// Narrows the field of view, or the height shown by orthographic cameras,
+    /// by `factor`, `2.0` showing things twice as big
+    pub fn zoom(&mut self, factor: f32) {
+        if factor <= 0.0 || !factor.is_finite() {
+            return;
+        }
+        match &mut self.projection {
+            Projection::Perspective => self.fov = (self.fov / factor).clamp(10.0, 120.0),
+            Projection::Orthographic { height } => *height /= factor,
+        }
+        self.update_projection_mat();
+    }
+
     pub fn set_ratio(&mut self, ratio: f32) {
         self.aspect = ratio;
+        self.update_projection_mat();
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.update_projection_mat();
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// In degrees
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.aspect
+    }
+
+    pub fn near(&self) -> f32 {
+        self.near
+    }
+
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    /// In degrees, 0 looks down +X
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    /// In degrees, clamped to ±89 while flying
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    fn update_projection_mat(&mut self) {
         self.projection_mat =
-            glam::Mat4::perspective_rh(self.fov, self.aspect, self.near, self.far);
-        self.view_projection_mat = self.projection_mat * self.view_mat;
+            projection_mat(self.projection, self.fov, self.aspect, self.near, self.far);
+        self.update_view_mat();
     }
 
     pub fn set_position(&mut self, position: glam::Vec3) {
@@ -136,4 +227,250 @@ impl Camera {
     pub fn view_projection_mat(&self) -> glam::Mat4 {
         self.view_projection_mat
     }
+
+    /// The fraction of the screen height covered by `sphere`
+    pub fn projected_size(&self, sphere: &BoundingSphere) -> f32 {
+        let scale = self.projection_mat.y_axis.y;
+        match self.projection {
+            Projection::Perspective => {
+                let depth = (sphere.center - self.position).dot(self.front);
+                sphere.radius * scale / depth.max(self.near)
+            }
+            Projection::Orthographic { .. } => sphere.radius * scale,
+        }
+    }
+
+    pub fn position(&self) -> glam::Vec3 {
+        self.position
+    }
+
+    pub fn front(&self) -> glam::Vec3 {
+        self.front
+    }
+
+    pub fn right(&self) -> glam::Vec3 {
+        self.right
+    }
+
+    pub fn up(&self) -> glam::Vec3 {
+        self.up
+    }
+}
+
+fn projection_mat(
+    projection: Projection,
+    fov: f32,
+    aspect: f32,
+    near: f32,
+    far: f32,
+) -> glam::Mat4 {
+    match projection {
+        Projection::Perspective => glam::Mat4::perspective_rh(fov.to_radians(), aspect, near, far),
+        Projection::Orthographic { height } => {
+            let (half_width, half_height) = (height * aspect / 2.0, height / 2.0);
+            glam::Mat4::orthographic_rh(
+                -half_width,
+                half_width,
+                -half_height,
+                half_height,
+                near,
+                far,
+            )
+        }
+    }
+}
+
+/// Spawns a [`Camera`] and binds the `move`, `fly`, `look` and `turn` actions to fly it:
+/// WASD, space and shift, the mouse, or a gamepad.
+///
+/// The mouse looks around while the right button is held, or all the time once
+/// `lock_cursor` (a left click) locks the cursor, until `unlock_cursor` (Escape).
+/// `zoom` (the wheel) and pinching zoom, dragging a finger looks around and
+/// rotating two fingers turns the camera.
+pub struct FlyCameraPlugin;
+
+impl Plugin for FlyCameraPlugin {
+    fn build(&self, core: &mut Core) {
+        core.world_mut().spawn().with(Camera::new(
+            glam::Vec3 {
+                x: -3.0,
+                y: 0.0,
+                z: -3.0,
+            },
+            90.0,
+            16.0 / 9.0,
+            0.1,
+            100.0,
+        ));
+
+        let mut input_system = core.world_mut().resource_mut::<InputSystem>();
+        // Shift flies down while moving
+        input_system.add_default_action(
+            "move",
+            ActionKind::Axis2d,
+            [
+                Binding::key(KeyCode::KeyW)
+                    .with_direction(glam::Vec2::Y)
+                    .with_extra_modifiers(),
+                Binding::key(KeyCode::KeyS)
+                    .with_direction(glam::Vec2::NEG_Y)
+                    .with_extra_modifiers(),
+                Binding::key(KeyCode::KeyA)
+                    .with_direction(glam::Vec2::NEG_X)
+                    .with_extra_modifiers(),
+                Binding::key(KeyCode::KeyD)
+                    .with_direction(glam::Vec2::X)
+                    .with_extra_modifiers(),
+                Binding::gamepad_axis(GamepadAxis::LeftStickX),
+                Binding::gamepad_axis(GamepadAxis::LeftStickY).with_direction(glam::Vec2::Y),
+            ],
+        );
+        input_system.add_default_action(
+            "fly",
+            ActionKind::Axis,
+            [
+                Binding::key(KeyCode::Space),
+                Binding::key(KeyCode::ShiftLeft).with_scale(-1.0),
+                Binding::gamepad_axis(GamepadAxis::RightTrigger),
+                Binding::gamepad_axis(GamepadAxis::LeftTrigger).with_scale(-1.0),
+            ],
+        );
+        // In degrees after `MouseSettings::sensitivity`, +Y down
+        input_system.add_default_action(
+            "look",
+            ActionKind::Axis2d,
+            [
+                Binding::new(InputSource::MouseMotionX),
+                Binding::new(InputSource::MouseMotionY).with_direction(glam::Vec2::Y),
+            ],
+        );
+        // In degrees per second, +Y down
+        input_system.add_default_action(
+            "turn",
+            ActionKind::Axis2d,
+            [
+                Binding::gamepad_axis(GamepadAxis::RightStickX).with_scale(180.0),
+                Binding::gamepad_axis(GamepadAxis::RightStickY)
+                    .with_direction(glam::Vec2::new(0.0, -180.0)),
+            ],
+        );
+        // In lines, +1.0 a wheel notch away from the user
+        input_system.add_default_action(
+            "zoom",
+            ActionKind::Axis,
+            [Binding::new(InputSource::ScrollY)],
+        );
+        input_system.add_default_action(
+            "lock_cursor",
+            ActionKind::Button,
+            [Binding::mouse_button(MouseButton::Left)],
+        );
+        input_system.add_default_action(
+            "unlock_cursor",
+            ActionKind::Button,
+            [Binding::key(KeyCode::Escape)],
+        );
+        drop(input_system);
+
+        // The camera follows input every frame, even while the simulation is paused
+        core.add_system(
+            Stage::PreRender,
+            fly_camera
+                .with_name("fly_camera")
+                .after("update_camera_ratio")
+                .before("reset_cursor_delta")
+                .reads::<Time>()
+                .reads::<InputSystem>()
+                .queries::<&mut Camera>(),
+        );
+        core.add_system(
+            Stage::PreRender,
+            lock_cursor
+                .with_name("lock_cursor")
+                .before("fly_camera")
+                .writes::<InputSystem>(),
+        );
+    }
+}
+
+fn lock_cursor(world: &World) {
+    let mut input_system = world.resource_mut::<InputSystem>();
+    if input_system.action("unlock_cursor").just_pressed() {
+        input_system.set_cursor_mode(CursorMode::Free);
+    } else if input_system.action("lock_cursor").just_pressed() {
+        input_system.set_cursor_mode(CursorMode::Locked);
+    }
+}
+
+fn fly_camera(world: &World) {
+    let time = world.resource::<Time>();
+    let input_system = world.resource::<InputSystem>();
+    let movement = input_system.action("move").axis_2d();
+    let movement = glam::Vec3::new(movement.x, input_system.action("fly").value(), movement.y);
+    // Gestures aren't actions, so menus on top would see them too
+    let gestures = if input_system.is_context_blocked(GAMEPLAY) {
+        Gestures::default()
+    } else {
+        input_system.touches().gestures().clone()
+    };
+    // Dragging turns as much per pixel as the mouse per count
+    let look = input_system.action("look").axis_2d()
+        + gestures.drag * input_system.mouse_settings().sensitivity;
+    let turn = input_system.action("turn").axis_2d();
+    let zoom = 1.1_f32.powf(input_system.action("zoom").value()) * gestures.pinch;
+    world.query::<&mut Camera>().for_each(|_, camera| {
+        camera.zoom(zoom);
+        camera.set_yaw(camera.yaw() - gestures.rotation.to_degrees());
+        camera.tick(&time, movement, look, turn);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn fov_is_in_degrees() {
+        let camera = Camera::new(glam::Vec3::ZERO, 90.0, 1.0, 0.1, 100.0);
+        // A point 45 degrees up lands on the top edge
+        let clip = camera.projection_mat * glam::Vec4::new(0.0, 1.0, -1.0, 1.0);
+        assert!((clip.y / clip.w - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn turning_follows_the_frame_time_but_looking_does_not() {
+        let yaw_after = |frame: Duration, look: glam::Vec2, turn: glam::Vec2| {
+            let mut time = Time::default();
+            time.advance_frame(frame);
+            let mut camera = Camera::new(glam::Vec3::ZERO, 90.0, 1.0, 0.1, 100.0);
+            let yaw = camera.yaw();
+            camera.tick(&time, glam::Vec3::ZERO, look, turn);
+            camera.yaw() - yaw
+        };
+        let turn = glam::Vec2::new(180.0, 0.0);
+        let slow = yaw_after(Duration::from_millis(32), glam::Vec2::ZERO, turn);
+        let fast = yaw_after(Duration::from_millis(16), glam::Vec2::ZERO, turn);
+        assert!((slow - 2.0 * fast).abs() < 1e-3);
+        assert!((fast - 180.0 * 0.016).abs() < 1e-3);
+
+        let look = glam::Vec2::new(10.0, 0.0);
+        let slow = yaw_after(Duration::from_millis(32), look, glam::Vec2::ZERO);
+        let fast = yaw_after(Duration::from_millis(16), look, glam::Vec2::ZERO);
+        assert_eq!(slow, fast);
+    }
+
+    #[test]
+    fn zoom_clamps_the_fov() {
+        let mut camera = Camera::new(glam::Vec3::ZERO, 90.0, 1.0, 0.1, 100.0);
+        camera.zoom(2.0);
+        assert_eq!(camera.fov(), 45.0);
+        camera.zoom(100.0);
+        assert_eq!(camera.fov(), 10.0);
+        camera.zoom(0.01);
+        assert_eq!(camera.fov(), 120.0);
+        camera.zoom(0.0);
+        assert_eq!(camera.fov(), 120.0);
+    }
 }
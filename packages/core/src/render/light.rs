@@ -0,0 +1,132 @@
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LightKind {
+    /// Shines along the -Z axis of its transform
+    Directional,
+    Point {
+        range: f32,
+    },
+}
+
+/// A light source, placed in the world by its scene node. The cube pipeline
+/// shades with the first [`MAX_LIGHTS`] of the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: glam::Vec3,
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn directional(color: glam::Vec3, intensity: f32) -> Self {
+        Self {
+            kind: LightKind::Directional,
+            color,
+            intensity,
+        }
+    }
+
+    pub fn point(color: glam::Vec3, intensity: f32, range: f32) -> Self {
+        Self {
+            kind: LightKind::Point { range },
+            color,
+            intensity,
+        }
+    }
+}
+
+/// How many lights the forward pipelines shade with, the others are ignored
+pub const MAX_LIGHTS: usize = 8;
+
+/// The GPU layout of a light, see `shaders/cube_pipeline/shader.wgsl`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+pub struct GpuLight {
+    /// The direction the light shines along with `w = 0.0`, or the position
+    /// of a point light with `w = 1.0`
+    pub position: [f32; 4],
+    /// The color times the intensity, and the range of a point light in `w`
+    pub color: [f32; 4],
+}
+
+/// The lights of a frame as uploaded to the GPU
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct LightsUniform {
+    pub lights: [GpuLight; MAX_LIGHTS],
+    pub ambient: [f32; 3],
+    pub count: u32,
+}
+
+impl LightsUniform {
+    /// Packs the first [`MAX_LIGHTS`] lights with their world transforms
+    pub fn new<'a>(
+        lights: impl IntoIterator<Item = (glam::Mat4, &'a Light)>,
+        ambient: glam::Vec3,
+    ) -> Self {
+        let mut uniform = Self {
+            lights: [GpuLight::default(); MAX_LIGHTS],
+            ambient: ambient.into(),
+            count: 0,
+        };
+        for (world, light) in lights.into_iter().take(MAX_LIGHTS) {
+            let color = light.color * light.intensity;
+            uniform.lights[uniform.count as usize] = match light.kind {
+                LightKind::Directional => GpuLight {
+                    position: world
+                        .transform_vector3(glam::Vec3::NEG_Z)
+                        .normalize_or_zero()
+                        .extend(0.0)
+                        .into(),
+                    color: color.extend(0.0).into(),
+                },
+                LightKind::Point { range } => GpuLight {
+                    position: world.w_axis.truncate().extend(1.0).into(),
+                    color: color.extend(range).into(),
+                },
+            };
+            uniform.count += 1;
+        }
+        uniform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_directional_and_point_lights() {
+        let sun = Light::directional(glam::Vec3::ONE, 2.0);
+        let lamp = Light::point(glam::Vec3::X, 1.0, 5.0);
+        let uniform = LightsUniform::new(
+            [
+                (
+                    glam::Mat4::from_rotation_x(-std::f32::consts::FRAC_PI_2),
+                    &sun,
+                ),
+                (glam::Mat4::from_translation(glam::Vec3::Y), &lamp),
+            ],
+            glam::Vec3::splat(0.1),
+        );
+        assert_eq!(uniform.count, 2);
+        // Turned to shine straight down
+        let direction = glam::Vec4::from(uniform.lights[0].position);
+        assert!(direction.abs_diff_eq(glam::Vec4::NEG_Y, 1e-6));
+        assert_eq!(uniform.lights[0].color, [2.0, 2.0, 2.0, 0.0]);
+        assert_eq!(uniform.lights[1].position, [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(uniform.lights[1].color, [1.0, 0.0, 0.0, 5.0]);
+    }
+
+    #[test]
+    fn ignores_lights_past_the_limit() {
+        let lamp = Light::point(glam::Vec3::ONE, 1.0, 1.0);
+        let uniform = LightsUniform::new(
+            std::iter::repeat_n((glam::Mat4::IDENTITY, &lamp), MAX_LIGHTS + 2),
+            glam::Vec3::ZERO,
+        );
+        assert_eq!(uniform.count as usize, MAX_LIGHTS);
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use super::resource::RenderResource;
+
+/// A lower detail mesh, drawn once the object covers less than `screen_size`
+/// of the screen height.
+#[derive(Clone)]
+pub struct LodLevel {
+    pub resource: Arc<RenderResource>,
+    pub screen_size: f32,
+}
+
+/// Picks the level for an object covering `screen_size` of the screen height,
+/// `None` meaning the full detail mesh. `levels` are sorted by descending `screen_size`.
+pub fn select_lod(levels: &[LodLevel], screen_size: f32) -> Option<&LodLevel> {
+    lod_index(levels.iter().map(|level| level.screen_size), screen_size).map(|i| &levels[i])
+}
+
+/// The index of the smallest of the descending `screen_sizes` above `screen_size`
+fn lod_index(screen_sizes: impl Iterator<Item = f32>, screen_size: f32) -> Option<usize> {
+    screen_sizes
+        .take_while(|&level| screen_size < level)
+        .count()
+        .checked_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_smallest_level_above() {
+        let levels = [0.5, 0.25, 0.1];
+        let select = |screen_size| lod_index(levels.into_iter(), screen_size);
+        assert_eq!(select(1.0), None);
+        assert_eq!(select(0.5), None);
+        assert_eq!(select(0.4), Some(0));
+        assert_eq!(select(0.25), Some(0));
+        assert_eq!(select(0.2), Some(1));
+        assert_eq!(select(0.01), Some(2));
+        assert_eq!(select(0.0), Some(2));
+        assert_eq!(lod_index(std::iter::empty(), 0.0), None);
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BlendMode {
+    Opaque,
+    /// Opaque, but fragments with an alpha below `cutoff` are discarded
+    AlphaTest {
+        cutoff: f32,
+    },
+    AlphaBlend,
+    Additive,
+    /// Like [`BlendMode::AlphaBlend`] for colors already multiplied by their alpha
+    Premultiplied,
+}
+
+impl BlendMode {
+    /// Transparent objects are drawn after opaque ones, sorted back-to-front
+    pub fn is_transparent(&self) -> bool {
+        !matches!(self, BlendMode::Opaque | BlendMode::AlphaTest { .. })
+    }
+
+    pub fn blend_state(&self) -> Option<wgpu::BlendState> {
+        match self {
+            BlendMode::Opaque | BlendMode::AlphaTest { .. } => None,
+            BlendMode::AlphaBlend => Some(wgpu::BlendState::ALPHA_BLENDING),
+            BlendMode::Additive => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::OVER,
+            }),
+            BlendMode::Premultiplied => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        }
+    }
+
+    pub fn alpha_cutoff(&self) -> f32 {
+        match self {
+            BlendMode::AlphaTest { cutoff } => *cutoff,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Material {
+    /// Multiplied with the shaded color, its alpha drives blending and alpha testing
+    pub color: glam::Vec4,
+    pub blend_mode: BlendMode,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            color: glam::Vec4::ONE,
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+}
+
+impl Material {
+    pub fn new(color: glam::Vec4, blend_mode: BlendMode) -> Self {
+        Self { color, blend_mode }
+    }
+}
@@ -1,25 +1,90 @@
+pub mod bounds;
 pub mod camera;
+pub mod light;
+pub mod lod;
+pub mod material;
+pub mod particle;
 pub mod pipeline;
 pub mod primitive;
+pub mod queue;
 pub mod resource;
 pub mod scene;
+pub mod sprite;
+pub mod texture;
+pub mod transform;
 pub mod wgpu_context;
 
-use std::sync::Arc;
+use std::{
+    any::TypeId,
+    cell::Cell,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
 
 use camera::Camera;
-use pipeline::CubePipeline;
-use primitive::entity::cube::Cube;
+use light::Light;
+use pipeline::{CubePipeline, HelloTrianglePipeline, ParticlePipeline, Pipeline, SpritePipeline};
+use queue::CullingStats;
 use resource::Resource;
-use scene::Scene;
+use scene::{NodeId, Scene};
+use transform::Transform;
 use wgpu_context::WgpuContext;
 use winit::{dpi::PhysicalSize, window::Window};
 
+use crate::{
+    app::Plugin,
+    ecs::{
+        components::{Name, Parent, PreviousTransform, Renderable},
+        Entity, EventReader, Events, IntoSystemConfig, Stage, World,
+    },
+    runtime::WindowResized,
+    Core,
+};
+
+/// Registers the built-in pipelines, drawn in the order cubes, sprites, particles
+pub struct RenderPlugin;
+
+impl Plugin for RenderPlugin {
+    fn build(&self, core: &mut Core) {
+        let renderer = core.renderer_mut();
+        renderer
+            .resource_mut()
+            .add_pipeline::<HelloTrianglePipeline>();
+        renderer.add_pipeline::<CubePipeline>();
+        renderer.add_pipeline::<SpritePipeline>();
+        renderer.add_pipeline::<ParticlePipeline>();
+
+        let mut resized = EventReader::<WindowResized>::default();
+        core.add_system(
+            Stage::PreRender,
+            (move |world: &World| {
+                let events = world.resource::<Events<WindowResized>>();
+                // Only the latest size matters
+                let Some(size) = resized.read(&events).last() else {
+                    return;
+                };
+                let ratio = size.width as f32 / size.height.max(1) as f32;
+                world
+                    .query::<&mut Camera>()
+                    .for_each(|_, camera| camera.set_ratio(ratio));
+            })
+            .with_name("update_camera_ratio")
+            .reads::<Events<WindowResized>>()
+            .queries::<&mut Camera>(),
+        );
+    }
+}
+
 pub struct Renderer {
     ctx: Arc<WgpuContext>,
     resource: Resource,
     // pub pipeline: RefCell<Box<dyn Pipeline>>,
     scene: Scene,
+    /// The pipelines drawn each frame, in order
+    pipelines: Vec<TypeId>,
+    entity_nodes: HashMap<Entity, NodeId>,
+    culling_stats: Cell<CullingStats>,
 }
 
 impl Renderer {
@@ -31,28 +96,15 @@ impl Renderer {
         resource.init();
         // let pipeline = CubePipeline::new(&ctx);
         // let pipeline = HelloTrianglePipeline::new(&ctx);
-        let mut scene = Scene::new();
-        scene.add_render_object(Arc::new(resource.create_render_object::<Cube>(
-            [0.0, 0.0, 0.0].into(),
-            [0.0, 0.0, 0.0].into(),
-            [0.0, 0.0, 0.0].into(),
-        )));
-        scene.add_render_object(Arc::new(resource.create_render_object::<Cube>(
-            [3.0, 3.0, 3.0].into(),
-            [0.5, 1.0, 2.0].into(),
-            [1.0, 2.0, 3.0].into(),
-        )));
-        scene.add_render_object(Arc::new(resource.create_render_object::<Cube>(
-            [3.0, 0.0, 3.0].into(),
-            [0.5, 0.0, 3.14].into(),
-            [1.0, 2.0, 3.0].into(),
-        )));
-
+        let scene = Scene::new();
         Self {
             ctx,
             resource,
             // pipeline: RefCell::new(Box::new(pipeline)),
             scene,
+            pipelines: Vec::new(),
+            entity_nodes: HashMap::new(),
+            culling_stats: Cell::new(CullingStats::default()),
         }
     }
 
@@ -63,6 +115,48 @@ impl Renderer {
         self.ctx.update_surface_size(size);
     }
 
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    /// Registers `P` if needed and draws it after the pipelines added before
+    pub fn add_pipeline<P: Pipeline + 'static>(&mut self) {
+        self.resource.add_pipeline::<P>();
+        if !self.pipelines.contains(&TypeId::of::<P>()) {
+            self.pipelines.push(TypeId::of::<P>());
+        }
+    }
+
+    pub fn resource(&self) -> &Resource {
+        &self.resource
+    }
+
+    pub fn resource_mut(&mut self) -> &mut Resource {
+        &mut self.resource
+    }
+
+    /// Mirrors the entities that have a [`Transform`] into the scene graph,
+    /// together with their [`Renderable`], [`Light`] and [`Parent`].
+    ///
+    /// Transforms are interpolated by `alpha` from their [`PreviousTransform`].
+    /// New entities are added in entity order, after the nodes of the others.
+    pub fn extract(&mut self, world: &World, alpha: f32) {
+        extract_scene(&mut self.scene, &mut self.entity_nodes, world, alpha);
+    }
+
+    pub fn update(&mut self, delta_time: Duration) {
+        self.scene.update(delta_time);
+    }
+
+    /// Objects drawn and culled by the last [`Renderer::render`]
+    pub fn culling_stats(&self) -> CullingStats {
+        self.culling_stats.get()
+    }
+
     pub fn render(&self, camera: &Camera) {
         // println!("[core]: render");
         let output = self.ctx.surface.get_current_texture().unwrap();
@@ -70,13 +164,135 @@ impl Renderer {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        self.resource
-            .get_pipeline::<CubePipeline>()
-            .unwrap()
-            .render(&self.ctx, &view, camera, &self.scene, &self.resource);
+        let mut culling_stats = None;
+        for pipeline in &self.pipelines {
+            let pipeline = self.resource.get_pipeline_by_id(*pipeline).unwrap();
+            pipeline.render(&self.ctx, &view, camera, &self.scene, &self.resource);
+            culling_stats = culling_stats.or(pipeline.culling_stats());
+        }
+        self.culling_stats.set(culling_stats.unwrap_or_default());
         // self.pipeline
         //     .borrow_mut()
         //     .render(&self.ctx, &view, camera, &self.scene);
         output.present();
     }
 }
+
+/// See [`Renderer::extract`], `entity_nodes` being the node of each entity
+fn extract_scene(
+    scene: &mut Scene,
+    entity_nodes: &mut HashMap<Entity, NodeId>,
+    world: &World,
+    alpha: f32,
+) {
+    // In entity order, so that the nodes are added and reparented deterministically
+    let mut entities = BTreeMap::new();
+    world
+        .query::<(
+            &Transform,
+            Option<&PreviousTransform>,
+            Option<&Renderable>,
+            Option<&Light>,
+            Option<&Parent>,
+            Option<&Name>,
+        )>()
+        .for_each(
+            |entity, (transform, previous, renderable, light, parent, name)| {
+                entities.insert(
+                    entity,
+                    (
+                        previous.map_or(*transform, |previous| previous.0.lerp(transform, alpha)),
+                        renderable.map(|renderable| renderable.0.clone()),
+                        light.copied(),
+                        parent.map(|parent| parent.0),
+                        name.map(|name| name.0.clone()),
+                    ),
+                );
+            },
+        );
+
+    entity_nodes.retain(|entity, node| {
+        let alive = entities.contains_key(entity);
+        if !alive {
+            scene.remove_node(*node);
+        }
+        alive
+    });
+    // Removing a node also removed its descendants
+    entity_nodes.retain(|_, node| scene.node(*node).is_some());
+
+    for (entity, (transform, renderable, light, _, name)) in &entities {
+        let node = *entity_nodes
+            .entry(*entity)
+            .or_insert_with(|| scene.add_node(name.clone().unwrap_or_default(), None, *transform));
+        if scene.node(node).unwrap().transform() != transform {
+            scene.set_transform(node, *transform);
+        }
+        scene.set_renderable(node, renderable.clone());
+        scene.set_light(node, *light);
+    }
+    for (entity, (_, _, _, parent, _)) in &entities {
+        let node = entity_nodes[entity];
+        let parent = parent.and_then(|parent| entity_nodes.get(&parent).copied());
+        if scene.node(node).unwrap().parent() != parent {
+            scene.reparent(node, parent);
+        }
+    }
+    scene.update_world_transforms();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(scene: &Scene) -> Vec<String> {
+        scene
+            .iter()
+            .map(|(_, node)| node.name().to_string())
+            .collect()
+    }
+
+    fn spawn(world: &mut World, name: &str, parent: Option<Entity>) -> Entity {
+        let mut entity = world
+            .spawn()
+            .with(Transform::IDENTITY)
+            .with(Name(name.to_string()));
+        if let Some(parent) = parent {
+            entity = entity.with(Parent(parent));
+        }
+        entity.id()
+    }
+
+    #[test]
+    fn adds_the_nodes_in_entity_order() {
+        let mut world = World::new();
+        let mut scene = Scene::new();
+        let mut entity_nodes = HashMap::new();
+        let entities = (0..16)
+            .map(|i| spawn(&mut world, &format!("{i}"), None))
+            .collect::<Vec<_>>();
+        let child = spawn(&mut world, "child", Some(entities[3]));
+        let other_child = spawn(&mut world, "other_child", Some(entities[3]));
+        spawn(&mut world, "0", Some(child));
+
+        extract_scene(&mut scene, &mut entity_nodes, &world, 1.0);
+        let mut expected = (0..16).map(|i| i.to_string()).collect::<Vec<_>>();
+        expected.splice(4..4, ["child", "0", "other_child"].map(String::from));
+        assert_eq!(names(&scene), expected);
+        // The first one depth-first, the root
+        assert_eq!(scene.find_node("0"), Some(entity_nodes[&entities[0]]));
+
+        // New entities go after the others, by slot
+        world.despawn(entities[0]);
+        world.despawn(other_child);
+        let a = spawn(&mut world, "a", None);
+        let b = spawn(&mut world, "b", None);
+        assert!(b < a);
+        extract_scene(&mut scene, &mut entity_nodes, &world, 1.0);
+        expected.retain(|name| name != "other_child");
+        expected.remove(0);
+        expected.extend(["b", "a"].map(String::from));
+        assert_eq!(names(&scene), expected);
+        assert_ne!(scene.find_node("0"), None);
+    }
+}
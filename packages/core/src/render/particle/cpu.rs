@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use super::{Particle, ParticleEffect, ParticleEmitter, CURVE_SAMPLES};
+
+/// Runs a [`ParticleEffect`] on the CPU with the same spawn and integration
+/// rules as the compute pass, so results are deterministic for a given seed.
+pub struct CpuParticleSimulator {
+    effect: ParticleEffect,
+    particles: Vec<Particle>,
+}
+
+impl CpuParticleSimulator {
+    pub fn new(emitter: ParticleEmitter, seed: u64) -> Self {
+        let effect = ParticleEffect::with_seed(emitter, seed);
+        let particles = vec![Particle::default(); effect.capacity() as usize];
+        Self { effect, particles }
+    }
+
+    pub fn step(&mut self, delta_time: Duration) {
+        self.effect.update(delta_time);
+        for (slot, particles) in self.effect.spawned_runs() {
+            let slot = slot as usize;
+            self.particles[slot..slot + particles.len()].copy_from_slice(particles);
+        }
+
+        let gravity = self.effect.emitter.gravity;
+        let delta_time = self.effect.delta_time();
+        for particle in &mut self.particles {
+            particle.integrate(gravity, delta_time);
+        }
+    }
+
+    pub fn effect(&self) -> &ParticleEffect {
+        &self.effect
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn alive_particles(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter().filter(|particle| particle.is_alive())
+    }
+
+    /// The color `particle` is drawn with, from the baked curve like `render.wgsl`
+    pub fn color(&self, particle: &Particle) -> glam::Vec4 {
+        let (i0, i1, f) = curve_samples(particle);
+        let colors = self.effect.emitter.color_over_life.bake();
+        colors[i0].lerp(colors[i1], f)
+    }
+
+    pub fn size(&self, particle: &Particle) -> f32 {
+        let (i0, i1, f) = curve_samples(particle);
+        let sizes = self.effect.emitter.size_over_life.bake();
+        sizes[i0] + (sizes[i1] - sizes[i0]) * f
+    }
+}
+
+/// The two baked samples around the particle's normalized age, and how far it is between them
+fn curve_samples(particle: &Particle) -> (usize, usize, f32) {
+    let last = CURVE_SAMPLES - 1;
+    let t = (particle.age / particle.lifetime).clamp(0.0, 1.0) * last as f32;
+    let i0 = t.floor() as usize;
+    (i0, (i0 + 1).min(last), t.fract())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::particle::Curve;
+
+    fn emitter(rate: f32, lifetime: f32) -> ParticleEmitter {
+        ParticleEmitter {
+            rate,
+            lifetime: (lifetime, lifetime),
+            velocity_randomness: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn spawns_rate_times_delta() {
+        let mut simulator = CpuParticleSimulator::new(emitter(20.0, 10.0), 7);
+        simulator.step(Duration::from_millis(500));
+        assert_eq!(simulator.effect().spawned().len(), 10);
+        assert_eq!(simulator.alive_particles().count(), 10);
+
+        // The fraction left over carries to the next step
+        simulator.step(Duration::from_millis(125));
+        simulator.step(Duration::from_millis(125));
+        assert_eq!(simulator.alive_particles().count(), 15);
+    }
+
+    #[test]
+    fn spawns_at_most_capacity() {
+        let mut simulator = CpuParticleSimulator::new(
+            ParticleEmitter {
+                max_particles: 4,
+                ..emitter(100.0, 10.0)
+            },
+            7,
+        );
+        simulator.step(Duration::from_secs(1));
+        assert_eq!(simulator.effect().spawned().len(), 4);
+        assert_eq!(simulator.particles().len(), 4);
+    }
+
+    #[test]
+    fn spawned_runs_split_where_the_ring_wraps() {
+        let mut simulator = CpuParticleSimulator::new(
+            ParticleEmitter {
+                max_particles: 4,
+                ..emitter(30.0, 10.0)
+            },
+            7,
+        );
+        simulator.step(Duration::from_millis(100));
+        let runs = |simulator: &CpuParticleSimulator| {
+            simulator
+                .effect()
+                .spawned_runs()
+                .map(|(slot, particles)| (slot, particles.len()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(runs(&simulator), [(0, 3)]);
+        simulator.step(Duration::from_millis(100));
+        assert_eq!(runs(&simulator), [(3, 1), (0, 2)]);
+        let slots = simulator
+            .effect()
+            .spawned()
+            .map(|(slot, _)| slot)
+            .collect::<Vec<_>>();
+        assert_eq!(slots, [3, 0, 1]);
+        // Integrated since
+        assert_eq!(
+            simulator.particles()[3].velocity,
+            simulator.effect().spawned().next().unwrap().1.velocity
+        );
+
+        simulator.step(Duration::from_millis(0));
+        assert_eq!(runs(&simulator), []);
+    }
+
+    #[test]
+    fn particles_expire_after_their_lifetime() {
+        let mut simulator = CpuParticleSimulator::new(emitter(20.0, 0.75), 7);
+        simulator.step(Duration::from_millis(500));
+        assert_eq!(simulator.alive_particles().count(), 10);
+        // The first ten reach an age of 1.0, the next ten are at 0.5
+        simulator.step(Duration::from_millis(500));
+        assert_eq!(simulator.alive_particles().count(), 10);
+        assert!(simulator
+            .alive_particles()
+            .all(|particle| particle.age == 0.5));
+    }
+
+    #[test]
+    fn same_seed_same_particles() {
+        let run = |seed| {
+            let mut simulator = CpuParticleSimulator::new(emitter(20.0, 10.0), seed);
+            for _ in 0..10 {
+                simulator.step(Duration::from_millis(100));
+            }
+            simulator.particles().to_vec()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn color_and_size_follow_the_curves() {
+        let simulator = CpuParticleSimulator::new(
+            ParticleEmitter {
+                color_over_life: Curve::linear(glam::Vec4::ZERO, glam::Vec4::ONE),
+                size_over_life: Curve::constant(1.0).with_key(1.0, 3.0),
+                ..emitter(0.0, 2.0)
+            },
+            7,
+        );
+        let at_age = |age| Particle {
+            age,
+            lifetime: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(simulator.color(&at_age(0.0)), glam::Vec4::ZERO);
+        assert!(simulator
+            .color(&at_age(1.0))
+            .abs_diff_eq(glam::Vec4::splat(0.5), 1e-5));
+        assert_eq!(simulator.color(&at_age(2.0)), glam::Vec4::ONE);
+        assert!((simulator.size(&at_age(1.0)) - 2.0).abs() < 1e-5);
+        assert_eq!(simulator.size(&at_age(2.0)), 3.0);
+    }
+}
@@ -0,0 +1,318 @@
+pub mod cpu;
+
+use std::{
+    ops::{Add, Mul},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use bytemuck::{Pod, Zeroable};
+
+/// Number of samples a [`Curve`] is baked into before being uploaded to the GPU
+pub const CURVE_SAMPLES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitterShape {
+    Point,
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_extents: glam::Vec3,
+    },
+    /// Spawns on a disc of `radius` and shoots within `angle` (radians) of the emitter velocity
+    Cone {
+        angle: f32,
+        radius: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParticleBlendMode {
+    Additive,
+    Alpha,
+}
+
+/// A piecewise linear curve over a particle's normalized age in `[0, 1]`.
+#[derive(Debug, Clone)]
+pub struct Curve<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T> Curve<T>
+where
+    T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+{
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    pub fn linear(from: T, to: T) -> Self {
+        Self {
+            keys: vec![(0.0, from), (1.0, to)],
+        }
+    }
+
+    pub fn with_key(mut self, t: f32, value: T) -> Self {
+        let index = self.keys.partition_point(|(key, _)| *key <= t);
+        self.keys.insert(index, (t, value));
+        self
+    }
+
+    pub fn sample(&self, t: f32) -> T {
+        let index = self.keys.partition_point(|(key, _)| *key <= t);
+        if index == 0 {
+            return self.keys[0].1;
+        }
+        if index == self.keys.len() {
+            return self.keys[index - 1].1;
+        }
+
+        let (t0, v0) = self.keys[index - 1];
+        let (t1, v1) = self.keys[index];
+        let f = (t - t0) / (t1 - t0);
+        v0 * (1.0 - f) + v1 * f
+    }
+
+    pub fn bake(&self) -> [T; CURVE_SAMPLES] {
+        std::array::from_fn(|i| self.sample(i as f32 / (CURVE_SAMPLES - 1) as f32))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParticleEmitter {
+    pub position: glam::Vec3,
+    pub shape: EmitterShape,
+    /// Particles spawned per second
+    pub rate: f32,
+    /// Lifetime range in seconds
+    pub lifetime: (f32, f32),
+    pub velocity: glam::Vec3,
+    /// Maximum length of the random offset added to `velocity`
+    pub velocity_randomness: f32,
+    pub gravity: glam::Vec3,
+    pub color_over_life: Curve<glam::Vec4>,
+    pub size_over_life: Curve<f32>,
+    pub max_particles: u32,
+    pub blend_mode: ParticleBlendMode,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            position: glam::Vec3::ZERO,
+            shape: EmitterShape::Point,
+            rate: 100.0,
+            lifetime: (1.0, 2.0),
+            velocity: glam::Vec3::Y,
+            velocity_randomness: 0.0,
+            gravity: glam::Vec3::ZERO,
+            color_over_life: Curve::constant(glam::Vec4::ONE),
+            size_over_life: Curve::constant(0.1),
+            max_particles: 1024,
+            blend_mode: ParticleBlendMode::Additive,
+        }
+    }
+}
+
+impl ParticleEmitter {
+    fn spawn_particle(&self, rng: &mut Rng) -> Particle {
+        let (offset, velocity) = match self.shape {
+            EmitterShape::Point => (glam::Vec3::ZERO, self.velocity),
+            EmitterShape::Sphere { radius } => (
+                rng.unit_vector() * radius * rng.next_f32().cbrt(),
+                self.velocity,
+            ),
+            EmitterShape::Box { half_extents } => (
+                glam::Vec3::new(
+                    rng.range(-1.0, 1.0),
+                    rng.range(-1.0, 1.0),
+                    rng.range(-1.0, 1.0),
+                ) * half_extents,
+                self.velocity,
+            ),
+            EmitterShape::Cone { angle, radius } => {
+                let axis = self.velocity.try_normalize().unwrap_or(glam::Vec3::Y);
+                let rotation = glam::Quat::from_rotation_arc(glam::Vec3::Y, axis);
+
+                let theta = rng.range(0.0, std::f32::consts::TAU);
+                let r = radius * rng.next_f32().sqrt();
+                let offset = glam::Vec3::new(theta.cos() * r, 0.0, theta.sin() * r);
+
+                let phi = rng.range(0.0, std::f32::consts::TAU);
+                let cos_angle = rng.range(angle.cos(), 1.0);
+                let sin_angle = (1.0 - cos_angle * cos_angle).sqrt();
+                let direction =
+                    glam::Vec3::new(phi.cos() * sin_angle, cos_angle, phi.sin() * sin_angle);
+
+                (
+                    rotation * offset,
+                    rotation * direction * self.velocity.length(),
+                )
+            }
+        };
+        let velocity = velocity + rng.unit_vector() * self.velocity_randomness * rng.next_f32();
+
+        Particle {
+            position: (self.position + offset).into(),
+            age: 0.0,
+            velocity: velocity.into(),
+            lifetime: rng.range(self.lifetime.0, self.lifetime.1),
+        }
+    }
+}
+
+/// The GPU layout of a single particle, a particle is alive while `age < lifetime`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+pub struct Particle {
+    pub position: [f32; 3],
+    pub age: f32,
+    pub velocity: [f32; 3],
+    pub lifetime: f32,
+}
+
+impl Particle {
+    pub fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    /// Mirrors the integration in `shaders/particle_pipeline/simulate.wgsl`
+    pub fn integrate(&mut self, gravity: glam::Vec3, delta_time: f32) {
+        if !self.is_alive() {
+            return;
+        }
+        let velocity = glam::Vec3::from(self.velocity) + gravity * delta_time;
+        self.velocity = velocity.into();
+        self.position = (glam::Vec3::from(self.position) + velocity * delta_time).into();
+        self.age += delta_time;
+    }
+}
+
+/// A small xorshift generator so that particle spawning is reproducible from a seed.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    fn unit_vector(&mut self) -> glam::Vec3 {
+        let z = self.range(-1.0, 1.0);
+        let theta = self.range(0.0, std::f32::consts::TAU);
+        let r = (1.0 - z * z).sqrt();
+        glam::Vec3::new(r * theta.cos(), r * theta.sin(), z)
+    }
+}
+
+static NEXT_EFFECT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A [`ParticleEmitter`] placed in a scene.
+///
+/// [`ParticleEffect::update`] decides which particles spawn this frame, the simulation
+/// itself runs in the `ParticlePipeline` compute pass or in [`cpu::CpuParticleSimulator`].
+pub struct ParticleEffect {
+    id: u64,
+    pub emitter: ParticleEmitter,
+    rng: Rng,
+    spawn_accumulator: f32,
+    next_slot: u32,
+    frame: u64,
+    delta_time: f32,
+    /// The slot of the first spawned particle, the others follow it around the ring
+    first_spawned: u32,
+    spawned: Vec<Particle>,
+}
+
+impl ParticleEffect {
+    pub fn new(emitter: ParticleEmitter) -> Self {
+        Self::with_seed(emitter, 0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn with_seed(emitter: ParticleEmitter, seed: u64) -> Self {
+        Self {
+            id: NEXT_EFFECT_ID.fetch_add(1, Ordering::Relaxed),
+            emitter,
+            rng: Rng::new(seed),
+            spawn_accumulator: 0.0,
+            next_slot: 0,
+            frame: 0,
+            delta_time: 0.0,
+            first_spawned: 0,
+            spawned: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, delta_time: Duration) {
+        let delta_time = delta_time.as_secs_f32();
+        let capacity = self.capacity();
+
+        self.frame += 1;
+        self.delta_time = delta_time;
+        self.spawned.clear();
+        self.first_spawned = self.next_slot;
+
+        self.spawn_accumulator += self.emitter.rate * delta_time;
+        let count = (self.spawn_accumulator as u32).min(capacity);
+        self.spawn_accumulator -= self.spawn_accumulator.floor();
+        for _ in 0..count {
+            let particle = self.emitter.spawn_particle(&mut self.rng);
+            self.spawned.push(particle);
+            self.next_slot = (self.next_slot + 1) % capacity;
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.emitter.max_particles.max(1)
+    }
+
+    /// Increases on every [`ParticleEffect::update`], lets the simulation skip frames it already stepped
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn delta_time(&self) -> f32 {
+        self.delta_time
+    }
+
+    /// Particles spawned by the last update, keyed by the slot they overwrite
+    pub fn spawned(&self) -> impl ExactSizeIterator<Item = (u32, &Particle)> {
+        let capacity = self.capacity();
+        self.spawned
+            .iter()
+            .enumerate()
+            .map(move |(i, particle)| ((self.first_spawned + i as u32) % capacity, particle))
+    }
+
+    /// [`ParticleEffect::spawned`] as at most two runs of consecutive slots,
+    /// split where the ring wraps around, keyed by their first slot
+    pub fn spawned_runs(&self) -> impl Iterator<Item = (u32, &[Particle])> {
+        let until_wrap = (self.capacity() - self.first_spawned) as usize;
+        let (end, start) = self.spawned.split_at(until_wrap.min(self.spawned.len()));
+        [(self.first_spawned, end), (0, start)]
+            .into_iter()
+            .filter(|(_, particles)| !particles.is_empty())
+    }
+}
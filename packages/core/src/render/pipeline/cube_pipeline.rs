@@ -1,21 +1,28 @@
 // use std::f32::consts;
 
-use std::sync::Arc;
+use std::cell::{Cell, RefCell};
 
+use bytemuck::{Pod, Zeroable};
 use wgpu::{
-    BindGroup, BindGroupLayoutDescriptor, PipelineCompilationOptions, PipelineLayoutDescriptor,
-    RenderPipeline,
+    BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, RenderPipeline,
 };
 
 use super::Pipeline;
 use crate::render::{
     camera::Camera,
-    primitive::{Renderable, Vertex},
+    light::LightsUniform,
+    material::BlendMode,
+    primitive::Vertex,
+    queue::{CullingStats, RenderQueue},
     resource::Resource,
     scene::Scene,
-    wgpu_context::WgpuContext,
+    wgpu_context::{WgpuContext, DEPTH_FORMAT},
 };
 
+/// What objects are lit with besides the lights
+const AMBIENT: glam::Vec3 = glam::Vec3::splat(0.15);
+
 fn create_texels(size: usize) -> Vec<u8> {
     (0..size * size)
         .map(|id| {
@@ -34,14 +41,81 @@ fn create_texels(size: usize) -> Vec<u8> {
         .collect()
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ObjectUniform {
+    model_mat: [[f32; 4]; 4],
+    color: [f32; 4],
+    alpha_cutoff: f32,
+    _padding: [f32; 3],
+}
+
+/// One uniform buffer holding an [`ObjectUniform`] per drawn object, addressed by dynamic offsets
+struct ObjectBuffer {
+    capacity: usize,
+    buffer: wgpu::Buffer,
+    bind_group: BindGroup,
+}
+
 pub struct CubePipeline {
-    pipeline: RenderPipeline,
+    opaque_pipeline: RenderPipeline,
+    alpha_blend_pipeline: RenderPipeline,
+    additive_pipeline: RenderPipeline,
+    premultiplied_pipeline: RenderPipeline,
     bind_group: BindGroup,
 
     // vertex_buf: wgpu::Buffer,
     // index_buf: wgpu::Buffer,
     ubuf_view_projection_mat: wgpu::Buffer,
-    ubuf_model_mat: wgpu::Buffer,
+    ubuf_lights: wgpu::Buffer,
+    object_bind_group_layout: BindGroupLayout,
+    object_stride: u64,
+    objects: RefCell<ObjectBuffer>,
+    culling_stats: Cell<CullingStats>,
+}
+
+fn create_object_buffer(
+    context: &WgpuContext,
+    layout: &BindGroupLayout,
+    stride: u64,
+    capacity: usize,
+) -> ObjectBuffer {
+    let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Object Uniform Buffer"),
+        size: capacity as u64 * stride,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = context
+        .device
+        .create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<ObjectUniform>() as u64),
+                }),
+            }],
+            label: None,
+        });
+    ObjectBuffer {
+        capacity,
+        buffer,
+        bind_group,
+    }
+}
+
+impl CubePipeline {
+    fn pipeline(&self, blend_mode: BlendMode) -> &RenderPipeline {
+        match blend_mode {
+            BlendMode::Opaque | BlendMode::AlphaTest { .. } => &self.opaque_pipeline,
+            BlendMode::AlphaBlend => &self.alpha_blend_pipeline,
+            BlendMode::Additive => &self.additive_pipeline,
+            BlendMode::Premultiplied => &self.premultiplied_pipeline,
+        }
+    }
 }
 
 // fn generate_matrix(aspect_ratio: f32) -> glam::Mat4 {
@@ -75,33 +149,55 @@ impl Pipeline for CubePipeline {
                         },
                         wgpu::BindGroupLayoutEntry {
                             binding: 1,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: wgpu::BufferSize::new(64),
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Uint,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
                             },
                             count: None,
                         },
                         wgpu::BindGroupLayoutEntry {
                             binding: 2,
                             visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Texture {
-                                sample_type: wgpu::TextureSampleType::Uint,
-                                view_dimension: wgpu::TextureViewDimension::D2,
-                                multisampled: false,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<
+                                    LightsUniform,
+                                >(
+                                )
+                                    as u64),
                             },
                             count: None,
                         },
                     ],
                 });
+        let object_bind_group_layout =
+            context
+                .device
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("Cube Object Bind Group Layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: wgpu::BufferSize::new(
+                                std::mem::size_of::<ObjectUniform>() as u64,
+                            ),
+                        },
+                        count: None,
+                    }],
+                });
 
         // ? Pipeline layout
         let pipeline_layout = context
             .device
             .create_pipeline_layout(&PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&bind_group_layout], // ? Bind group layout
+                bind_group_layouts: &[&bind_group_layout, &object_bind_group_layout], // ? Bind group layout
                 push_constant_ranges: &[],
             });
 
@@ -145,12 +241,15 @@ impl Pipeline for CubePipeline {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
-        let ubuf_model_mat = context.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Uniform Buffer"),
-            size: 4 * 4 * std::mem::size_of::<f32>() as u64,
+        let ubuf_lights = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lights Uniform Buffer"),
+            size: std::mem::size_of::<LightsUniform>() as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
+        let alignment = context.device.limits().min_uniform_buffer_offset_alignment as u64;
+        let object_stride =
+            (std::mem::size_of::<ObjectUniform>() as u64).next_multiple_of(alignment);
 
         let bind_group = context
             .device
@@ -163,11 +262,11 @@ impl Pipeline for CubePipeline {
                     },
                     wgpu::BindGroupEntry {
                         binding: 1,
-                        resource: ubuf_model_mat.as_entire_binding(),
+                        resource: wgpu::BindingResource::TextureView(&texture_view),
                     },
                     wgpu::BindGroupEntry {
                         binding: 2,
-                        resource: wgpu::BindingResource::TextureView(&texture_view),
+                        resource: ubuf_lights.as_entire_binding(),
                     },
                 ],
                 label: None,
@@ -199,51 +298,77 @@ impl Pipeline for CubePipeline {
         let swapchain_capabilities = context.surface.get_capabilities(&context.adapter);
         let swapchain_format = swapchain_capabilities.formats[0];
 
-        let pipeline = context
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout), // ? Pipeline layout
-                vertex: wgpu::VertexState {
-                    module: &shader_module, // ? Shader module
-                    entry_point: "vs_main",
-                    buffers: &[vertex_buffer_layout],
-                    compilation_options: PipelineCompilationOptions {
-                        ..Default::default()
+        let create_pipeline = |blend_mode: BlendMode| {
+            context
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: None,
+                    layout: Some(&pipeline_layout), // ? Pipeline layout
+                    vertex: wgpu::VertexState {
+                        module: &shader_module, // ? Shader module
+                        entry_point: "vs_main",
+                        buffers: std::slice::from_ref(&vertex_buffer_layout),
+                        compilation_options: PipelineCompilationOptions {
+                            ..Default::default()
+                        },
                     },
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader_module, // ? Shader modyle
-                    entry_point: "fs_main",
-                    targets: &[Some(swapchain_format.into())],
-                    compilation_options: PipelineCompilationOptions {
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader_module, // ? Shader modyle
+                        entry_point: "fs_main",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: swapchain_format,
+                            blend: blend_mode.blend_state(),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                        compilation_options: PipelineCompilationOptions {
+                            ..Default::default()
+                        },
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        cull_mode: Some(wgpu::Face::Back),
                         ..Default::default()
                     },
-                }),
-                primitive: wgpu::PrimitiveState {
-                    cull_mode: Some(wgpu::Face::Back),
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            });
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: DEPTH_FORMAT,
+                        // Transparent objects are tested against, but don't occlude, other objects
+                        depth_write_enabled: !blend_mode.is_transparent(),
+                        depth_compare: wgpu::CompareFunction::Less,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    }),
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
+        };
+
+        let objects = create_object_buffer(context, &object_bind_group_layout, object_stride, 16);
 
         Self {
-            pipeline,
+            opaque_pipeline: create_pipeline(BlendMode::Opaque),
+            alpha_blend_pipeline: create_pipeline(BlendMode::AlphaBlend),
+            additive_pipeline: create_pipeline(BlendMode::Additive),
+            premultiplied_pipeline: create_pipeline(BlendMode::Premultiplied),
             bind_group,
             ubuf_view_projection_mat,
-            ubuf_model_mat,
+            ubuf_lights,
+            object_bind_group_layout,
+            object_stride,
+            objects: RefCell::new(objects),
+            culling_stats: Cell::new(CullingStats::default()),
         }
     }
 
+    fn culling_stats(&self) -> Option<CullingStats> {
+        Some(self.culling_stats.get())
+    }
+
     fn render(
         &self,
         context: &WgpuContext,
         view: &wgpu::TextureView,
         camera: &Camera,
         scene: &Scene,
-        resource: &Resource,
+        _resource: &Resource,
     ) {
         {
             // TODO: move these things out of [`render`] to optimize performance (maybe add a method called [`update`]?)
@@ -256,57 +381,85 @@ impl Pipeline for CubePipeline {
                 bytemuck::cast_slice(mx_ref),
             );
         }
+        let lights = LightsUniform::new(scene.lights(), AMBIENT);
+        context
+            .queue
+            .write_buffer(&self.ubuf_lights, 0, bytemuck::bytes_of(&lights));
 
-        let render = |renderable: &Arc<dyn Renderable>| {
-            let mut encoder = context
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-            {
-                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: None,
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            // load: wgpu::LoadOp::Clear(wgpu::Color {
-                            //     r: 0.1,
-                            //     g: 0.2,
-                            //     b: 0.3,
-                            //     a: 1.0,
-                            // }),
-                            load: wgpu::LoadOp::Load,
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
-                });
+        let queue = RenderQueue::new(scene, camera);
+        self.culling_stats.set(queue.culling_stats);
+
+        let mut objects = self.objects.borrow_mut();
+        if queue.len() > objects.capacity {
+            *objects = create_object_buffer(
+                context,
+                &self.object_bind_group_layout,
+                self.object_stride,
+                queue.len().next_power_of_two(),
+            );
+        }
+        let mut object_data = vec![0u8; queue.len() * self.object_stride as usize];
+        for (i, item) in queue.items().enumerate() {
+            let uniform = ObjectUniform {
+                model_mat: item.model.to_cols_array_2d(),
+                color: item.material.color.into(),
+                alpha_cutoff: item.material.blend_mode.alpha_cutoff(),
+                _padding: [0.0; 3],
+            };
+            let offset = i * self.object_stride as usize;
+            object_data[offset..offset + std::mem::size_of::<ObjectUniform>()]
+                .copy_from_slice(bytemuck::bytes_of(&uniform));
+        }
+        context.queue.write_buffer(&objects.buffer, 0, &object_data);
+
+        let depth_view = context.depth_view.lock().unwrap();
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.1,
+                            g: 0.2,
+                            b: 0.3,
+                            a: 1.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+            for (i, item) in queue.items().enumerate() {
                 rpass.push_debug_group("Prepare data for draw.");
-                rpass.set_pipeline(&self.pipeline);
-                rpass.set_bind_group(0, &self.bind_group, &[]);
-                rpass.set_index_buffer(renderable.index_buf().slice(..), wgpu::IndexFormat::Uint16);
-                rpass.set_vertex_buffer(0, renderable.vertex_buf().slice(..));
+                rpass.set_pipeline(self.pipeline(item.material.blend_mode));
+                rpass.set_bind_group(
+                    1,
+                    &objects.bind_group,
+                    &[(i as u64 * self.object_stride) as u32],
+                );
+                rpass.set_index_buffer(item.index_buf().slice(..), wgpu::IndexFormat::Uint16);
+                rpass.set_vertex_buffer(0, item.vertex_buf().slice(..));
                 rpass.pop_debug_group();
                 rpass.insert_debug_marker("Draw!");
-                rpass.draw_indexed(0..renderable.vertex_cnt() as u32, 0, 0..1);
-                // if let Some(ref pipe) = self.pipeline_wire {
-                //     rpass.set_pipeline(pipe);
-                //     rpass.draw_indexed(0..self.index_count as u32, 0, 0..1);
-                // }
+                rpass.draw_indexed(0..item.vertex_cnt() as u32, 0, 0..1);
             }
-
-            context.queue.submit(Some(encoder.finish()));
-        };
-
-        // println!("{:?} meshes", scene.meshes().len());
-        for render_object in scene.render_objects() {
-            let mx = render_object.model_matrix();
-            let mx_ref: &[f32; 16] = mx.as_ref();
-            context
-                .queue
-                .write_buffer(&self.ubuf_model_mat, 0, bytemuck::cast_slice(mx_ref));
-            render(render_object);
         }
+
+        context.queue.submit(Some(encoder.finish()));
     }
 }
@@ -1,12 +1,16 @@
 pub mod cube_pipeline;
 pub mod hello_triangle_pipeline;
+pub mod particle_pipeline;
+pub mod sprite_pipeline;
 
 use crate::render::wgpu_context::WgpuContext;
 
 pub use cube_pipeline::CubePipeline;
 pub use hello_triangle_pipeline::HelloTrianglePipeline;
+pub use particle_pipeline::ParticlePipeline;
+pub use sprite_pipeline::SpritePipeline;
 
-use super::{camera::Camera, resource::Resource, scene::Scene};
+use super::{camera::Camera, queue::CullingStats, resource::Resource, scene::Scene};
 
 pub trait Pipeline {
     fn new(context: &WgpuContext) -> Self
@@ -21,4 +25,9 @@ pub trait Pipeline {
         scene: &Scene,
         resource: &Resource,
     );
+
+    /// Culling results of the last [`Pipeline::render`], for pipelines that cull
+    fn culling_stats(&self) -> Option<CullingStats> {
+        None
+    }
 }
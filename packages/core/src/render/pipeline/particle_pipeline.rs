@@ -0,0 +1,375 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroupLayout, BindGroupLayoutDescriptor, ComputePipeline, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, RenderPipeline,
+};
+
+use super::Pipeline;
+use crate::render::{
+    camera::Camera,
+    particle::{Particle, ParticleBlendMode, ParticleEffect},
+    resource::Resource,
+    scene::Scene,
+    wgpu_context::{WgpuContext, DEPTH_FORMAT},
+};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ParticleParams {
+    view_projection_mat: [[f32; 4]; 4],
+    camera_right: [f32; 4],
+    camera_up: [f32; 4],
+    gravity: [f32; 3],
+    delta_time: f32,
+    color_over_life: [[f32; 4]; 16],
+    size_over_life: [[f32; 4]; 4],
+    particle_count: u32,
+    _padding: [u32; 3],
+}
+
+/// GPU side state of a single [`ParticleEffect`]
+struct ParticleBuffers {
+    capacity: u32,
+    frame: u64,
+    particle_buf: wgpu::Buffer,
+    params_buf: wgpu::Buffer,
+    simulate_bind_group: wgpu::BindGroup,
+    render_bind_group: wgpu::BindGroup,
+}
+
+pub struct ParticlePipeline {
+    simulate_pipeline: ComputePipeline,
+    additive_pipeline: RenderPipeline,
+    alpha_pipeline: RenderPipeline,
+    simulate_bind_group_layout: BindGroupLayout,
+    render_bind_group_layout: BindGroupLayout,
+
+    buffers: RefCell<HashMap<u64, ParticleBuffers>>,
+}
+
+fn bind_group_layout(
+    context: &WgpuContext,
+    label: &str,
+    visibility: wgpu::ShaderStages,
+    read_only: bool,
+) -> BindGroupLayout {
+    context
+        .device
+        .create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<ParticleParams>() as u64,
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<Particle>() as u64
+                        ),
+                    },
+                    count: None,
+                },
+            ],
+        })
+}
+
+impl ParticlePipeline {
+    fn create_buffers(&self, context: &WgpuContext, effect: &ParticleEffect) -> ParticleBuffers {
+        let capacity = effect.capacity();
+        let particle_buf = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Buffer"),
+            size: capacity as u64 * std::mem::size_of::<Particle>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params_buf = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Params Buffer"),
+            size: std::mem::size_of::<ParticleParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let create_bind_group = |layout: &BindGroupLayout| {
+            context
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: params_buf.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: particle_buf.as_entire_binding(),
+                        },
+                    ],
+                    label: None,
+                })
+        };
+        let simulate_bind_group = create_bind_group(&self.simulate_bind_group_layout);
+        let render_bind_group = create_bind_group(&self.render_bind_group_layout);
+
+        ParticleBuffers {
+            capacity,
+            frame: 0,
+            particle_buf,
+            params_buf,
+            simulate_bind_group,
+            render_bind_group,
+        }
+    }
+}
+
+impl Pipeline for ParticlePipeline {
+    fn new(context: &WgpuContext) -> Self {
+        // ? Bind group layout
+        let simulate_bind_group_layout = bind_group_layout(
+            context,
+            "Particle Simulate Bind Group Layout",
+            wgpu::ShaderStages::COMPUTE,
+            false,
+        );
+        let render_bind_group_layout = bind_group_layout(
+            context,
+            "Particle Render Bind Group Layout",
+            wgpu::ShaderStages::VERTEX,
+            true,
+        );
+
+        // ? Compute pipeline
+        let simulate_module = context.device.create_shader_module(wgpu::include_wgsl!(
+            "../shaders/particle_pipeline/simulate.wgsl"
+        ));
+        let simulate_pipeline_layout =
+            context
+                .device
+                .create_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: Some("Particle Simulate Pipeline Layout"),
+                    bind_group_layouts: &[&simulate_bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+        let simulate_pipeline =
+            context
+                .device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("Particle Simulate Pipeline"),
+                    layout: Some(&simulate_pipeline_layout),
+                    module: &simulate_module,
+                    entry_point: "cs_main",
+                    compilation_options: PipelineCompilationOptions {
+                        ..Default::default()
+                    },
+                });
+
+        // ? Render pipelines
+        let render_module = context.device.create_shader_module(wgpu::include_wgsl!(
+            "../shaders/particle_pipeline/render.wgsl"
+        ));
+        let render_pipeline_layout =
+            context
+                .device
+                .create_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: Some("Particle Render Pipeline Layout"),
+                    bind_group_layouts: &[&render_bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+        let create_render_pipeline = |blend: wgpu::BlendState| {
+            context
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Particle Render Pipeline"),
+                    layout: Some(&render_pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &render_module,
+                        entry_point: "vs_main",
+                        buffers: &[],
+                        compilation_options: PipelineCompilationOptions {
+                            ..Default::default()
+                        },
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &render_module,
+                        entry_point: "fs_main",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: context.get_surface_format(),
+                            blend: Some(blend),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                        compilation_options: PipelineCompilationOptions {
+                            ..Default::default()
+                        },
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        cull_mode: None,
+                        ..Default::default()
+                    },
+                    // Hidden behind opaque geometry, but don't hide each other
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: DEPTH_FORMAT,
+                        depth_write_enabled: false,
+                        depth_compare: wgpu::CompareFunction::Less,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    }),
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
+        };
+        let additive_pipeline = create_render_pipeline(wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::SrcAlpha,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Zero,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        });
+        let alpha_pipeline = create_render_pipeline(wgpu::BlendState::ALPHA_BLENDING);
+
+        Self {
+            simulate_pipeline,
+            additive_pipeline,
+            alpha_pipeline,
+            simulate_bind_group_layout,
+            render_bind_group_layout,
+            buffers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn render(
+        &self,
+        context: &WgpuContext,
+        view: &wgpu::TextureView,
+        camera: &Camera,
+        scene: &Scene,
+        _resource: &Resource,
+    ) {
+        let mut buffers = self.buffers.borrow_mut();
+        // Drop the buffers of effects that left the scene
+        buffers.retain(|id, _| {
+            scene
+                .particle_effects()
+                .iter()
+                .any(|effect| effect.id() == *id)
+        });
+        if scene.particle_effects().is_empty() {
+            return;
+        }
+
+        let depth_view = context.depth_view.lock().unwrap();
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Particle Encoder"),
+            });
+
+        for effect in scene.particle_effects() {
+            let state = buffers
+                .entry(effect.id())
+                .or_insert_with(|| self.create_buffers(context, effect));
+            if state.capacity != effect.capacity() {
+                *state = self.create_buffers(context, effect);
+            }
+
+            // Only step the simulation once per `ParticleEffect::update`
+            let delta_time = if state.frame == effect.frame() {
+                0.0
+            } else {
+                state.frame = effect.frame();
+                for (slot, particles) in effect.spawned_runs() {
+                    context.queue.write_buffer(
+                        &state.particle_buf,
+                        slot as u64 * std::mem::size_of::<Particle>() as u64,
+                        bytemuck::cast_slice(particles),
+                    );
+                }
+                effect.delta_time()
+            };
+
+            let emitter = &effect.emitter;
+            let sizes = emitter.size_over_life.bake();
+            let params = ParticleParams {
+                view_projection_mat: camera.view_projection_mat().to_cols_array_2d(),
+                camera_right: camera.right().extend(0.0).into(),
+                camera_up: camera.up().extend(0.0).into(),
+                gravity: emitter.gravity.into(),
+                delta_time,
+                color_over_life: emitter.color_over_life.bake().map(Into::into),
+                size_over_life: std::array::from_fn(|i| {
+                    [
+                        sizes[i * 4],
+                        sizes[i * 4 + 1],
+                        sizes[i * 4 + 2],
+                        sizes[i * 4 + 3],
+                    ]
+                }),
+                particle_count: state.capacity,
+                _padding: [0; 3],
+            };
+            context
+                .queue
+                .write_buffer(&state.params_buf, 0, bytemuck::bytes_of(&params));
+
+            {
+                let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Particle Simulate Pass"),
+                    timestamp_writes: None,
+                });
+                cpass.set_pipeline(&self.simulate_pipeline);
+                cpass.set_bind_group(0, &state.simulate_bind_group, &[]);
+                cpass.dispatch_workgroups(state.capacity.div_ceil(WORKGROUP_SIZE), 1, 1);
+            }
+            {
+                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Particle Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    ..Default::default()
+                });
+                rpass.set_pipeline(match emitter.blend_mode {
+                    ParticleBlendMode::Additive => &self.additive_pipeline,
+                    ParticleBlendMode::Alpha => &self.alpha_pipeline,
+                });
+                rpass.set_bind_group(0, &state.render_bind_group, &[]);
+                rpass.draw(0..6, 0..state.capacity);
+            }
+        }
+
+        context.queue.submit(Some(encoder.finish()));
+    }
+}
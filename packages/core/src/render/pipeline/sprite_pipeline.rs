@@ -0,0 +1,203 @@
+use std::cell::RefCell;
+
+use wgpu::{
+    BindGroup, BindGroupLayoutDescriptor, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    RenderPipeline,
+};
+
+use super::Pipeline;
+use crate::render::{
+    camera::Camera,
+    resource::Resource,
+    scene::Scene,
+    sprite::{batch_sprites, SpriteInstance},
+    texture::TEXTURE_BIND_GROUP_LAYOUT,
+    wgpu_context::WgpuContext,
+};
+
+pub struct SpritePipeline {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+
+    ubuf_view_projection_mat: wgpu::Buffer,
+    instance_buf: RefCell<wgpu::Buffer>,
+}
+
+fn create_instance_buf(context: &WgpuContext, capacity: usize) -> wgpu::Buffer {
+    context.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Sprite Instance Buffer"),
+        size: (capacity * std::mem::size_of::<SpriteInstance>()) as u64,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+impl Pipeline for SpritePipeline {
+    fn new(context: &WgpuContext) -> Self {
+        // ? Bind group layout
+        let bind_group_layout =
+            context
+                .device
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("Sprite Bind Group Layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(64),
+                        },
+                        count: None,
+                    }],
+                });
+        let texture_bind_group_layout = context
+            .device
+            .create_bind_group_layout(&TEXTURE_BIND_GROUP_LAYOUT);
+
+        // ? Pipeline layout
+        let pipeline_layout = context
+            .device
+            .create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Sprite Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout, &texture_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let ubuf_view_projection_mat = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer"),
+            size: 4 * 4 * std::mem::size_of::<f32>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: ubuf_view_projection_mat.as_entire_binding(),
+                }],
+                label: None,
+            });
+
+        // ? Shader module
+        let shader_module = context.device.create_shader_module(wgpu::include_wgsl!(
+            "../shaders/sprite_pipeline/shader.wgsl"
+        ));
+
+        let instance_buffer_layout = wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &wgpu::vertex_attr_array![
+                0 => Float32x2,
+                1 => Float32x2,
+                2 => Float32x2,
+                3 => Float32x2,
+                4 => Float32x4,
+                5 => Float32,
+            ],
+        };
+
+        let pipeline = context
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Sprite Pipeline"),
+                layout: Some(&pipeline_layout), // ? Pipeline layout
+                vertex: wgpu::VertexState {
+                    module: &shader_module, // ? Shader module
+                    entry_point: "vs_main",
+                    buffers: &[instance_buffer_layout],
+                    compilation_options: PipelineCompilationOptions {
+                        ..Default::default()
+                    },
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module, // ? Shader module
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: context.get_surface_format(),
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: PipelineCompilationOptions {
+                        ..Default::default()
+                    },
+                }),
+                primitive: wgpu::PrimitiveState {
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+
+        Self {
+            pipeline,
+            bind_group,
+            ubuf_view_projection_mat,
+            instance_buf: RefCell::new(create_instance_buf(context, 64)),
+        }
+    }
+
+    fn render(
+        &self,
+        context: &WgpuContext,
+        view: &wgpu::TextureView,
+        camera: &Camera,
+        scene: &Scene,
+        _resource: &Resource,
+    ) {
+        if scene.sprites().is_empty() {
+            return;
+        }
+
+        let mx_total = camera.view_projection_mat();
+        let mx_ref: &[f32; 16] = mx_total.as_ref();
+        context.queue.write_buffer(
+            &self.ubuf_view_projection_mat,
+            0,
+            bytemuck::cast_slice(mx_ref),
+        );
+
+        let (instances, batches) = batch_sprites(scene.sprites());
+        let mut instance_buf = self.instance_buf.borrow_mut();
+        let capacity = instance_buf.size() as usize / std::mem::size_of::<SpriteInstance>();
+        if instances.len() > capacity {
+            *instance_buf = create_instance_buf(context, instances.len().next_power_of_two());
+        }
+        context
+            .queue
+            .write_buffer(&instance_buf, 0, bytemuck::cast_slice(&instances));
+
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Sprite Encoder"),
+            });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Sprite Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+            rpass.set_vertex_buffer(0, instance_buf.slice(..));
+            for batch in &batches {
+                rpass.set_bind_group(1, &batch.texture.bind_group, &[]);
+                rpass.draw(0..6, batch.instances.clone());
+            }
+        }
+
+        context.queue.submit(Some(encoder.finish()));
+    }
+}
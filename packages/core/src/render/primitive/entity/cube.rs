@@ -1,4 +1,4 @@
-use crate::render::{primitive::{vertex, RenderData, Renderable, Vertex}, resource::Resource};
+use crate::render::primitive::{vertex, RenderData, Vertex};
 
 pub struct Cube;
 
@@ -12,7 +12,7 @@ impl RenderData for Cube {
     }
 
     fn index_data() -> &'static [u16] {
-        &CUBE_VERTEX_INDEX
+        CUBE_VERTEX_INDEX
     }
 
 }
@@ -1,6 +1,11 @@
 use std::sync::Arc;
 
-use crate::render::resource::{RenderResource, Resource};
+use crate::render::{
+    bounds::{Aabb, BoundingSphere},
+    lod::{select_lod, LodLevel},
+    material::Material,
+    resource::RenderResource,
+};
 
 use super::Renderable;
 
@@ -9,8 +14,8 @@ pub mod cube;
 
 pub struct RenderObject {
     resource: Arc<RenderResource>,
-    position: glam::Vec3,
-    model: glam::Mat4,
+    material: Material,
+    lods: Vec<LodLevel>,
 }
 
 impl Renderable for RenderObject {
@@ -23,34 +28,47 @@ impl Renderable for RenderObject {
     fn vertex_cnt(&self) -> usize {
         self.resource.vertex_cnt
     }
-    
-    fn model_matrix(&self) -> glam::Mat4 {
-        self.model
+
+    fn material(&self) -> Material {
+        self.material
+    }
+
+    fn aabb(&self) -> Option<Aabb> {
+        Some(self.resource.aabb)
+    }
+
+    fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        Some(self.resource.bounding_sphere)
+    }
+
+    fn lod(&self, screen_size: f32) -> Option<&RenderResource> {
+        select_lod(&self.lods, screen_size).map(|level| level.resource.as_ref())
     }
 
 }
 
 impl RenderObject {
-    pub fn new(
-        resource: Arc<RenderResource>,
-        position: glam::Vec3,
-        rotation: glam::Vec3,
-        scale: glam::Vec3,
-    ) -> Self {
-        let scale_matrix = glam::Mat4::from_scale(scale);
-
-        let rotation_matrix_x = glam::Mat4::from_rotation_x(rotation.x);
-        let rotation_matrix_y = glam::Mat4::from_rotation_y(rotation.y);
-        let rotation_matrix_z = glam::Mat4::from_rotation_z(rotation.z);
-        let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
-
-        let translation_matrix = glam::Mat4::from_translation(position);
-
-        let model = translation_matrix * rotation_matrix * scale_matrix;
+    /// Place it in the world through the [`Transform`] of its scene node
+    ///
+    /// [`Transform`]: crate::render::transform::Transform
+    pub fn new(resource: Arc<RenderResource>) -> Self {
         Self {
             resource,
-            position,
-            model,
+            material: Material::default(),
+            lods: Vec::new(),
         }
     }
+
+    /// `lods` must be sorted by descending screen size, as [`Resource::load_lod_levels`] returns them
+    ///
+    /// [`Resource::load_lod_levels`]: crate::render::resource::Resource::load_lod_levels
+    pub fn with_lods(mut self, lods: Vec<LodLevel>) -> Self {
+        self.lods = lods;
+        self
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
 }
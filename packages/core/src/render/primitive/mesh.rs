@@ -1,6 +1,11 @@
 use wgpu::{util::DeviceExt, Buffer};
 
-use crate::render::{resource::Resource, wgpu_context::WgpuContext};
+use crate::render::{
+    bounds::{Aabb, BoundingSphere},
+    lod::{select_lod, LodLevel},
+    resource::RenderResource,
+    wgpu_context::WgpuContext,
+};
 
 use super::{Renderable, Vertex};
 
@@ -8,6 +13,12 @@ pub struct Mesh {
     vertex_cnt: usize,
     vertex_buf: Buffer,
     index_buf: Buffer,
+    aabb: Option<Aabb>,
+    bounding_sphere: Option<BoundingSphere>,
+    /// Kept on the CPU to build the LODs from
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    lods: Vec<LodLevel>,
 }
 
 impl Mesh {
@@ -27,12 +38,34 @@ impl Mesh {
                 contents: bytemuck::cast_slice(index_arr),
                 usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
             });
+        let positions = vertex_arr.iter().map(Vertex::position);
         Self {
             vertex_cnt,
             vertex_buf,
             index_buf,
+            aabb: Aabb::from_points(positions.clone()),
+            bounding_sphere: BoundingSphere::from_points(positions),
+            vertices: vertex_arr.to_vec(),
+            indices: index_arr.to_vec(),
+            lods: Vec::new(),
         }
     }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u16] {
+        &self.indices
+    }
+
+    /// E.g. from [`Resource::load_lod_levels_from`] with [`Mesh::vertices`] and [`Mesh::indices`]
+    ///
+    /// [`Resource::load_lod_levels_from`]: crate::render::resource::Resource::load_lod_levels_from
+    pub fn with_lods(mut self, lods: Vec<LodLevel>) -> Self {
+        self.lods = lods;
+        self
+    }
 }
 
 impl Renderable for Mesh {
@@ -48,4 +81,13 @@ impl Renderable for Mesh {
     fn model_matrix(&self) -> glam::Mat4 {
         glam::Mat4::from_scale(glam::Vec3::new(1.0, 1.0, 1.0))
     }
+    fn aabb(&self) -> Option<Aabb> {
+        self.aabb
+    }
+    fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.bounding_sphere
+    }
+    fn lod(&self, screen_size: f32) -> Option<&RenderResource> {
+        select_lod(&self.lods, screen_size).map(|level| level.resource.as_ref())
+    }
 }
@@ -1,9 +1,16 @@
 pub mod mesh;
 pub mod entity;
+pub mod simplify;
 
 use bytemuck::{Pod, Zeroable};
 use wgpu::Buffer;
 
+use super::{
+    bounds::{Aabb, BoundingSphere},
+    material::Material,
+    resource::RenderResource,
+};
+
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct Vertex {
@@ -11,6 +18,12 @@ pub struct Vertex {
     _tex_coord: [f32; 2],
 }
 
+impl Vertex {
+    pub fn position(&self) -> glam::Vec3 {
+        glam::Vec4::from(self._pos).truncate()
+    }
+}
+
 pub const fn vertex(pos: [i8; 3], tc: [i8; 2]) -> Vertex {
     Vertex {
         _pos: [pos[0] as f32, pos[1] as f32, pos[2] as f32, 1.0],
@@ -24,10 +37,31 @@ pub trait RenderData {
     fn index_data() -> &'static [u16];
 }
 
-pub trait Renderable {
+pub trait Renderable: Send + Sync {
     fn vertex_buf(&self) -> &Buffer;
     fn index_buf(&self) -> &Buffer;
     fn vertex_cnt(&self) -> usize;
 
-    fn model_matrix(&self) -> glam::Mat4;
+    /// The mesh's offset from the scene node it is attached to
+    fn model_matrix(&self) -> glam::Mat4 {
+        glam::Mat4::IDENTITY
+    }
+
+    fn material(&self) -> Material {
+        Material::default()
+    }
+
+    /// Bounds in model space, objects without bounds are never culled
+    fn aabb(&self) -> Option<Aabb> {
+        None
+    }
+
+    fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        None
+    }
+
+    /// A lower detail mesh to draw instead when the object covers `screen_size` of the screen height
+    fn lod(&self, _screen_size: f32) -> Option<&RenderResource> {
+        None
+    }
 }
@@ -0,0 +1,512 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet},
+};
+
+use super::Vertex;
+
+/// A symmetric 4x4 error quadric, the sum of squared distances to a set of planes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(a: f64, b: f64, c: f64, d: f64, weight: f64) -> Self {
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|v| v * weight),
+        )
+    }
+
+    fn add(&self, other: &Quadric) -> Quadric {
+        Quadric(std::array::from_fn(|i| self.0[i] + other.0[i]))
+    }
+
+    fn error(&self, p: glam::Vec3) -> f64 {
+        let (x, y, z) = (p.x as f64, p.y as f64, p.z as f64);
+        let q = &self.0;
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+/// Collapsing `from` into `to` costs `cost`, `version` invalidates stale heap entries
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed, so that the `BinaryHeap` pops the cheapest collapse first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// How much more moving off a boundary or seam costs than moving off a face
+const BOUNDARY_WEIGHT: f64 = 100.0;
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// The root of `v` in the union-find forest `parents`
+fn island(parents: &mut [usize], mut v: usize) -> usize {
+    while parents[v] != v {
+        parents[v] = parents[parents[v]];
+        v = parents[v];
+    }
+    v
+}
+
+struct Simplifier {
+    /// The positions of the welded vertices
+    positions: Vec<glam::Vec3>,
+    /// The original vertices of each welded one
+    originals: Vec<Vec<usize>>,
+    /// The connected part of the original mesh each original vertex is in,
+    /// its attributes only being continuous within it
+    islands: Vec<usize>,
+    /// Triangles of welded vertices
+    triangles: Vec<[usize; 3]>,
+    /// The original vertex of each corner of `triangles`
+    corners: Vec<[usize; 3]>,
+    removed: Vec<bool>,
+    vertex_triangles: Vec<BTreeSet<usize>>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    heap: BinaryHeap<Collapse>,
+}
+
+impl Simplifier {
+    fn new(vertices: &[Vertex], indices: &[u16]) -> Self {
+        // Vertices split only for their attributes share a position
+        let mut welded_of = HashMap::new();
+        let mut positions = Vec::new();
+        let mut originals = Vec::<Vec<usize>>::new();
+        let welded = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, vertex)| {
+                // Adding zero turns -0.0 into 0.0
+                let position = vertex.position() + glam::Vec3::ZERO;
+                let welded = *welded_of
+                    .entry(position.to_array().map(f32::to_bits))
+                    .or_insert_with(|| {
+                        positions.push(position);
+                        originals.push(Vec::new());
+                        positions.len() - 1
+                    });
+                originals[welded].push(i);
+                welded
+            })
+            .collect::<Vec<_>>();
+        let corners = indices
+            .chunks_exact(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+            .collect::<Vec<_>>();
+        let triangles = corners
+            .iter()
+            .map(|corner| corner.map(|v| welded[v]))
+            .collect::<Vec<_>>();
+
+        let mut vertex_triangles = vec![BTreeSet::new(); positions.len()];
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        let mut islands = (0..vertices.len()).collect::<Vec<_>>();
+        // The original edges along each welded edge, and the triangles on it
+        let mut edges = BTreeMap::<(usize, usize), (HashSet<(usize, usize)>, Vec<usize>)>::new();
+        for (i, (triangle, corner)) in triangles.iter().zip(&corners).enumerate() {
+            let [a, b, c] = triangle.map(|v| positions[v]);
+            let normal = (b - a).cross(c - a);
+            let area = normal.length() as f64 * 0.5;
+            let normal = normal.normalize_or_zero();
+            let quadric = Quadric::from_plane(
+                normal.x as f64,
+                normal.y as f64,
+                normal.z as f64,
+                -normal.dot(a) as f64,
+                area,
+            );
+            for v in *triangle {
+                vertex_triangles[v].insert(i);
+                quadrics[v] = quadrics[v].add(&quadric);
+            }
+            for k in 0..3 {
+                let original = edge_key(corner[k], corner[(k + 1) % 3]);
+                let (a, b) = (
+                    island(&mut islands, original.0),
+                    island(&mut islands, original.1),
+                );
+                islands[a] = b;
+                let edge = edges
+                    .entry(edge_key(triangle[k], triangle[(k + 1) % 3]))
+                    .or_default();
+                edge.0.insert(original);
+                edge.1.push(i);
+            }
+        }
+        // Planes through the boundary and seam edges, perpendicular to their
+        // triangles, keep them from moving inward or sliding across
+        for ((a, b), (original, edge_triangles)) in edges {
+            if edge_triangles.len() > 1 && original.len() == 1 {
+                continue;
+            }
+            let (pa, pb) = (positions[a], positions[b]);
+            for t in edge_triangles {
+                let [p0, p1, p2] = triangles[t].map(|v| positions[v]);
+                let face_normal = (p1 - p0).cross(p2 - p0);
+                let normal = (pb - pa).cross(face_normal).normalize_or_zero();
+                let quadric = Quadric::from_plane(
+                    normal.x as f64,
+                    normal.y as f64,
+                    normal.z as f64,
+                    -normal.dot(pa) as f64,
+                    BOUNDARY_WEIGHT * pa.distance_squared(pb) as f64,
+                );
+                quadrics[a] = quadrics[a].add(&quadric);
+                quadrics[b] = quadrics[b].add(&quadric);
+            }
+        }
+
+        let mut simplifier = Self {
+            removed: vec![false; triangles.len()],
+            versions: vec![0; positions.len()],
+            positions,
+            originals,
+            islands: (0..vertices.len())
+                .map(|v| island(&mut islands, v))
+                .collect(),
+            triangles,
+            corners,
+            vertex_triangles,
+            quadrics,
+            heap: BinaryHeap::new(),
+        };
+        for v in 0..simplifier.positions.len() {
+            simplifier.push_collapses(v);
+        }
+        simplifier
+    }
+
+    fn neighbors(&self, v: usize) -> BTreeSet<usize> {
+        self.vertex_triangles[v]
+            .iter()
+            .flat_map(|&t| self.triangles[t])
+            .filter(|&n| n != v)
+            .collect()
+    }
+
+    /// Queues collapsing `v` into each of its neighbors
+    fn push_collapses(&mut self, v: usize) {
+        for n in self.neighbors(v) {
+            let cost = self.quadrics[v]
+                .add(&self.quadrics[n])
+                .error(self.positions[n]);
+            self.heap.push(Collapse {
+                cost,
+                from: v,
+                to: n,
+                version: self.versions[v] + self.versions[n],
+            });
+        }
+    }
+
+    /// The link condition: the end points of the edge may only share the
+    /// vertices across its triangles, or the collapse pinches the surface
+    fn keeps_manifold(&self, from: usize, to: usize) -> bool {
+        let opposite = self.vertex_triangles[from]
+            .iter()
+            .map(|&t| self.triangles[t])
+            .filter(|triangle| triangle.contains(&to))
+            .flatten()
+            .filter(|&v| v != from && v != to)
+            .collect::<HashSet<_>>();
+        let to_neighbors = self.neighbors(to);
+        self.neighbors(from)
+            .iter()
+            .filter(|v| to_neighbors.contains(v))
+            .all(|v| opposite.contains(v))
+    }
+
+    /// Moving `from` onto `to` must not flip nor duplicate any triangle that survives the collapse
+    fn flips(&self, from: usize, to: usize) -> bool {
+        self.vertex_triangles[from].iter().any(|&t| {
+            let triangle = self.triangles[t];
+            if triangle.contains(&to) {
+                return false;
+            }
+            let moved = triangle.map(|v| if v == from { to } else { v });
+            let [a, b, c] = triangle.map(|v| self.positions[v]);
+            let [na, nb, nc] = moved.map(|v| self.positions[v]);
+            (b - a).cross(c - a).dot((nb - na).cross(nc - na)) <= 0.0
+                || self.vertex_triangles[to]
+                    .iter()
+                    .any(|&other| self.triangles[other].iter().all(|v| moved.contains(v)))
+        })
+    }
+
+    /// The original vertex at `to` to replace the corner `original` with,
+    /// preferably one of the same island so that attributes stay continuous
+    fn replacement(&self, original: usize, to: usize) -> usize {
+        let candidates = &self.originals[to];
+        candidates
+            .iter()
+            .copied()
+            .find(|&candidate| self.islands[candidate] == self.islands[original])
+            .unwrap_or(candidates[0])
+    }
+
+    fn collapse(&mut self, from: usize, to: usize) -> usize {
+        let mut removed = 0;
+        for t in std::mem::take(&mut self.vertex_triangles[from]) {
+            if self.triangles[t].contains(&to) {
+                self.removed[t] = true;
+                removed += 1;
+                for v in self.triangles[t] {
+                    self.vertex_triangles[v].remove(&t);
+                }
+            } else {
+                for k in 0..3 {
+                    if self.triangles[t][k] == from {
+                        self.triangles[t][k] = to;
+                        self.corners[t][k] = self.replacement(self.corners[t][k], to);
+                    }
+                }
+                self.vertex_triangles[to].insert(t);
+            }
+        }
+        self.quadrics[to] = self.quadrics[to].add(&self.quadrics[from]);
+        self.versions[from] += 1;
+        self.versions[to] += 1;
+
+        for n in self.neighbors(to) {
+            self.versions[n] += 1;
+            self.push_collapses(n);
+        }
+        self.push_collapses(to);
+        removed
+    }
+
+    fn run(&mut self, target_triangles: usize) {
+        let mut triangle_cnt = self.triangles.len();
+        while triangle_cnt > target_triangles {
+            let Some(collapse) = self.heap.pop() else {
+                break;
+            };
+            if collapse.version != self.versions[collapse.from] + self.versions[collapse.to]
+                || self.vertex_triangles[collapse.from].is_empty()
+                || !self.keeps_manifold(collapse.from, collapse.to)
+                || self.flips(collapse.from, collapse.to)
+            {
+                continue;
+            }
+            triangle_cnt -= self.collapse(collapse.from, collapse.to);
+        }
+    }
+
+    fn finish(self, vertices: &[Vertex]) -> (Vec<Vertex>, Vec<u16>) {
+        let mut remap = vec![None; vertices.len()];
+        let mut out_vertices = Vec::new();
+        let mut out_indices = Vec::new();
+        for (corner, removed) in self.corners.iter().zip(self.removed) {
+            if removed {
+                continue;
+            }
+            for &v in corner {
+                let index = *remap[v].get_or_insert_with(|| {
+                    out_vertices.push(vertices[v]);
+                    out_vertices.len() as u16 - 1
+                });
+                out_indices.push(index);
+            }
+        }
+        (out_vertices, out_indices)
+    }
+}
+
+/// Reduces a triangle list to about `ratio` of its triangles with quadric error metrics.
+///
+/// Vertices at the same position are welded first, so that meshes split along
+/// their UV seams don't tear open. Edges are collapsed onto one of their end
+/// points, so the remaining vertices keep their original attributes, and
+/// boundaries and seams are kept in place.
+pub fn simplify(vertices: &[Vertex], indices: &[u16], ratio: f32) -> (Vec<Vertex>, Vec<u16>) {
+    let target_triangles = ((indices.len() / 3) as f32 * ratio.clamp(0.0, 1.0)) as usize;
+
+    let mut simplifier = Simplifier::new(vertices, indices);
+    simplifier.run(target_triangles);
+    simplifier.finish(vertices)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::render::primitive::{entity::cube::Cube, RenderData};
+
+    fn vertex(position: glam::Vec3, tex_coord: glam::Vec2) -> Vertex {
+        Vertex {
+            _pos: position.extend(1.0).to_array(),
+            _tex_coord: tex_coord.to_array(),
+        }
+    }
+
+    /// A cube from -1 to 1 with `n` by `n` quads per face, each face with its own vertices
+    fn subdivided_cube(n: u16) -> (Vec<Vertex>, Vec<u16>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for normal in [
+            glam::Vec3::X,
+            glam::Vec3::NEG_X,
+            glam::Vec3::Y,
+            glam::Vec3::NEG_Y,
+            glam::Vec3::Z,
+            glam::Vec3::NEG_Z,
+        ] {
+            let u = normal.any_orthonormal_vector();
+            let v = normal.cross(u);
+            let start = vertices.len() as u16;
+            for j in 0..=n {
+                for i in 0..=n {
+                    let uv = glam::Vec2::new(i as f32, j as f32) / n as f32;
+                    let (s, t) = (uv.x * 2.0 - 1.0, uv.y * 2.0 - 1.0);
+                    vertices.push(vertex(normal + u * s + v * t, uv));
+                }
+            }
+            for j in 0..n {
+                for i in 0..n {
+                    let a = start + j * (n + 1) + i;
+                    let (b, c, d) = (a + 1, a + n + 2, a + n + 1);
+                    indices.extend([a, b, c, c, d, a]);
+                }
+            }
+        }
+        (vertices, indices)
+    }
+
+    /// Every edge between welded positions has exactly two triangles, on
+    /// opposite sides
+    fn assert_watertight(vertices: &[Vertex], indices: &[u16]) {
+        let key = |i: u16| vertices[i as usize].position().to_array().map(f32::to_bits);
+        let mut edges = HashMap::new();
+        for triangle in indices.chunks_exact(3) {
+            for k in 0..3 {
+                let edge = (key(triangle[k]), key(triangle[(k + 1) % 3]));
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+        for (&(a, b), &count) in &edges {
+            assert_eq!(count, 1, "edge used twice in the same direction");
+            assert_eq!(edges.get(&(b, a)), Some(&1), "open edge");
+        }
+    }
+
+    fn bounds(vertices: &[Vertex]) -> (glam::Vec3, glam::Vec3) {
+        vertices
+            .iter()
+            .map(Vertex::position)
+            .fold((glam::Vec3::MAX, glam::Vec3::MIN), |(min, max), p| {
+                (min.min(p), max.max(p))
+            })
+    }
+
+    #[test]
+    fn the_test_cube_is_watertight() {
+        let (vertices, indices) = subdivided_cube(4);
+        assert_eq!(indices.len() / 3, 192);
+        assert_watertight(&vertices, &indices);
+    }
+
+    #[test]
+    fn simplifies_flat_faces_without_tearing() {
+        let (vertices, indices) = subdivided_cube(4);
+        let (out_vertices, out_indices) = simplify(&vertices, &indices, 0.25);
+        assert_eq!(out_indices.len() / 3, 48);
+        assert_watertight(&out_vertices, &out_indices);
+        assert_eq!(
+            bounds(&out_vertices),
+            (glam::Vec3::splat(-1.0), glam::Vec3::splat(1.0))
+        );
+    }
+
+    #[test]
+    fn keeps_the_corners_down_to_a_plain_cube() {
+        let (vertices, indices) = subdivided_cube(4);
+        let (out_vertices, out_indices) = simplify(&vertices, &indices, 12.0 / 192.0);
+        assert_eq!(out_indices.len() / 3, 12);
+        assert_watertight(&out_vertices, &out_indices);
+        for p in out_vertices.iter().map(Vertex::position) {
+            assert_eq!(p.abs(), glam::Vec3::ONE, "{p} isn't a corner");
+        }
+    }
+
+    #[test]
+    fn keeps_the_attributes_of_each_face() {
+        let (vertices, indices) = subdivided_cube(4);
+        let (out_vertices, out_indices) = simplify(&vertices, &indices, 0.25);
+        // Each corner still has the texture coordinates of its own face
+        for triangle in out_indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|k| out_vertices[triangle[k] as usize]);
+            let [a, b, c] = corners.map(|v| v.position());
+            let normal = (b - a).cross(c - a).normalize().round();
+            let u = normal.any_orthonormal_vector();
+            let v = normal.cross(u);
+            for corner in corners {
+                let uv = glam::Vec2::from(corner._tex_coord) * 2.0 - 1.0;
+                let expected = normal + u * uv.x + v * uv.y;
+                assert!(
+                    corner.position().distance(expected) < 1e-5,
+                    "{normal} {} {expected}",
+                    corner.position()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_plain_cube_stays_closed() {
+        let (vertices, indices) = simplify(Cube::vertex_data(), Cube::index_data(), 1.0);
+        assert_eq!(indices.len(), Cube::index_data().len());
+        assert_eq!(vertices.len(), Cube::vertex_data().len());
+        assert_watertight(&vertices, &indices);
+
+        let (vertices, indices) = simplify(Cube::vertex_data(), Cube::index_data(), 0.5);
+        assert!(indices.len() / 3 <= 6);
+        assert_watertight(&vertices, &indices);
+    }
+}
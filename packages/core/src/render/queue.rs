@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use super::{
+    bounds::Frustum, camera::Camera, material::Material, primitive::Renderable,
+    resource::RenderResource, scene::Scene,
+};
+
+pub struct RenderItem<'a> {
+    pub renderable: &'a Arc<dyn Renderable>,
+    pub model: glam::Mat4,
+    pub material: Material,
+    /// How far the object's origin is in front of the camera, along its view direction
+    pub depth: f32,
+    /// The level of detail selected for this frame, `None` for the renderable's own mesh
+    pub lod: Option<&'a RenderResource>,
+}
+
+impl RenderItem<'_> {
+    pub fn vertex_buf(&self) -> &wgpu::Buffer {
+        self.lod
+            .map_or_else(|| self.renderable.vertex_buf(), |lod| &lod.vertex_buf)
+    }
+
+    pub fn index_buf(&self) -> &wgpu::Buffer {
+        self.lod
+            .map_or_else(|| self.renderable.index_buf(), |lod| &lod.index_buf)
+    }
+
+    pub fn vertex_cnt(&self) -> usize {
+        self.lod
+            .map_or_else(|| self.renderable.vertex_cnt(), |lod| lod.vertex_cnt)
+    }
+}
+
+/// Objects drawn and culled by the last frame, a resource
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub total: usize,
+    pub visible: usize,
+    pub culled: usize,
+}
+
+/// The objects of a [`Scene`] that intersect the camera frustum in draw order:
+/// opaque ones front-to-back, then transparent ones back-to-front.
+pub struct RenderQueue<'a> {
+    pub opaque: Vec<RenderItem<'a>>,
+    pub transparent: Vec<RenderItem<'a>>,
+    pub culling_stats: CullingStats,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new(scene: &'a Scene, camera: &Camera) -> Self {
+        let frustum = Frustum::from_view_projection(camera.view_projection_mat());
+        let (camera_position, camera_front) = (camera.position(), camera.front());
+
+        let total = scene.render_objects().count();
+        let (mut opaque, mut transparent): (Vec<_>, Vec<_>) = scene
+            .render_objects()
+            .filter_map(|(world, renderable)| {
+                let model = world * renderable.model_matrix();
+                let sphere = renderable
+                    .bounding_sphere()
+                    .map(|sphere| sphere.transformed(model));
+
+                // Test the cheap bounding sphere first and only then the tighter AABB
+                if sphere.is_some_and(|sphere| !frustum.intersects_sphere(&sphere)) {
+                    return None;
+                }
+                if let Some(aabb) = renderable.aabb() {
+                    if !frustum.intersects_aabb(&aabb.transformed(model)) {
+                        return None;
+                    }
+                }
+
+                Some(RenderItem {
+                    renderable,
+                    model,
+                    material: renderable.material(),
+                    depth: (model.w_axis.truncate() - camera_position).dot(camera_front),
+                    lod: sphere.and_then(|sphere| renderable.lod(camera.projected_size(&sphere))),
+                })
+            })
+            .partition(|item| !item.material.blend_mode.is_transparent());
+
+        opaque.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+        transparent.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+        let visible = opaque.len() + transparent.len();
+        Self {
+            opaque,
+            transparent,
+            culling_stats: CullingStats {
+                total,
+                visible,
+                culled: total - visible,
+            },
+        }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &RenderItem<'a>> {
+        self.opaque.iter().chain(self.transparent.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.opaque.len() + self.transparent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -1,8 +1,16 @@
-use wgpu::{naga::proc::index, util::DeviceExt};
+use wgpu::util::DeviceExt;
 
 use super::{
-    pipeline::{CubePipeline, HelloTrianglePipeline, Pipeline},
-    primitive::{entity::{cube::Cube, RenderObject}, mesh::Mesh, RenderData, Renderable},
+    bounds::{Aabb, BoundingSphere},
+    lod::LodLevel,
+    pipeline::Pipeline,
+    primitive::{
+        entity::{cube::Cube, RenderObject},
+        simplify::simplify,
+        RenderData, Renderable, Vertex,
+    },
+    sprite::atlas::TextureAtlas,
+    texture::Texture,
     wgpu_context::WgpuContext,
 };
 use std::{any::TypeId, collections::HashMap, sync::Arc};
@@ -15,12 +23,20 @@ pub struct Resource {
     pipelines: HashMap<TypeId, Box<dyn Pipeline>>,
     meshes: HashMap<String, Arc<dyn Renderable>>,
     render_resources: HashMap<String, Arc<RenderResource>>,
+    /// The levels loaded for each render resource, given to the objects created from it
+    lods: HashMap<String, Vec<LodLevel>>,
+    textures: HashMap<String, Arc<Texture>>,
 }
 
 pub struct RenderResource {
     pub vertex_cnt: usize,
     pub vertex_buf: wgpu::Buffer,
     pub index_buf: wgpu::Buffer,
+    pub aabb: Aabb,
+    pub bounding_sphere: BoundingSphere,
+    /// Kept on the CPU to build the LODs from
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u16>,
 }
 
 impl Resource {
@@ -30,25 +46,35 @@ impl Resource {
             pipelines: HashMap::new(),
             meshes: HashMap::new(),
             render_resources: HashMap::new(),
+            lods: HashMap::new(),
+            textures: HashMap::new(),
         }
     }
 
     pub fn init(&mut self) {
-        self.pipelines.insert(
-            TypeId::of::<HelloTrianglePipeline>(),
-            Box::new(HelloTrianglePipeline::new(&self.context)),
-        );
-        self.pipelines.insert(
-            TypeId::of::<CubePipeline>(),
-            Box::new(CubePipeline::new(&self.context)),
-        );
-
         self.load_render_resource::<Cube>();
     }
 
+    /// Creates the pipeline `P`, returns `false` if it was already registered
+    pub fn add_pipeline<P: Pipeline + 'static>(&mut self) -> bool {
+        if self.pipelines.contains_key(&TypeId::of::<P>()) {
+            return false;
+        }
+        self.pipelines
+            .insert(TypeId::of::<P>(), Box::new(P::new(&self.context)));
+        true
+    }
+
     pub fn load_render_resource<M: RenderData>(&mut self) -> Arc<RenderResource> {
-        let (vertex_data, index_data) = (M::vertex_data(), M::index_data());
+        self.load_render_resource_from(M::identifier(), M::vertex_data(), M::index_data())
+    }
 
+    pub fn load_render_resource_from(
+        &mut self,
+        name: &str,
+        vertex_data: &[Vertex],
+        index_data: &[u16],
+    ) -> Arc<RenderResource> {
         let vertex_cnt = index_data.len();
         let vertex_buf =
             self.context
@@ -66,36 +92,123 @@ impl Resource {
                 contents: bytemuck::cast_slice(index_data),
                 usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
             });
+        let positions = vertex_data.iter().map(Vertex::position);
         let render_resource = RenderResource {
-            vertex_cnt: vertex_cnt,
+            vertex_cnt,
             vertex_buf,
             index_buf,
+            aabb: Aabb::from_points(positions.clone()).unwrap(),
+            bounding_sphere: BoundingSphere::from_points(positions).unwrap(),
+            vertices: vertex_data.to_vec(),
+            indices: index_data.to_vec(),
         };
 
         let render_resource = Arc::new(render_resource);
         self.render_resources
-            .insert(M::identifier().to_string(), render_resource.clone());
+            .insert(name.to_string(), render_resource.clone());
         render_resource
     }
 
-    pub fn create_render_object<M: RenderData>(
+    /// Simplifies `M` to each `(ratio, screen_size)` in `levels`, see [`Resource::load_lod_levels_from`]
+    pub fn load_lod_levels<M: RenderData>(&mut self, levels: &[(f32, f32)]) -> Vec<LodLevel> {
+        self.load_lod_levels_from(M::identifier(), M::vertex_data(), M::index_data(), levels)
+    }
+
+    /// Like [`Resource::load_lod_levels`] for a mesh loaded under `name`, e.g. an imported one
+    pub fn load_lod_levels_by_name(
         &mut self,
-        position: glam::Vec3,
-        rotation: glam::Vec3,
-        scale: glam::Vec3,
-    ) -> RenderObject {
-        let render_resource = match self.render_resources.get(M::identifier()) {
-            Some(resource) => resource.clone(),
-            None => self.load_render_resource::<M>(),
-        };
+        name: &str,
+        levels: &[(f32, f32)],
+    ) -> Option<Vec<LodLevel>> {
+        let resource = self.get_render_resource(name)?;
+        Some(self.load_lod_levels_from(name, &resource.vertices, &resource.indices, levels))
+    }
+
+    /// Simplifies the mesh `name` to each `(ratio, screen_size)` in `levels`,
+    /// see [`simplify`] and [`LodLevel`]. The render objects created from
+    /// `name` from now on draw them.
+    pub fn load_lod_levels_from(
+        &mut self,
+        name: &str,
+        vertex_data: &[Vertex],
+        index_data: &[u16],
+        levels: &[(f32, f32)],
+    ) -> Vec<LodLevel> {
+        let mut levels = levels
+            .iter()
+            .map(|&(ratio, screen_size)| {
+                let lod_name = format!("{name}@lod{ratio}");
+                let resource = match self.render_resources.get(&lod_name) {
+                    Some(resource) => resource.clone(),
+                    None => {
+                        let (vertices, indices) = simplify(vertex_data, index_data, ratio);
+                        self.load_render_resource_from(&lod_name, &vertices, &indices)
+                    }
+                };
+                LodLevel {
+                    resource,
+                    screen_size,
+                }
+            })
+            .collect::<Vec<_>>();
+        levels.sort_by(|a, b| b.screen_size.total_cmp(&a.screen_size));
+        self.lods.insert(name.to_string(), levels.clone());
+        levels
+    }
 
-        RenderObject::new(render_resource, position, rotation, scale)
+    pub fn create_render_object<M: RenderData>(&mut self) -> RenderObject {
+        if !self.render_resources.contains_key(M::identifier()) {
+            self.load_render_resource::<M>();
+        }
+        self.create_render_object_by_name(M::identifier()).unwrap()
+    }
+
+    /// Like [`Resource::create_render_object`] for a mesh loaded under `name`
+    pub fn create_render_object_by_name(&self, name: &str) -> Option<RenderObject> {
+        let render_object = RenderObject::new(self.get_render_resource(name)?);
+        Some(match self.lods.get(name) {
+            Some(lods) => render_object.with_lods(lods.clone()),
+            None => render_object,
+        })
+    }
+
+    pub fn load_texture(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+    ) -> Arc<Texture> {
+        let texture = Arc::new(Texture::from_rgba8(
+            &self.context,
+            name,
+            width,
+            height,
+            rgba,
+        ));
+        self.textures.insert(name.to_string(), texture.clone());
+        texture
+    }
+
+    pub fn load_texture_atlas(&mut self, name: &str, atlas: &TextureAtlas) -> Arc<Texture> {
+        self.load_texture(name, atlas.width, atlas.height, &atlas.data)
+    }
+
+    pub fn get_texture(&self, name: &str) -> Option<Arc<Texture>> {
+        self.textures.get(name).cloned()
+    }
+
+    pub fn get_render_resource(&self, name: &str) -> Option<Arc<RenderResource>> {
+        self.render_resources.get(name).cloned()
     }
 
     pub fn get_pipeline<T: Pipeline + 'static>(&self) -> Option<&dyn Pipeline> {
-        self.pipelines.get(&TypeId::of::<T>()).map(|b| &**b)
+        self.get_pipeline_by_id(TypeId::of::<T>())
+    }
+    pub fn get_pipeline_by_id(&self, id: TypeId) -> Option<&dyn Pipeline> {
+        self.pipelines.get(&id).map(|b| &**b)
     }
     pub fn get_mesh(&self, name: &str) -> Option<Arc<dyn Renderable>> {
-        self.meshes.get(name).map(|m| m.clone())
+        self.meshes.get(name).cloned()
     }
 }
@@ -1,22 +1,333 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use super::primitive::Renderable;
+use super::{
+    light::Light, particle::ParticleEffect, primitive::Renderable, sprite::Sprite,
+    transform::Transform,
+};
+
+/// A handle to a [`Node`] of a [`Scene`], it stays invalid once its node is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+pub struct Node {
+    name: String,
+    local: Transform,
+    world: glam::Mat4,
+    dirty: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    renderable: Option<Arc<dyn Renderable>>,
+    light: Option<Light>,
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.local
+    }
+
+    /// The transform relative to the scene root as of the last [`Scene::update_world_transforms`]
+    pub fn world_transform(&self) -> glam::Mat4 {
+        self.world
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn renderable(&self) -> Option<&Arc<dyn Renderable>> {
+        self.renderable.as_ref()
+    }
+
+    pub fn light(&self) -> Option<&Light> {
+        self.light.as_ref()
+    }
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
 
 #[derive(Default)]
 pub struct Scene {
-    render_objects: Vec<Arc<dyn Renderable>>,
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+    roots: Vec<NodeId>,
+    sprites: Vec<Sprite>,
+    particle_effects: Vec<ParticleEffect>,
 }
 
 impl Scene {
     pub fn new() -> Self {
-        Self { render_objects: Vec::new() }
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            roots: Vec::new(),
+            sprites: Vec::new(),
+            particle_effects: Vec::new(),
+        }
+    }
+
+    /// Adds a node under `parent`, or as a root when `parent` is `None`
+    pub fn add_node(
+        &mut self,
+        name: impl Into<String>,
+        parent: Option<NodeId>,
+        local: Transform,
+    ) -> NodeId {
+        let parent = parent.filter(|parent| self.node(*parent).is_some());
+        let node = Node {
+            name: name.into(),
+            local,
+            world: local.matrix(),
+            dirty: true,
+            parent,
+            children: Vec::new(),
+            renderable: None,
+            light: None,
+        };
+        let id = match self.free_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.node = Some(node);
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                NodeId {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+
+        match parent {
+            Some(parent) => self.node_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    /// Adds `render_object` as a new root node
+    pub fn add_render_object(
+        &mut self,
+        render_object: Arc<dyn Renderable>,
+        transform: Transform,
+    ) -> NodeId {
+        let id = self.add_node("", None, transform);
+        self.set_renderable(id, Some(render_object));
+        id
+    }
+
+    pub fn set_renderable(&mut self, id: NodeId, renderable: Option<Arc<dyn Renderable>>) {
+        if let Some(node) = self.node_mut(id) {
+            node.renderable = renderable;
+        }
+    }
+
+    pub fn set_light(&mut self, id: NodeId, light: Option<Light>) {
+        if let Some(node) = self.node_mut(id) {
+            node.light = light;
+        }
+    }
+
+    /// Removes `id` together with all of its descendants
+    pub fn remove_node(&mut self, id: NodeId) {
+        let Some(parent) = self.node(id).map(|node| node.parent) else {
+            return;
+        };
+        self.detach(id, parent);
+
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let slot = &mut self.slots[id.index as usize];
+            if let Some(node) = slot.node.take() {
+                stack.extend(node.children);
+                slot.generation += 1;
+                self.free_slots.push(id.index);
+            }
+        }
+    }
+
+    /// Moves `id` under `parent` keeping its local transform.
+    ///
+    /// Returns `false` without changing anything when a node is missing or
+    /// `parent` is `id` itself or one of its descendants.
+    pub fn reparent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        let Some(old_parent) = self.node(id).map(|node| node.parent) else {
+            return false;
+        };
+        if let Some(parent) = parent {
+            if self.node(parent).is_none() || self.ancestors(parent).any(|a| a == id) {
+                return false;
+            }
+        }
+
+        self.detach(id, old_parent);
+        match parent {
+            Some(parent) => self.node_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        let node = self.node_mut(id).unwrap();
+        node.parent = parent;
+        node.dirty = true;
+        true
+    }
+
+    fn detach(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let siblings = match parent {
+            Some(parent) => &mut self.node_mut(parent).unwrap().children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|sibling| *sibling != id);
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_ref())
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_mut())
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        if let Some(transform_mut) = self.transform_mut(id) {
+            *transform_mut = transform;
+        }
+    }
+
+    /// The local transform of `id`, its world transform is updated by the next [`Scene::update`]
+    pub fn transform_mut(&mut self, id: NodeId) -> Option<&mut Transform> {
+        self.node_mut(id).map(|node| {
+            node.dirty = true;
+            &mut node.local
+        })
+    }
+
+    /// The first node called `name`, in depth-first order
+    pub fn find_node(&self, name: &str) -> Option<NodeId> {
+        self.iter()
+            .find(|(_, node)| node.name == name)
+            .map(|(id, _)| id)
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// The parent of `id`, its grandparent and so on
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.node(id).and_then(|node| node.parent), |id| {
+            self.node(*id).and_then(|node| node.parent)
+        })
+    }
+
+    /// `id` and all of its descendants, depth-first
+    pub fn descendants(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &Node)> {
+        let mut stack = vec![id];
+        std::iter::from_fn(move || {
+            while let Some(id) = stack.pop() {
+                if let Some(node) = self.node(id) {
+                    stack.extend(node.children.iter().rev());
+                    return Some((id, node));
+                }
+            }
+            None
+        })
+    }
+
+    /// All nodes, depth-first from each root
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.roots.iter().flat_map(|root| self.descendants(*root))
+    }
+
+    /// World transforms and renderables of the nodes that have one
+    pub fn render_objects(&self) -> impl Iterator<Item = (glam::Mat4, &Arc<dyn Renderable>)> {
+        self.iter().filter_map(|(_, node)| {
+            node.renderable
+                .as_ref()
+                .map(|renderable| (node.world, renderable))
+        })
+    }
+
+    /// World transforms and lights of the nodes that have one
+    pub fn lights(&self) -> impl Iterator<Item = (glam::Mat4, &Light)> {
+        self.iter()
+            .filter_map(|(_, node)| node.light.as_ref().map(|light| (node.world, light)))
+    }
+
+    /// Recomputes the world transform of every dirty node and its descendants
+    pub fn update_world_transforms(&mut self) {
+        let mut stack = self
+            .roots
+            .iter()
+            .map(|root| (*root, glam::Mat4::IDENTITY, false))
+            .collect::<Vec<_>>();
+        while let Some((id, parent_world, parent_changed)) = stack.pop() {
+            let Some(node) = self.node_mut(id) else {
+                continue;
+            };
+            let changed = node.dirty || parent_changed;
+            if changed {
+                node.world = parent_world * node.local.matrix();
+                node.dirty = false;
+            }
+            let world = node.world;
+            stack.extend(node.children.iter().map(|child| (*child, world, changed)));
+        }
+    }
+
+    pub fn sprites(&self) -> &Vec<Sprite> {
+        &self.sprites
+    }
+
+    pub fn sprites_mut(&mut self) -> &mut Vec<Sprite> {
+        &mut self.sprites
+    }
+
+    pub fn add_sprite(&mut self, sprite: Sprite) {
+        self.sprites.push(sprite);
+    }
+
+    pub fn particle_effects(&self) -> &Vec<ParticleEffect> {
+        &self.particle_effects
+    }
+
+    pub fn particle_effects_mut(&mut self) -> &mut Vec<ParticleEffect> {
+        &mut self.particle_effects
     }
 
-    pub fn render_objects(&self) -> &Vec<Arc<dyn Renderable>> {
-        &self.render_objects
+    pub fn add_particle_effect(&mut self, effect: ParticleEffect) {
+        self.particle_effects.push(effect);
     }
 
-    pub fn add_render_object(&mut self, render_object: Arc<dyn Renderable>) {
-        self.render_objects.push(render_object);
+    pub fn update(&mut self, delta_time: Duration) {
+        self.update_world_transforms();
+        for effect in &mut self.particle_effects {
+            effect.update(delta_time);
+        }
     }
 }
@@ -1,5 +1,6 @@
 struct VertexOutput {
     @location(0) tex_coord: vec2<f32>,
+    @location(1) world_position: vec3<f32>,
     @builtin(position) position: vec4<f32>,
 };
 
@@ -7,9 +8,15 @@ struct VertexOutput {
 @binding(0)
 var<uniform> view_projection_mat: mat4x4<f32>;
 
-@group(0)
-@binding(1)
-var<uniform> model_mat: mat4x4<f32>;
+struct Object {
+    model_mat: mat4x4<f32>,
+    color: vec4<f32>,
+    alpha_cutoff: f32,
+};
+
+@group(1)
+@binding(0)
+var<uniform> object: Object;
 
 @vertex
 fn vs_main(
@@ -17,20 +24,66 @@ fn vs_main(
     @location(1) tex_coord: vec2<f32>,
 ) -> VertexOutput {
     var result: VertexOutput;
+    let world_position = object.model_mat * position;
     result.tex_coord = tex_coord;
-    result.position = view_projection_mat * model_mat * position;
+    result.world_position = world_position.xyz;
+    result.position = view_projection_mat * world_position;
     return result;
 }
 
 @group(0)
-@binding(2)
+@binding(1)
 var r_color: texture_2d<u32>;
 
+// Mirrors `LightsUniform` in `render/light.rs`
+struct Light {
+    // A direction with `w == 0.0`, or a point light position with `w == 1.0`
+    position: vec4<f32>,
+    // The color times the intensity, with the range of point lights in `w`
+    color: vec4<f32>,
+};
+
+struct Lights {
+    lights: array<Light, 8>,
+    ambient: vec3<f32>,
+    count: u32,
+};
+
+@group(0)
+@binding(2)
+var<uniform> lights: Lights;
+
+// Lambert diffuse with the face normal, the vertices have no normals
+fn lighting(world_position: vec3<f32>) -> vec3<f32> {
+    if (lights.count == 0u) {
+        return vec3<f32>(1.0);
+    }
+    let normal = normalize(cross(dpdy(world_position), dpdx(world_position)));
+    var result = lights.ambient;
+    for (var i = 0u; i < lights.count; i++) {
+        let light = lights.lights[i];
+        var to_light = -light.position.xyz;
+        var attenuation = 1.0;
+        if (light.position.w == 1.0) {
+            to_light = light.position.xyz - world_position;
+            let falloff = max(1.0 - length(to_light) / light.color.w, 0.0);
+            attenuation = falloff * falloff;
+        }
+        result += light.color.rgb * max(dot(normal, normalize(to_light)), 0.0) * attenuation;
+    }
+    return result;
+}
+
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
     let tex = textureLoad(r_color, vec2<i32>(vertex.tex_coord * 256.0), 0);
     let v = f32(tex.x) / 255.0;
-    return vec4<f32>(1.0 - (v * 5.0), 1.0 - (v * 15.0), 1.0 - (v * 50.0), 1.0);
+    let base = vec4<f32>(1.0 - (v * 5.0), 1.0 - (v * 15.0), 1.0 - (v * 50.0), 1.0) * object.color;
+    let color = vec4<f32>(base.rgb * lighting(vertex.world_position), base.a);
+    if (color.a < object.alpha_cutoff) {
+        discard;
+    }
+    return color;
 }
 
 @fragment
@@ -0,0 +1,78 @@
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+};
+
+struct Params {
+    view_projection_mat: mat4x4<f32>,
+    camera_right: vec4<f32>,
+    camera_up: vec4<f32>,
+    gravity: vec3<f32>,
+    delta_time: f32,
+    color_over_life: array<vec4<f32>, 16>,
+    size_over_life: array<vec4<f32>, 4>,
+    particle_count: u32,
+};
+
+struct VertexOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+@group(0)
+@binding(0)
+var<uniform> params: Params;
+
+@group(0)
+@binding(1)
+var<storage, read> particles: array<Particle>;
+
+fn size_sample(index: u32) -> f32 {
+    return params.size_over_life[index / 4u][index % 4u];
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> VertexOutput {
+    var result: VertexOutput;
+    let particle = particles[instance_index];
+    if (particle.age >= particle.lifetime) {
+        // Degenerate triangle, nothing is rasterized
+        result.color = vec4<f32>(0.0);
+        result.position = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        return result;
+    }
+
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, -0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, 0.5),
+        vec2<f32>(-0.5, -0.5),
+    );
+    let corner = corners[vertex_index];
+
+    let t = clamp(particle.age / particle.lifetime, 0.0, 1.0) * 15.0;
+    let i0 = u32(floor(t));
+    let i1 = min(i0 + 1u, 15u);
+    let f = fract(t);
+    let color = mix(params.color_over_life[i0], params.color_over_life[i1], f);
+    let size = mix(size_sample(i0), size_sample(i1), f);
+
+    let world = particle.position
+        + (params.camera_right.xyz * corner.x + params.camera_up.xyz * corner.y) * size;
+
+    result.color = color;
+    result.position = params.view_projection_mat * vec4<f32>(world, 1.0);
+    return result;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    return vertex.color;
+}
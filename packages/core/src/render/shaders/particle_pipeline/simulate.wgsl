@@ -0,0 +1,42 @@
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+};
+
+struct Params {
+    view_projection_mat: mat4x4<f32>,
+    camera_right: vec4<f32>,
+    camera_up: vec4<f32>,
+    gravity: vec3<f32>,
+    delta_time: f32,
+    color_over_life: array<vec4<f32>, 16>,
+    size_over_life: array<vec4<f32>, 4>,
+    particle_count: u32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> params: Params;
+
+@group(0)
+@binding(1)
+var<storage, read_write> particles: array<Particle>;
+
+@compute
+@workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.particle_count) {
+        return;
+    }
+
+    var particle = particles[id.x];
+    if (particle.age >= particle.lifetime) {
+        return;
+    }
+    particle.velocity += params.gravity * params.delta_time;
+    particle.position += particle.velocity * params.delta_time;
+    particle.age += params.delta_time;
+    particles[id.x] = particle;
+}
@@ -0,0 +1,58 @@
+struct InstanceInput {
+    @location(0) position: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) uv_min: vec2<f32>,
+    @location(3) uv_max: vec2<f32>,
+    @location(4) tint: vec4<f32>,
+    @location(5) rotation: f32,
+};
+
+struct VertexOutput {
+    @location(0) tex_coord: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+@group(0)
+@binding(0)
+var<uniform> view_projection_mat: mat4x4<f32>;
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, -0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, 0.5),
+        vec2<f32>(-0.5, -0.5),
+    );
+    let corner = corners[vertex_index];
+
+    let c = cos(instance.rotation);
+    let s = sin(instance.rotation);
+    let local = corner * instance.size;
+    let world = instance.position + vec2<f32>(local.x * c - local.y * s, local.x * s + local.y * c);
+
+    var result: VertexOutput;
+    result.tex_coord = mix(instance.uv_min, instance.uv_max, vec2<f32>(corner.x + 0.5, 0.5 - corner.y));
+    result.tint = instance.tint;
+    result.position = view_projection_mat * vec4<f32>(world, 0.0, 1.0);
+    return result;
+}
+
+@group(1)
+@binding(0)
+var t_sprite: texture_2d<f32>;
+
+@group(1)
+@binding(1)
+var s_sprite: sampler;
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_sprite, s_sprite, vertex.tex_coord) * vertex.tint;
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use super::Rect;
+
+struct AtlasImage {
+    name: String,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+/// Packs loose RGBA8 images into a single texture using shelf packing.
+pub struct TextureAtlasBuilder {
+    max_width: u32,
+    padding: u32,
+    images: Vec<AtlasImage>,
+}
+
+/// The packed RGBA8 pixels of a [`TextureAtlasBuilder`] and where each image ended up.
+pub struct TextureAtlas {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+    rects: HashMap<String, Rect>,
+}
+
+impl TextureAtlasBuilder {
+    /// Images are placed on shelves no wider than `max_width`, an image wider
+    /// than that widens the atlas to fit it.
+    pub fn new(max_width: u32) -> Self {
+        Self {
+            max_width,
+            padding: 1,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn add_image(&mut self, name: impl Into<String>, width: u32, height: u32, data: Vec<u8>) {
+        let name = name.into();
+        assert_eq!(
+            data.len(),
+            (width * height * 4) as usize,
+            "[core/atlas]: rgba8 data of `{name}` does not match {width}x{height}"
+        );
+        self.images.push(AtlasImage {
+            name,
+            width,
+            height,
+            data,
+        });
+    }
+
+    pub fn build(mut self) -> TextureAtlas {
+        // Taller images first keeps the shelves tight
+        self.images
+            .sort_by(|a, b| b.height.cmp(&a.height).then(b.width.cmp(&a.width)));
+
+        let width = self
+            .images
+            .iter()
+            .map(|image| image.width + self.padding * 2)
+            .max()
+            .unwrap_or(1)
+            .max(self.max_width);
+
+        let mut placements = Vec::with_capacity(self.images.len());
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+        for image in &self.images {
+            let (w, h) = (
+                image.width + self.padding * 2,
+                image.height + self.padding * 2,
+            );
+            if x + w > width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            placements.push((x + self.padding, y + self.padding));
+            x += w;
+            shelf_height = shelf_height.max(h);
+        }
+        let height = (y + shelf_height).max(1);
+
+        let mut data = vec![0; (width * height * 4) as usize];
+        let mut rects = HashMap::with_capacity(self.images.len());
+        for (image, (px, py)) in self.images.iter().zip(placements) {
+            let row_len = (image.width * 4) as usize;
+            for row in 0..image.height {
+                let src = (row * image.width * 4) as usize;
+                let dst = (((py + row) * width + px) * 4) as usize;
+                data[dst..dst + row_len].copy_from_slice(&image.data[src..src + row_len]);
+            }
+
+            let size = glam::Vec2::new(width as f32, height as f32);
+            rects.insert(
+                image.name.clone(),
+                Rect::new(
+                    glam::Vec2::new(px as f32, py as f32) / size,
+                    glam::Vec2::new((px + image.width) as f32, (py + image.height) as f32) / size,
+                ),
+            );
+        }
+
+        TextureAtlas {
+            width,
+            height,
+            data,
+            rects,
+        }
+    }
+}
+
+impl TextureAtlas {
+    pub fn uv_rect(&self, name: &str) -> Option<Rect> {
+        self.rects.get(name).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `width`x`height` image filled with `value`
+    fn add(builder: &mut TextureAtlasBuilder, name: &str, width: u32, height: u32, value: u8) {
+        builder.add_image(
+            name,
+            width,
+            height,
+            vec![value; (width * height * 4) as usize],
+        );
+    }
+
+    /// The top-left corner of `name` in pixels
+    fn corner(atlas: &TextureAtlas, name: &str) -> (u32, u32) {
+        let min = atlas.uv_rect(name).unwrap().min
+            * glam::Vec2::new(atlas.width as f32, atlas.height as f32);
+        (min.x.round() as u32, min.y.round() as u32)
+    }
+
+    fn pixel(atlas: &TextureAtlas, x: u32, y: u32) -> u8 {
+        atlas.data[((y * atlas.width + x) * 4) as usize]
+    }
+
+    #[test]
+    fn packs_taller_images_first_on_shelves() {
+        let mut builder = TextureAtlasBuilder::new(16).with_padding(0);
+        add(&mut builder, "short", 8, 4, 1);
+        add(&mut builder, "tall", 8, 8, 2);
+        add(&mut builder, "small", 4, 4, 3);
+        add(&mut builder, "tall_too", 8, 8, 4);
+        let atlas = builder.build();
+
+        assert_eq!((atlas.width, atlas.height), (16, 12));
+        assert_eq!(corner(&atlas, "tall"), (0, 0));
+        assert_eq!(corner(&atlas, "tall_too"), (8, 0));
+        assert_eq!(corner(&atlas, "short"), (0, 8));
+        assert_eq!(corner(&atlas, "small"), (8, 8));
+        assert_eq!(
+            atlas.uv_rect("short"),
+            Some(Rect::new(
+                glam::Vec2::new(0.0, 8.0 / 12.0),
+                glam::Vec2::new(0.5, 1.0)
+            ))
+        );
+        assert_eq!(atlas.uv_rect("missing"), None);
+
+        assert_eq!(pixel(&atlas, 7, 7), 2);
+        assert_eq!(pixel(&atlas, 8, 0), 4);
+        assert_eq!(pixel(&atlas, 7, 11), 1);
+        assert_eq!(pixel(&atlas, 11, 11), 3);
+        // Left empty next to the small image
+        assert_eq!(pixel(&atlas, 12, 8), 0);
+    }
+
+    #[test]
+    fn pads_around_each_image() {
+        let mut builder = TextureAtlasBuilder::new(16).with_padding(1);
+        add(&mut builder, "a", 6, 6, 1);
+        add(&mut builder, "b", 6, 6, 2);
+        add(&mut builder, "c", 6, 6, 3);
+        let atlas = builder.build();
+
+        // Two 8 pixel wide cells fit on a shelf
+        assert_eq!((atlas.width, atlas.height), (16, 16));
+        assert_eq!(corner(&atlas, "a"), (1, 1));
+        assert_eq!(corner(&atlas, "b"), (9, 1));
+        assert_eq!(corner(&atlas, "c"), (1, 9));
+        for (x, y) in [(0, 0), (7, 1), (8, 1), (1, 7), (1, 8), (15, 15)] {
+            assert_eq!(pixel(&atlas, x, y), 0, "padding at ({x}, {y})");
+        }
+        assert_eq!(pixel(&atlas, 6, 6), 1);
+        assert_eq!(pixel(&atlas, 9, 1), 2);
+    }
+
+    #[test]
+    fn widens_for_images_wider_than_the_shelves() {
+        let mut builder = TextureAtlasBuilder::new(8).with_padding(1);
+        add(&mut builder, "wide", 12, 2, 1);
+        add(&mut builder, "small", 2, 2, 2);
+        let atlas = builder.build();
+
+        assert_eq!(atlas.width, 14);
+        assert_eq!(corner(&atlas, "wide"), (1, 1));
+        assert_eq!(corner(&atlas, "small"), (1, 5));
+        assert_eq!(atlas.height, 8);
+    }
+
+    #[test]
+    fn empty_atlases_have_a_pixel() {
+        let atlas = TextureAtlasBuilder::new(0).build();
+        assert_eq!((atlas.width, atlas.height), (1, 1));
+        assert_eq!(atlas.data.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match 2x2")]
+    fn rejects_mismatched_data() {
+        TextureAtlasBuilder::new(8).add_image("broken", 2, 2, vec![0; 4]);
+    }
+}
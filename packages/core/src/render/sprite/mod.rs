@@ -0,0 +1,192 @@
+pub mod atlas;
+
+use std::{ops::Range, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+
+use super::texture::Texture;
+
+/// A rectangle in normalized texture coordinates, `(0, 0)` being the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub min: glam::Vec2,
+    pub max: glam::Vec2,
+}
+
+impl Rect {
+    pub const FULL: Rect = Rect {
+        min: glam::Vec2::ZERO,
+        max: glam::Vec2::ONE,
+    };
+
+    pub fn new(min: glam::Vec2, max: glam::Vec2) -> Self {
+        Self { min, max }
+    }
+}
+
+impl Default for Rect {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// A textured quad in the XY plane.
+///
+/// Sprites are drawn in ascending `layer` order; sprites sharing a layer are
+/// grouped by texture, so their relative order is unspecified.
+#[derive(Clone)]
+pub struct Sprite {
+    pub texture: Arc<Texture>,
+    pub position: glam::Vec2,
+    pub rotation: f32,
+    pub scale: glam::Vec2,
+    pub size: glam::Vec2,
+    pub tint: glam::Vec4,
+    pub uv_rect: Rect,
+    pub layer: i32,
+}
+
+impl Sprite {
+    pub fn new(texture: Arc<Texture>) -> Self {
+        Self {
+            texture,
+            position: glam::Vec2::ZERO,
+            rotation: 0.0,
+            scale: glam::Vec2::ONE,
+            size: glam::Vec2::ONE,
+            tint: glam::Vec4::ONE,
+            uv_rect: Rect::FULL,
+            layer: 0,
+        }
+    }
+
+    pub fn with_position(mut self, position: glam::Vec2) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: glam::Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_size(mut self, size: glam::Vec2) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: glam::Vec4) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_uv_rect(mut self, uv_rect: Rect) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct SpriteInstance {
+    _position: [f32; 2],
+    _size: [f32; 2],
+    _uv_min: [f32; 2],
+    _uv_max: [f32; 2],
+    _tint: [f32; 4],
+    _rotation: f32,
+    _padding: [f32; 3],
+}
+
+impl From<&Sprite> for SpriteInstance {
+    fn from(sprite: &Sprite) -> Self {
+        Self {
+            _position: sprite.position.into(),
+            _size: (sprite.size * sprite.scale).into(),
+            _uv_min: sprite.uv_rect.min.into(),
+            _uv_max: sprite.uv_rect.max.into(),
+            _tint: sprite.tint.into(),
+            _rotation: sprite.rotation,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// A run of instances that share one texture and can be drawn with a single call.
+pub struct SpriteBatch {
+    pub texture: Arc<Texture>,
+    pub instances: Range<u32>,
+}
+
+/// Sorts `sprites` by layer and texture and splits them into [`SpriteBatch`]es.
+///
+/// The returned instances are laid out in draw order, every batch indexes into them.
+pub fn batch_sprites(sprites: &[Sprite]) -> (Vec<SpriteInstance>, Vec<SpriteBatch>) {
+    let keys = sprites
+        .iter()
+        .map(|sprite| (sprite.layer, Arc::as_ptr(&sprite.texture) as usize))
+        .collect::<Vec<_>>();
+    let (order, runs) = batch_order(&keys);
+    let instances = order
+        .iter()
+        .map(|&i| SpriteInstance::from(&sprites[i]))
+        .collect();
+    let batches = runs
+        .into_iter()
+        .map(|instances| SpriteBatch {
+            texture: sprites[order[instances.start as usize]].texture.clone(),
+            instances,
+        })
+        .collect();
+    (instances, batches)
+}
+
+/// The draw order of sprites keyed by `(layer, texture)`, and its runs of one texture
+fn batch_order(keys: &[(i32, usize)]) -> (Vec<usize>, Vec<Range<u32>>) {
+    let mut order = (0..keys.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| keys[i]);
+
+    let mut runs: Vec<Range<u32>> = Vec::new();
+    for (index, &i) in order.iter().enumerate() {
+        let index = index as u32;
+        match runs.last_mut() {
+            Some(run) if keys[order[run.start as usize]].1 == keys[i].1 => run.end = index + 1,
+            _ => runs.push(index..index + 1),
+        }
+    }
+    (order, runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_by_texture_within_a_layer() {
+        let (order, runs) = batch_order(&[(0, 1), (0, 2), (0, 1), (0, 2), (0, 3)]);
+        // Stable within a texture
+        assert_eq!(order, [0, 2, 1, 3, 4]);
+        assert_eq!(runs, [0..2, 2..4, 4..5]);
+    }
+
+    #[test]
+    fn draws_the_layers_in_order() {
+        let (order, runs) = batch_order(&[(1, 1), (0, 2), (-1, 1), (1, 1), (0, 1)]);
+        assert_eq!(order, [2, 4, 1, 0, 3]);
+        // The runs of one texture span layers
+        assert_eq!(runs, [0..2, 2..3, 3..5]);
+
+        let (order, runs) = batch_order(&[]);
+        assert!(order.is_empty() && runs.is_empty());
+    }
+}
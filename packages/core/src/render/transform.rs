@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+/// Translation, rotation and scale, applied as scale first and translation last.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub translation: glam::Vec3,
+    pub rotation: glam::Quat,
+    pub scale: glam::Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: glam::Vec3::ZERO,
+        rotation: glam::Quat::IDENTITY,
+        scale: glam::Vec3::ONE,
+    };
+
+    pub fn new(translation: glam::Vec3, rotation: glam::Quat, scale: glam::Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: glam::Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Decomposes an affine matrix without shear
+    pub fn from_matrix(matrix: glam::Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: glam::Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// See [`Transform::set_euler`]
+    pub fn with_euler(mut self, euler: glam::Vec3) -> Self {
+        self.set_euler(euler);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: glam::Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Rotation angles in radians around x, y and z, applied in that order
+    pub fn euler(&self) -> glam::Vec3 {
+        let (z, y, x) = self.rotation.to_euler(glam::EulerRot::ZYX);
+        glam::Vec3::new(x, y, z)
+    }
+
+    /// Sets the rotation from angles in radians around x, y and z, applied in that order
+    pub fn set_euler(&mut self, euler: glam::Vec3) {
+        self.rotation = glam::Quat::from_euler(glam::EulerRot::ZYX, euler.z, euler.y, euler.x);
+    }
+
+    pub fn matrix(&self) -> glam::Mat4 {
+        glam::Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    /// The local -Z axis, the direction [`Transform::look_at`] points to
+    pub fn forward(&self) -> glam::Vec3 {
+        self.rotation * glam::Vec3::NEG_Z
+    }
+
+    pub fn right(&self) -> glam::Vec3 {
+        self.rotation * glam::Vec3::X
+    }
+
+    pub fn up(&self) -> glam::Vec3 {
+        self.rotation * glam::Vec3::Y
+    }
+
+    /// Rotates so that [`Transform::forward`] points at `target`, keeping `up` as close to up as possible
+    pub fn look_at(&mut self, target: glam::Vec3, up: glam::Vec3) {
+        let forward = (target - self.translation).normalize_or_zero();
+        if forward == glam::Vec3::ZERO {
+            return;
+        }
+        let right = forward.cross(up).normalize_or_zero();
+        // `up` is parallel to `forward`, any perpendicular axis will do
+        let right = if right == glam::Vec3::ZERO {
+            forward.any_orthonormal_vector()
+        } else {
+            right
+        };
+        let up = right.cross(forward);
+        self.rotation = glam::Quat::from_mat3(&glam::Mat3::from_cols(right, up, -forward));
+    }
+
+    pub fn rotate(&mut self, rotation: glam::Quat) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    /// Rotates both the position and the orientation around `point`
+    pub fn rotate_around(&mut self, point: glam::Vec3, rotation: glam::Quat) {
+        self.translation = point + rotation * (self.translation - point);
+        self.rotate(rotation);
+    }
+
+    pub fn transform_point(&self, point: glam::Vec3) -> glam::Vec3 {
+        self.translation + self.rotation * (self.scale * point)
+    }
+
+    /// `self` applied after `other`, exact as long as the scales are uniform
+    pub fn mul_transform(&self, other: &Transform) -> Transform {
+        Transform {
+            translation: self.transform_point(other.translation),
+            rotation: self.rotation * other.rotation,
+            scale: self.scale * other.scale,
+        }
+    }
+
+    /// Interpolates towards `other`, slerping the rotation
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+impl From<Transform> for glam::Mat4 {
+    fn from(transform: Transform) -> Self {
+        transform.matrix()
+    }
+}
@@ -1,34 +1,57 @@
-use std::{cell::RefCell, sync::Arc};
+use std::sync::{Arc, Mutex};
 
 use wgpu::{DeviceDescriptor, InstanceDescriptor, RequestAdapterOptions, TextureFormat};
 use winit::{dpi::PhysicalSize, window::Window};
 
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
 pub struct WgpuContext {
     pub surface: wgpu::Surface<'static>,
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    pub config: RefCell<wgpu::SurfaceConfiguration>,
+    pub config: Mutex<wgpu::SurfaceConfiguration>,
+    pub depth_view: Mutex<wgpu::TextureView>,
 }
 
 impl WgpuContext {
     pub fn get_surface_format(&self) -> TextureFormat {
-        self.config.borrow().format
+        self.config.lock().unwrap().format
     }
     pub fn get_surface_size(&self) -> (u32, u32) {
-        let config = self.config.borrow();
+        let config = self.config.lock().unwrap();
         (config.width, config.height)
     }
     pub fn update_surface_size(&self, size: PhysicalSize<u32>) {
-        {
-            let mut config = self.config.borrow_mut();
-            config.width = size.width;
-            config.height = size.height;
-        }
-        self.surface.configure(&self.device, &self.config.borrow());
+        let mut config = self.config.lock().unwrap();
+        config.width = size.width;
+        config.height = size.height;
+        self.surface.configure(&self.device, &config);
+        *self.depth_view.lock().unwrap() = create_depth_view(&self.device, &config);
     }
 }
 
+fn create_depth_view(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
 impl WgpuContext {
     pub async fn new(window: Arc<Window>) -> Self {
         let size = window.inner_size();
@@ -60,13 +83,15 @@ impl WgpuContext {
             .unwrap();
         surface.configure(&device, &config);
         surface.configure(&device, &config);
+        let depth_view = create_depth_view(&device, &config);
 
         Self {
             surface,
             adapter,
             device,
             queue,
-            config: RefCell::new(config),
+            config: Mutex::new(config),
+            depth_view: Mutex::new(depth_view),
         }
     }
 }
@@ -1,11 +1,329 @@
-pub struct Runtime {}
-
-// impl Runtime {
-//     pub fn main_loop(
-//         &mut self,
-//         event: Event<()>,
-//         _target: &EventLoopWindowTarget<T>,
-//         control_flow: &mut ControlFlow,
-//     ) {
-//     }
-// }
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use winit::{
+    application::ApplicationHandler,
+    error::EventLoopError,
+    event::{DeviceEvent, DeviceId, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    window::{Window, WindowAttributes, WindowId},
+};
+
+use crate::{
+    input::record::{InputRecording, RecordedFrame},
+    Core,
+};
+
+/// Sent when the window is resized, with its new inner size in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Splits variable frame times into fixed simulation steps.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    /// Longer frames are clamped, so that a spike (a breakpoint, a dragged window)
+    /// doesn't turn into a burst of steps
+    max_frame_time: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(Duration::from_secs_f64(1.0 / 60.0))
+    }
+}
+
+impl FixedTimestep {
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            max_frame_time: Duration::from_millis(250),
+            max_steps: 8,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    pub fn with_max_frame_time(mut self, max_frame_time: Duration) -> Self {
+        self.max_frame_time = max_frame_time;
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Adds a frame and returns how many steps to simulate
+    pub fn advance(&mut self, frame_time: Duration) -> u32 {
+        self.accumulator += frame_time.min(self.max_frame_time);
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        if self.accumulator >= self.step {
+            // Still behind, drop the backlog instead of spiraling but keep the phase
+            self.accumulator =
+                Duration::from_nanos((self.accumulator.as_nanos() % self.step.as_nanos()) as u64);
+        }
+        steps
+    }
+
+    /// How far the leftover time is into the next step, in `0.0..1.0`, to
+    /// interpolate the last two states
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()).min(1.0) as f32
+    }
+}
+
+type Setup = Box<dyn FnOnce(&mut Core)>;
+
+/// Owns the event loop, the window and the [`Core`]: simulates at a fixed
+/// timestep and renders once per frame.
+pub struct Runtime {
+    window_attributes: WindowAttributes,
+    timestep: FixedTimestep,
+    setup: Option<Setup>,
+    window: Option<Arc<Window>>,
+    core: Option<Core>,
+    last_frame: Option<Instant>,
+    /// Where the session's inputs are saved on exit
+    recording_path: Option<PathBuf>,
+    /// The frames left to replay, live input is ignored meanwhile
+    replay: Option<std::vec::IntoIter<RecordedFrame>>,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        Self {
+            window_attributes: Window::default_attributes(),
+            timestep: FixedTimestep::default(),
+            setup: None,
+            window: None,
+            core: None,
+            last_frame: None,
+            recording_path: None,
+            replay: None,
+        }
+    }
+
+    pub fn with_window_attributes(mut self, window_attributes: WindowAttributes) -> Self {
+        self.window_attributes = window_attributes;
+        self
+    }
+
+    pub fn with_timestep(mut self, timestep: FixedTimestep) -> Self {
+        self.timestep = timestep;
+        self
+    }
+
+    /// Called once the [`Core`] is created, to add systems and spawn entities
+    pub fn with_setup(mut self, setup: impl FnOnce(&mut Core) + 'static) -> Self {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Records the inputs and frame timings, saved to `path` on exit
+    pub fn with_recording(mut self, path: impl Into<PathBuf>) -> Self {
+        self.recording_path = Some(path.into());
+        self
+    }
+
+    /// Drives the [`Core`] with `recording` instead of the user and the clock,
+    /// then exits once it is over
+    pub fn with_replay(mut self, recording: InputRecording) -> Self {
+        self.replay = Some(recording.frames.into_iter());
+        self
+    }
+
+    pub fn run(mut self) -> Result<(), EventLoopError> {
+        let event_loop = EventLoop::new()?;
+        // ControlFlow::Poll continuously runs the event loop, even if the OS hasn't
+        // dispatched any events. This is ideal for games and similar applications.
+        event_loop.set_control_flow(ControlFlow::Poll);
+        event_loop.run_app(&mut self)
+    }
+
+    fn frame(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(replay) = &mut self.replay {
+            match replay.next() {
+                Some(frame) => self.core.as_mut().unwrap().replay_frame(&frame),
+                None => {
+                    println!("[core/runtime]: replay finished, stopping");
+                    event_loop.exit();
+                }
+            }
+            return;
+        }
+
+        let now = Instant::now();
+        let frame_time = self
+            .last_frame
+            .map_or(Duration::ZERO, |last_frame| now - last_frame);
+        self.last_frame = Some(now);
+
+        let core = self.core.as_mut().unwrap();
+        // Paused or slowed down game time simulates fewer steps
+        let game_time = core.begin_frame(frame_time);
+        for _ in 0..self.timestep.advance(game_time) {
+            core.tick(self.timestep.step());
+        }
+        core.render(self.timestep.alpha());
+    }
+}
+
+impl ApplicationHandler for Runtime {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.core.is_some() {
+            return;
+        }
+        let window = event_loop
+            .create_window(self.window_attributes.clone())
+            .unwrap();
+        let window = Arc::new(window);
+
+        let mut core = Core::new(window.clone());
+        if let Some(setup) = self.setup.take() {
+            setup(&mut core);
+        }
+        if self.recording_path.is_some() {
+            core.start_recording();
+        }
+
+        self.window = Some(window);
+        self.core = Some(core);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        let Some(core) = self.core.as_mut() else {
+            return;
+        };
+        match event {
+            WindowEvent::CloseRequested => {
+                println!("[core/runtime]: close requested, stopping");
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => self.frame(event_loop),
+            WindowEvent::Resized(size) => core.handle_resize(size),
+            WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::Focused(_)
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Touch(_)
+            | WindowEvent::PinchGesture { .. }
+            | WindowEvent::RotationGesture { .. }
+            | WindowEvent::PanGesture { .. }
+            | WindowEvent::Ime(_)
+                if self.replay.is_none() =>
+            {
+                core.handle_input_event(event)
+            }
+            _ => (),
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        let (Some(core), Some(path)) = (self.core.as_mut(), &self.recording_path) else {
+            return;
+        };
+        let Some(recording) = core.stop_recording() else {
+            return;
+        };
+        match recording.save(path) {
+            Ok(()) => println!(
+                "[core/runtime]: saved {} frames of input to {path:?}",
+                recording.frames.len()
+            ),
+            Err(err) => println!("[core/runtime]: failed to save the input recording: {err}"),
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        if let (Some(core), None) = (self.core.as_mut(), &self.replay) {
+            core.handle_device_event(event);
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn splits_frames_into_steps() {
+        let mut timestep = FixedTimestep::new(ms(10));
+        assert_eq!(timestep.advance(ms(25)), 2);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(ms(4)), 0);
+        assert!((timestep.alpha() - 0.9).abs() < 1e-6);
+        assert_eq!(timestep.advance(ms(1)), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn clamps_long_frames() {
+        let mut timestep = FixedTimestep::new(ms(10));
+        // Clamped to 250ms, of which 8 steps are simulated and the rest dropped
+        assert_eq!(timestep.advance(Duration::from_secs(1)), 8);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(ms(15)), 1);
+        assert_eq!(timestep.alpha(), 0.5);
+
+        let mut timestep = FixedTimestep::new(ms(10))
+            .with_max_frame_time(ms(100))
+            .with_max_steps(100);
+        assert_eq!(timestep.advance(Duration::from_secs(1)), 10);
+    }
+
+    #[test]
+    fn dropping_the_backlog_keeps_the_phase() {
+        let mut timestep = FixedTimestep::new(ms(10)).with_max_steps(2);
+        assert_eq!(timestep.advance(ms(37)), 2);
+        assert!((timestep.alpha() - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn alpha_stays_below_one() {
+        let mut timestep = FixedTimestep::default();
+        for millis in [1, 16, 17, 33, 50, 100, 250, 1000, 3, 7] {
+            timestep.advance(ms(millis));
+            assert!((0.0..1.0).contains(&timestep.alpha()));
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// Frames kept for [`Time::frame_time_percentile`]
+const FRAME_HISTORY: usize = 240;
+/// How much the latest frame weighs in the smoothed frame time
+const FPS_SMOOTHING: f64 = 0.1;
+
+/// The clock resource.
+///
+/// Real time follows the wall clock frame by frame. Game time advances by the
+/// fixed step of each simulation tick, scaled by [`Time::time_scale`] and
+/// frozen while paused, since the runtime then simulates fewer or no ticks.
+#[derive(Debug, Clone)]
+pub struct Time {
+    real_delta: Duration,
+    real_elapsed: Duration,
+    delta: Duration,
+    elapsed: Duration,
+    fixed_delta: Duration,
+    frame_count: u64,
+    tick_count: u64,
+    paused: bool,
+    time_scale: f64,
+    smoothed_frame_time: f64,
+    frame_times: VecDeque<Duration>,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self {
+            real_delta: Duration::ZERO,
+            real_elapsed: Duration::ZERO,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            fixed_delta: Duration::from_secs_f64(1.0 / 60.0),
+            frame_count: 0,
+            tick_count: 0,
+            paused: false,
+            time_scale: 1.0,
+            smoothed_frame_time: 0.0,
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+        }
+    }
+}
+
+impl Time {
+    /// Game time of the current simulation tick, zero while the game time is frozen
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Game time since startup
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Wall clock time of the current frame, unaffected by pause and time scale
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+
+    pub fn real_delta_secs(&self) -> f32 {
+        self.real_delta.as_secs_f32()
+    }
+
+    pub fn real_elapsed(&self) -> Duration {
+        self.real_elapsed
+    }
+
+    /// The duration of a simulation tick
+    pub fn fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// `0.5` for half speed slow motion, negative scales are clamped to `0.0`
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /// Frames per second, from an exponentially smoothed frame time
+    pub fn fps(&self) -> f64 {
+        if self.smoothed_frame_time > 0.0 {
+            1.0 / self.smoothed_frame_time
+        } else {
+            0.0
+        }
+    }
+
+    /// The frame time that `percentile` (in `0.0..=1.0`) of the recent frames
+    /// didn't exceed, e.g. `0.99` for the 99th percentile
+    pub fn frame_time_percentile(&self, percentile: f64) -> Duration {
+        let mut frame_times = self.frame_times.iter().copied().collect::<Vec<_>>();
+        if frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+        frame_times.sort();
+        let rank = (percentile.clamp(0.0, 1.0) * frame_times.len() as f64).ceil() as usize;
+        frame_times[rank.saturating_sub(1)]
+    }
+
+    /// Records a frame and returns how much game time it amounts to
+    pub(crate) fn advance_frame(&mut self, real_delta: Duration) -> Duration {
+        self.real_delta = real_delta;
+        self.real_elapsed += real_delta;
+        self.frame_count += 1;
+
+        let frame_time = real_delta.as_secs_f64();
+        self.smoothed_frame_time = if self.smoothed_frame_time == 0.0 {
+            frame_time
+        } else {
+            self.smoothed_frame_time + (frame_time - self.smoothed_frame_time) * FPS_SMOOTHING
+        };
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(real_delta);
+
+        let delta = if self.paused {
+            Duration::ZERO
+        } else {
+            real_delta.mul_f64(self.time_scale)
+        };
+        if delta.is_zero() {
+            // No tick runs, don't leave the last tick's delta around
+            self.delta = Duration::ZERO;
+        }
+        delta
+    }
+
+    pub(crate) fn advance_tick(&mut self, delta: Duration) {
+        self.fixed_delta = delta;
+        self.delta = delta;
+        self.elapsed += delta;
+        self.tick_count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn pausing_freezes_the_game_time() {
+        let mut time = Time::default();
+        assert_eq!(time.advance_frame(ms(16)), ms(16));
+        time.advance_tick(ms(16));
+        assert_eq!(time.delta(), ms(16));
+
+        time.pause();
+        assert_eq!(time.advance_frame(ms(20)), Duration::ZERO);
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.elapsed(), ms(16));
+        assert_eq!(time.real_delta(), ms(20));
+        assert_eq!(time.real_elapsed(), ms(36));
+        assert_eq!(time.frame_count(), 2);
+        assert_eq!(time.tick_count(), 1);
+
+        time.resume();
+        assert_eq!(time.advance_frame(ms(16)), ms(16));
+    }
+
+    #[test]
+    fn time_scale_scales_the_game_time() {
+        let mut time = Time::default();
+        time.set_time_scale(0.5);
+        assert_eq!(time.advance_frame(ms(20)), ms(10));
+        assert_eq!(time.real_delta(), ms(20));
+
+        time.set_time_scale(-1.0);
+        assert_eq!(time.time_scale(), 0.0);
+        time.advance_tick(ms(16));
+        assert_eq!(time.advance_frame(ms(20)), Duration::ZERO);
+        assert_eq!(time.delta(), Duration::ZERO);
+    }
+
+    #[test]
+    fn fps_is_smoothed() {
+        let mut time = Time::default();
+        assert_eq!(time.fps(), 0.0);
+        time.advance_frame(ms(10));
+        assert!((time.fps() - 100.0).abs() < 1e-9);
+        // 10% of the way from 10ms to 20ms
+        time.advance_frame(ms(20));
+        assert!((time.fps() - 1.0 / 0.011).abs() < 1e-9);
+        for _ in 0..200 {
+            time.advance_frame(ms(20));
+        }
+        assert!((time.fps() - 50.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn percentiles_of_the_recent_frames() {
+        let mut time = Time::default();
+        assert_eq!(time.frame_time_percentile(0.5), Duration::ZERO);
+        // The first 10 frames fall out of the history
+        for millis in 1..=FRAME_HISTORY as u64 + 10 {
+            time.advance_frame(ms(millis));
+        }
+        assert_eq!(time.frame_time_percentile(0.0), ms(11));
+        assert_eq!(time.frame_time_percentile(0.5), ms(130));
+        assert_eq!(time.frame_time_percentile(0.99), ms(248));
+        assert_eq!(time.frame_time_percentile(1.0), ms(250));
+        assert_eq!(time.frame_time_percentile(2.0), ms(250));
+    }
+}